@@ -0,0 +1,92 @@
+use shipyard::EntityId;
+
+use ruggrogue::util::Position;
+
+/// Number of frames that a move animation takes to play out.
+const MOVE_FRAMES: u32 = 4;
+
+/// Number of frames that a lunge animation takes to play out, i.e. about 0.1 seconds.
+const LUNGE_FRAMES: u32 = 3;
+
+/// Fraction of a tile that an attacker lunges towards its target.
+const LUNGE_DISTANCE: f32 = 0.4;
+
+/// A purely cosmetic effect that plays out over a few frames after the game logic behind it has
+/// already been resolved.
+pub enum Animation {
+    /// An entity sliding from its previous position into its current one.
+    Move {
+        id: EntityId,
+        prev_pos: Position,
+        pos: Position,
+    },
+    /// An entity briefly lunging towards a target position and snapping back.
+    Lunge {
+        id: EntityId,
+        pos: Position,
+        target_pos: Position,
+    },
+}
+
+impl Animation {
+    /// The entity being animated.
+    pub fn entity(&self) -> EntityId {
+        match self {
+            Animation::Move { id, .. } | Animation::Lunge { id, .. } => *id,
+        }
+    }
+
+    /// The map position that the entity actually occupies.
+    pub fn pos(&self) -> Position {
+        match self {
+            Animation::Move { pos, .. } | Animation::Lunge { pos, .. } => *pos,
+        }
+    }
+
+    /// Number of frames that the animation takes to play out.
+    pub fn frames(&self) -> u32 {
+        match self {
+            Animation::Move { .. } => MOVE_FRAMES,
+            Animation::Lunge { .. } => LUNGE_FRAMES,
+        }
+    }
+
+    /// Offset in tiles from the entity's actual position that it should be shown at for the given
+    /// animation frame.
+    pub fn offset(&self, frame: u32) -> (f32, f32) {
+        let t = frame.min(self.frames()) as f32 / self.frames() as f32;
+
+        match self {
+            Animation::Move { prev_pos, pos, .. } => (
+                (prev_pos.x - pos.x) as f32 * (1.0 - t),
+                (prev_pos.y - pos.y) as f32 * (1.0 - t),
+            ),
+            Animation::Lunge {
+                pos, target_pos, ..
+            } => {
+                // Peak halfway through, then return to rest.
+                let reach = LUNGE_DISTANCE * (1.0 - (2.0 * t - 1.0).abs());
+
+                (
+                    (target_pos.x - pos.x).signum() as f32 * reach,
+                    (target_pos.y - pos.y).signum() as f32 * reach,
+                )
+            }
+        }
+    }
+}
+
+/// Animations queued up by game logic, waiting to be played out by the dungeon mode.
+pub struct Animations(pub Vec<Animation>);
+
+impl Animations {
+    pub fn new() -> Self {
+        Self(Vec::new())
+    }
+}
+
+impl Default for Animations {
+    fn default() -> Self {
+        Self::new()
+    }
+}
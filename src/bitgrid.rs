@@ -2,7 +2,7 @@ use bitvec::prelude::*;
 use serde::{Deserialize, Serialize};
 
 /// A width-by-height-sized BitVec for convenient handling of a grid of boolean values.
-#[derive(Deserialize, Serialize)]
+#[derive(Clone, Deserialize, Serialize)]
 pub struct BitGrid {
     width: i32,
     height: i32,
@@ -1,6 +1,12 @@
 use shipyard::{Get, UniqueView, View, World};
 
-use crate::{components::FieldOfView, gamesym::GameSym, map::Map, player::PlayerId, ui::Options};
+use crate::{
+    components::FieldOfView,
+    gamesym::GameSym,
+    map::Map,
+    player::PlayerId,
+    ui::{Options, RevealStyle, WallStyle},
+};
 use ruggrogue::{
     util::{Color, Position, Size},
     Symbol, TileGrid, Tileset,
@@ -17,12 +23,69 @@ impl Camera {
     }
 }
 
+/// Tint a tile color by the ambient light shining on it.
+fn apply_light(color: Color, light: Color) -> Color {
+    Color {
+        r: (color.r as u16 * light.r as u16 / 255) as u8,
+        g: (color.g as u16 * light.g as u16 / 255) as u8,
+        b: (color.b as u16 * light.b as u16 / 255) as u8,
+    }
+}
+
+/// Returns true if there's no reveal radius or the position is within it of the center.
+fn within_reveal_radius(reveal_radius: Option<i32>, center: (i32, i32), pos: (i32, i32)) -> bool {
+    reveal_radius.is_none_or(|r| {
+        let dx = pos.0 - center.0;
+        let dy = pos.1 - center.1;
+
+        dx * dx + dy * dy <= r * r
+    })
+}
+
+/// The color to draw a revealed tile in, depending on whether it's in view and how remembered tiles
+/// are shown, or `None` if it shouldn't be drawn at all.
+fn revealed_tile_color(
+    reveal_style: RevealStyle,
+    color: Color,
+    in_fov: bool,
+    light: Color,
+) -> Option<Color> {
+    if in_fov {
+        Some(apply_light(color, light))
+    } else {
+        match reveal_style {
+            RevealStyle::Dim => {
+                let v =
+                    ((color.r as i32 * 30 + color.g as i32 * 59 + color.b as i32 * 11) / 200) as u8;
+                Some(Color { r: v, g: v, b: v })
+            }
+            RevealStyle::Monochrome => Some(Color::GRAY),
+            RevealStyle::Hidden => None,
+        }
+    }
+}
+
 #[derive(Copy, Clone)]
 struct ScreenChunk {
     dirty: bool,
     map_chunk: Position,
 }
 
+/// Number of chunks across and down needed to cover a screen of the given pixel size with tiles of
+/// the given pixel size, wherever the camera is offset within a chunk.
+fn chunks_to_cover(screen_px: Size, tile_px: Size) -> (i32, i32) {
+    let chunk_px_w = CHUNK_TILE_WIDTH * tile_px.w as i32;
+    let chunk_px_h = CHUNK_TILE_HEIGHT * tile_px.h as i32;
+    // We need to count (chunk_px_w - 1) twice: once to allow offsetting (chunk_px_w, 0], and
+    // again to round up the chunk count to guarantee that the right edge of the screen is
+    // covered.
+    let chunks_across = (screen_px.w as i32 + 2 * (chunk_px_w - 1)) / chunk_px_w;
+    // ... and again for (chunk_px_h - 1).
+    let chunks_down = (screen_px.h as i32 + 2 * (chunk_px_h - 1)) / chunk_px_h;
+
+    (chunks_across, chunks_down)
+}
+
 /// State and functions to support selective drawing of chunks of a map onto a TileGrid.
 ///
 /// The TileGrid retains its drawn contents, parts of which are only redrawn if they are assigned
@@ -42,6 +105,11 @@ pub struct ChunkedMapGrid {
     tile_size: Size,
     screen_size: Size,
     dirty_rects: Vec<(Position, Size)>,
+    wall_style: WallStyle,
+    reveal_style: RevealStyle,
+    /// Only draw tiles within this distance of the player, to reveal their field of view bit by
+    /// bit.
+    reveal_radius: Option<i32>,
 }
 
 impl ChunkedMapGrid {
@@ -54,6 +122,9 @@ impl ChunkedMapGrid {
             tile_size: Size { w: 0, h: 0 },
             screen_size: Size { w: 0, h: 0 },
             dirty_rects: Vec::new(),
+            wall_style: WallStyle::Lines,
+            reveal_style: RevealStyle::Dim,
+            reveal_radius: None,
         }
     }
 
@@ -87,6 +158,8 @@ impl ChunkedMapGrid {
         let Options {
             tileset: map_tileset_index,
             map_zoom,
+            wall_style,
+            reveal_style,
             ..
         } = *world.borrow::<UniqueView<Options>>();
         let map_tileset = &tilesets
@@ -94,16 +167,13 @@ impl ChunkedMapGrid {
             .unwrap_or(&tilesets[0]);
         let tile_px_w = map_zoom * map_tileset.tile_width();
         let tile_px_h = map_zoom * map_tileset.tile_height();
-        let chunk_px_w = CHUNK_TILE_WIDTH * tile_px_w as i32;
-        let chunk_px_h = CHUNK_TILE_HEIGHT * tile_px_h as i32;
-        let screen_px_w = size.w as i32;
-        let screen_px_h = size.h as i32;
-        // We need to count (chunk_px_w - 1) twice: once to allow offsetting (chunk_px_w, 0], and
-        // again to round up the chunk count to guarantee that the right edge of the screen is
-        // covered.
-        let new_chunks_across = (screen_px_w + 2 * (chunk_px_w - 1)) / chunk_px_w;
-        // ... and again for (chunk_px_h - 1).
-        let new_chunks_down = (screen_px_h + 2 * (chunk_px_h - 1)) / chunk_px_h;
+        let (new_chunks_across, new_chunks_down) = chunks_to_cover(
+            size,
+            Size {
+                w: tile_px_w,
+                h: tile_px_h,
+            },
+        );
         let new_len = (new_chunks_across * new_chunks_down) as usize;
 
         // Resize and invalidate all screen chunks if dimensions change.
@@ -123,6 +193,18 @@ impl ChunkedMapGrid {
             self.chunks_down = new_chunks_down;
         }
 
+        // Redraw everything with new wall symbols if the wall style changes.
+        if wall_style != self.wall_style {
+            self.mark_all_dirty();
+            self.wall_style = wall_style;
+        }
+
+        // Likewise for changes to how remembered tiles are drawn.
+        if reveal_style != self.reveal_style {
+            self.mark_all_dirty();
+            self.reveal_style = reveal_style;
+        }
+
         self.tile_size.w = tile_px_w;
         self.tile_size.h = tile_px_h;
         self.screen_size = size;
@@ -154,6 +236,26 @@ impl ChunkedMapGrid {
         }
     }
 
+    /// Limit drawing to tiles within the given distance of the player, or lift the limit with
+    /// `None`.
+    pub fn set_reveal_radius(&mut self, reveal_radius: Option<i32>) {
+        if reveal_radius != self.reveal_radius {
+            self.reveal_radius = reveal_radius;
+            self.mark_all_dirty();
+        }
+    }
+
+    /// Returns true if a map position is close enough to the center to be drawn, as limited by
+    /// [ChunkedMapGrid::set_reveal_radius].
+    pub fn is_revealed(&self, center: (i32, i32), pos: (i32, i32)) -> bool {
+        within_reveal_radius(self.reveal_radius, center, pos)
+    }
+
+    /// Pixel width and height of a single map tile on screen.
+    pub fn tile_size(&self) -> Size {
+        self.tile_size
+    }
+
     /// Convert a map position into a grid position.
     pub fn map_to_grid_pos(&self, world: &World, map_pos: Position) -> Option<Position> {
         let top_left_chunk = self.screen_top_left_map_chunk(world);
@@ -174,6 +276,36 @@ impl ChunkedMapGrid {
         })
     }
 
+    /// Convert a pixel position in the window into the map position drawn there, if any.
+    pub fn screen_to_map_pos(
+        &self,
+        world: &World,
+        grid: &TileGrid<GameSym>,
+        screen_x: i32,
+        screen_y: i32,
+    ) -> Option<Position> {
+        let view = &grid.view;
+
+        if screen_x < view.pos.x
+            || screen_y < view.pos.y
+            || screen_x >= view.pos.x + view.size.w as i32
+            || screen_y >= view.pos.y + view.size.h as i32
+            || self.tile_size.w == 0
+            || self.tile_size.h == 0
+        {
+            return None;
+        }
+
+        let top_left_chunk = self.screen_top_left_map_chunk(world);
+
+        Some(Position {
+            x: top_left_chunk.x * CHUNK_TILE_WIDTH
+                + (screen_x - view.pos.x - view.dx).div_euclid(self.tile_size.w as i32),
+            y: top_left_chunk.y * CHUNK_TILE_HEIGHT
+                + (screen_y - view.pos.y - view.dy).div_euclid(self.tile_size.h as i32),
+        })
+    }
+
     /// Draw all screen chunks flagged dirty to their destination on the grid with their matching
     /// map chunk and clear their dirty flags.
     pub fn draw(&mut self, world: &World, grid: &mut TileGrid<GameSym>) {
@@ -268,6 +400,9 @@ impl ChunkedMapGrid {
             fovs.get(player_id.0)
         };
 
+        let reveal_radius = self.reveal_radius;
+        let reveal_style = self.reveal_style;
+
         // Draw dirty grids and unflag them.
         for screen_chunk in self.screen_chunks.iter_mut() {
             if screen_chunk.dirty {
@@ -276,17 +411,19 @@ impl ChunkedMapGrid {
                     screen_chunk.map_chunk.y * CHUNK_TILE_HEIGHT,
                     (screen_chunk.map_chunk.x + 1) * CHUNK_TILE_WIDTH - 1,
                     (screen_chunk.map_chunk.y + 1) * CHUNK_TILE_HEIGHT - 1,
+                    self.wall_style,
                 ) {
-                    if let Some((sym, color)) = tile {
-                        let color = if player_fov.get((tx, ty)) {
-                            color
-                        } else {
-                            let v =
-                                ((color.r as i32 * 30 + color.g as i32 * 59 + color.b as i32 * 11)
-                                    / 200) as u8;
-                            Color { r: v, g: v, b: v }
-                        };
+                    let in_fov = player_fov.get((tx, ty));
+                    let tile = tile
+                        .filter(|_| {
+                            within_reveal_radius(reveal_radius, player_fov.center, (tx, ty))
+                        })
+                        .and_then(|(sym, color)| {
+                            revealed_tile_color(reveal_style, color, in_fov, map.light_at(tx, ty))
+                                .map(|color| (sym, color))
+                        });
 
+                    if let Some((sym, color)) = tile {
                         grid.put_sym_color_raw(
                             (tx - top_left_tile_x, ty - top_left_tile_y),
                             sym,
@@ -308,3 +445,70 @@ impl ChunkedMapGrid {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hidden_style_skips_remembered_tiles() {
+        let wall = Color {
+            r: 200,
+            g: 100,
+            b: 50,
+        };
+
+        assert!(revealed_tile_color(RevealStyle::Hidden, wall, false, Color::WHITE).is_none());
+        assert!(revealed_tile_color(RevealStyle::Hidden, wall, true, Color::WHITE) == Some(wall));
+        assert!(
+            revealed_tile_color(RevealStyle::Monochrome, wall, false, Color::WHITE)
+                == Some(Color::GRAY)
+        );
+
+        let dim = revealed_tile_color(RevealStyle::Dim, wall, false, Color::WHITE).unwrap();
+        assert!(dim.r == dim.g && dim.g == dim.b && dim.r < wall.r);
+    }
+
+    #[test]
+    fn visible_tiles_are_tinted_by_their_light() {
+        let wall = Color {
+            r: 200,
+            g: 100,
+            b: 50,
+        };
+        let light = Color {
+            r: 255,
+            g: 128,
+            b: 0,
+        };
+        let lit = revealed_tile_color(RevealStyle::Dim, wall, true, light).unwrap();
+        let remembered = revealed_tile_color(RevealStyle::Dim, wall, false, light).unwrap();
+        let unlit = revealed_tile_color(RevealStyle::Dim, wall, false, Color::WHITE).unwrap();
+
+        assert_eq!((lit.r, lit.g, lit.b), (200, 50, 0));
+        assert!(apply_light(wall, Color::WHITE) == wall);
+        assert!(apply_light(wall, Color::BLACK) == Color::BLACK);
+        // Light only shows on tiles that are in view.
+        assert!(remembered == unlit);
+    }
+
+    #[test]
+    fn chunks_cover_the_screen_at_high_zoom() {
+        let screen = Size { w: 1056, h: 1080 };
+
+        for (zoom, expected) in [(3, (7, 7)), (4, (6, 6))] {
+            let tile_px = Size {
+                w: 8 * zoom,
+                h: 8 * zoom,
+            };
+            let (across, down) = chunks_to_cover(screen, tile_px);
+            let chunk_px_w = CHUNK_TILE_WIDTH * tile_px.w as i32;
+            let chunk_px_h = CHUNK_TILE_HEIGHT * tile_px.h as i32;
+
+            assert_eq!((across, down), expected);
+            // Enough to cover the screen even when offset by up to a whole chunk.
+            assert!(across * chunk_px_w >= screen.w as i32 + chunk_px_w - 1);
+            assert!(down * chunk_px_h >= screen.h as i32 + chunk_px_h - 1);
+        }
+    }
+}
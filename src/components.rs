@@ -1,15 +1,19 @@
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use shipyard::EntityId;
 
-use crate::{bitgrid::BitGrid, gamesym::GameSym, player::AutoRun};
+use crate::{bitgrid::BitGrid, gamesym::GameSym, player::AutoRun, vision::DistanceMetric};
 use ruggrogue::util::{Color, Position};
 
+/// Monsters only start chasing the player once they see them within this many tiles.
+#[derive(Deserialize, Serialize)]
+pub struct AggroRange(pub i32);
+
 #[derive(Deserialize, Serialize)]
 pub struct AreaOfEffect {
     pub radius: i32,
 }
 
-#[derive(Deserialize, Serialize)]
+#[derive(Clone, Deserialize, Serialize)]
 pub struct Asleep {
     pub sleepiness: i32,
     pub last_hp: i32,
@@ -18,13 +22,17 @@ pub struct Asleep {
 #[derive(Deserialize, Serialize)]
 pub struct BlocksTile;
 
+/// A unique monster guarding the Present that drops it when it dies.
+#[derive(Deserialize, Serialize)]
+pub struct Boss;
+
 #[derive(Deserialize, Serialize)]
 pub struct CombatBonus {
     pub attack: f32,
     pub defense: f32,
 }
 
-#[derive(Deserialize, Serialize)]
+#[derive(Clone, Deserialize, Serialize)]
 pub struct CombatStats {
     pub max_hp: i32,
     pub hp: i32,
@@ -32,6 +40,11 @@ pub struct CombatStats {
     pub defense: f32,
 }
 
+#[derive(Clone, Deserialize, Serialize)]
+pub struct Confusion {
+    pub turns: i32,
+}
+
 #[derive(Deserialize, Serialize)]
 pub struct Consumable;
 
@@ -44,6 +57,24 @@ impl Coord {
     }
 }
 
+/// A monster that looks like an item until it's revealed.  The true name and appearance of the
+/// monster are kept here while its Name and Renderable components show the item it's posing as.
+#[derive(Deserialize, Serialize)]
+pub struct Disguise {
+    pub name: String,
+    pub sym: GameSym,
+    pub fg: Color,
+}
+
+#[derive(Clone, Copy, Default, Deserialize, PartialEq, Eq, Serialize)]
+pub enum DamageType {
+    #[default]
+    Physical,
+    Fire,
+    Cold,
+    Poison,
+}
+
 #[derive(Deserialize, Serialize)]
 pub enum EquipSlot {
     Weapon,
@@ -64,6 +95,13 @@ pub struct Experience {
     pub base: u64,
 }
 
+/// Makes a monster flee from the player instead of fighting when it's badly hurt.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct Fearful {
+    /// True while the monster is fleeing, so that it only announces it once.
+    pub fleeing: bool,
+}
+
 #[derive(Deserialize, Serialize)]
 pub struct FieldOfView {
     pub tiles: BitGrid,
@@ -106,6 +144,7 @@ impl FieldOfView {
         self.tiles.get_bit(offset_pos.0, offset_pos.1)
     }
 
+    /// Iterate over visible positions in row-major order, top to bottom and left to right.
     pub fn iter(&self) -> impl Iterator<Item = (i32, i32)> + '_ {
         let ys = (self.center.1 - self.range)..=(self.center.1 + self.range);
 
@@ -127,14 +166,24 @@ impl FieldOfView {
 #[derive(Deserialize, Serialize)]
 pub struct GivesExperience(pub u64);
 
+#[derive(Clone, Copy)]
 pub enum HurtBy {
     Someone(EntityId),
+    Poison,
     Starvation,
 }
 
+#[derive(Deserialize, Serialize)]
+pub struct InflictsConfusion {
+    pub turns: i32,
+}
+
 #[derive(Deserialize, Serialize)]
 pub struct InflictsDamage {
     pub damage: i32,
+    /// Older saves lack this, and all damage was physical then.
+    #[serde(default)]
+    pub damage_type: DamageType,
 }
 
 #[derive(Deserialize, Serialize)]
@@ -150,8 +199,39 @@ pub struct Inventory {
 #[derive(Deserialize, Serialize)]
 pub struct Item;
 
+/// Heals the attacker (or whoever wields this) by a fraction of melee damage dealt.
 #[derive(Deserialize, Serialize)]
-pub struct Monster;
+pub struct Lifesteal {
+    pub fraction: f32,
+}
+
+#[derive(Clone, Default, Serialize)]
+pub struct Monster {
+    /// Where the monster last saw the player, if it's still pursuing them.
+    pub last_seen_player: Option<(i32, i32)>,
+    /// Turns left to pursue the last seen player position before giving up.
+    pub pursuit_turns: i32,
+}
+
+/// Monster used to be a unit struct that was saved as `null`, so older saves are loaded as a
+/// monster that isn't pursuing anyone.
+impl<'de> Deserialize<'de> for Monster {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Default, Deserialize)]
+        #[serde(default)]
+        struct SavedMonster {
+            last_seen_player: Option<(i32, i32)>,
+            pursuit_turns: i32,
+        }
+
+        let saved = Option::<SavedMonster>::deserialize(deserializer)?.unwrap_or_default();
+
+        Ok(Self {
+            last_seen_player: saved.last_seen_player,
+            pursuit_turns: saved.pursuit_turns,
+        })
+    }
+}
 
 #[derive(Deserialize, Serialize)]
 pub struct Name(pub String);
@@ -163,8 +243,26 @@ pub struct Nutrition(pub i32);
 pub struct Player {
     #[serde(skip)]
     pub auto_run: Option<AutoRun>,
+    /// True if the player has been warned about their low hit points and they haven't recovered
+    /// since.
+    #[serde(default)]
+    pub low_hp_warned: bool,
+    /// The monster whose melee damage range was last shown to the player before attacking it.
+    #[serde(skip)]
+    pub damage_previewed: Option<EntityId>,
 }
 
+/// Damage dealt to the entity at the end of each turn until the poison wears off.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct Poison {
+    pub damage: i32,
+    pub turns: i32,
+}
+
+/// The entity's name is a proper noun, so it's never given an article or lowercased in messages.
+#[derive(Deserialize, Serialize)]
+pub struct ProperNoun;
+
 #[derive(Deserialize, Serialize)]
 pub struct ProvidesHealing {
     pub heal_amount: i32,
@@ -173,6 +271,10 @@ pub struct ProvidesHealing {
 #[derive(Deserialize, Serialize)]
 pub struct Ranged {
     pub range: i32,
+    /// How distance is measured for the range and any area of effect of the item.  Older saves
+    /// lack this, and all items were aimed with [DistanceMetric::Euclidean] then.
+    #[serde(default)]
+    pub metric: DistanceMetric,
 }
 
 #[derive(Deserialize, Serialize)]
@@ -188,19 +290,71 @@ pub struct Renderable {
     pub bg: Color,
 }
 
+/// Damage types that deal half damage to an entity.
 #[derive(Deserialize, Serialize)]
+pub struct Resistances(pub Vec<DamageType>);
+
+#[derive(Clone, Deserialize, Serialize)]
 pub struct Stomach {
     pub fullness: i32,
     pub max_fullness: i32,
     pub sub_hp: i32,
 }
 
-#[derive(Deserialize, Serialize)]
+#[derive(Clone, Deserialize, Serialize)]
 pub struct Tally {
     pub damage_dealt: u64,
     pub damage_taken: u64,
     pub kills: u64,
 }
 
+/// Damage dealt by this item when it's thrown at something, instead of the usual small amount.
+#[derive(Deserialize, Serialize)]
+pub struct ThrowDamage(pub i32);
+
+/// Deals damage back to anything that hits this in melee, or whoever wears this.
+#[derive(Deserialize, Serialize)]
+pub struct Thorns {
+    pub amount: i32,
+}
+
 #[derive(Deserialize, Serialize)]
 pub struct Victory;
+
+/// Damage types that deal double damage to an entity.
+#[derive(Deserialize, Serialize)]
+pub struct Vulnerabilities(pub Vec<DamageType>);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn monster_loads_from_older_saves() {
+        let monster: Monster = serde_json::from_str("null").unwrap();
+
+        assert!(monster.last_seen_player.is_none());
+        assert_eq!(monster.pursuit_turns, 0);
+    }
+
+    #[test]
+    fn inflicts_damage_loads_from_older_saves() {
+        let inflicts: InflictsDamage = serde_json::from_str(r#"{"damage":8}"#).unwrap();
+
+        assert_eq!(inflicts.damage, 8);
+        assert!(inflicts.damage_type == DamageType::Physical);
+    }
+
+    #[test]
+    fn monster_round_trips() {
+        let monster = Monster {
+            last_seen_player: Some((3, 4)),
+            pursuit_turns: 5,
+        };
+        let loaded: Monster =
+            serde_json::from_str(&serde_json::to_string(&monster).unwrap()).unwrap();
+
+        assert_eq!(loaded.last_seen_player, Some((3, 4)));
+        assert_eq!(loaded.pursuit_turns, 5);
+    }
+}
@@ -0,0 +1,137 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    error,
+    fs::File,
+    io::{BufReader, BufWriter, Write},
+};
+
+use crate::{gamekey::GameKey, ui::Options};
+
+#[cfg(target_os = "emscripten")]
+const CONFIG_FILENAME: &str = "/ruggrogue/config.txt";
+
+#[cfg(not(target_os = "emscripten"))]
+const CONFIG_FILENAME: &str = "config.txt";
+
+type BoxedError = Box<dyn error::Error>;
+
+/// Settings that persist across game sessions, independent of any save file.
+#[derive(Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Config {
+    /// Width and height of the window when the game last exited.
+    pub window_size: Option<(u32, u32)>,
+    /// Names of the keys bound to each game key.
+    pub key_bindings: HashMap<GameKey, Vec<String>>,
+    /// Options chosen in the options menu.
+    pub options: Options,
+}
+
+fn try_load_config() -> Result<Config, BoxedError> {
+    let reader = BufReader::new(File::open(CONFIG_FILENAME)?);
+
+    Ok(serde_json::from_reader(reader)?)
+}
+
+/// Load the config file, falling back to defaults if it's missing or unreadable.
+pub fn load_config() -> Config {
+    try_load_config().unwrap_or_default()
+}
+
+/// Save the config file.
+pub fn save_config(config: &Config) -> Result<(), BoxedError> {
+    let mut writer = BufWriter::new(File::create(CONFIG_FILENAME)?);
+
+    serde_json::to_writer(&mut writer, config)?;
+    writer.write_all(b"\n")?;
+    writer.flush()?;
+
+    Ok(())
+}
+
+/// Save the given options to the config file, keeping the rest of its settings as they are.
+pub fn save_options(options: &Options) {
+    let mut config = load_config();
+
+    config.options = options.clone();
+    if let Err(e) = save_config(&config) {
+        eprintln!("Warning: config::save_config: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui::{
+        MapGenerator, WallStyle, MAX_MONSTERS_LIMIT, MAX_MONSTERS_STEP, MAX_MONSTER_ACTION_DELAY,
+        MAX_ZOOM, NUM_FONTS,
+    };
+
+    #[test]
+    fn options_survive_a_round_trip() {
+        let mut config = Config::default();
+
+        config.options.tileset = 1;
+        config.options.font = 1;
+        config.options.map_zoom = 3;
+        config.options.text_zoom = 2;
+        config.options.wall_style = WallStyle::Hash;
+        config.options.map_generator = MapGenerator::Caves;
+
+        let saved = serde_json::to_string(&config).unwrap();
+
+        config.options.tileset = 0;
+        config.options.map_zoom = 1;
+
+        let loaded: Config = serde_json::from_str(&saved).unwrap();
+
+        assert_eq!(loaded.options.tileset, 1);
+        assert_eq!(loaded.options.font, 1);
+        assert_eq!(loaded.options.map_zoom, 3);
+        assert_eq!(loaded.options.text_zoom, 2);
+        assert!(loaded.options.wall_style == WallStyle::Hash);
+        assert!(loaded.options.map_generator == MapGenerator::Caves);
+    }
+
+    #[test]
+    fn hand_edited_options_are_clamped() {
+        let mut config: Config =
+            serde_json::from_str(r#"{"options":{"font":9,"map_zoom":0,"text_zoom":99}}"#).unwrap();
+
+        config.options.clamp_to_limits();
+
+        assert_eq!(config.options.font, NUM_FONTS - 1);
+        assert_eq!(config.options.map_zoom, 1);
+        assert_eq!(config.options.text_zoom, MAX_ZOOM);
+        assert_eq!(config.options.tileset, Options::default().tileset);
+
+        let mut config: Config = serde_json::from_str(
+            r#"{"options":{"rest_until_percent":250,"monster_action_delay":40,"max_monsters_per_level":3}}"#,
+        )
+        .unwrap();
+
+        config.options.clamp_to_limits();
+
+        assert_eq!(config.options.rest_until_percent, 100);
+        assert_eq!(
+            config.options.monster_action_delay,
+            MAX_MONSTER_ACTION_DELAY
+        );
+        assert_eq!(config.options.max_monsters_per_level, MAX_MONSTERS_STEP);
+
+        let mut config: Config = serde_json::from_str(
+            r#"{"options":{"rest_until_percent":75,"max_monsters_per_level":999}}"#,
+        )
+        .unwrap();
+
+        config.options.clamp_to_limits();
+
+        assert_eq!(config.options.rest_until_percent, 70);
+        assert_eq!(config.options.max_monsters_per_level, MAX_MONSTERS_LIMIT);
+        assert_eq!(
+            config.options.monster_action_delay,
+            Options::default().monster_action_delay
+        );
+    }
+}
@@ -8,30 +8,297 @@ use std::hash::Hasher;
 use wyhash::WyHash;
 
 use crate::{
+    animation::{Animation, Animations},
     components::{
-        Asleep, BlocksTile, CombatBonus, CombatStats, Coord, Equipment, Experience,
-        GivesExperience, HurtBy, Name, Tally,
+        Asleep, BlocksTile, Boss, CombatBonus, CombatStats, Coord, DamageType, Equipment,
+        Experience, GivesExperience, HurtBy, Lifesteal, Name, Player, ProperNoun, Resistances,
+        Tally, Thorns, Vulnerabilities,
     },
     magicnum,
     map::Map,
-    message::Messages,
+    message::{self, Messages},
+    monster,
     player::{PlayerAlive, PlayerId},
-    saveload, spawn, GameSeed, TurnCount,
+    saveload, spawn,
+    ui::Options,
+    GameSeed, TurnCount,
 };
+use ruggrogue::util::Color;
+
+/// A single instance of damage to be dealt to a target.
+pub struct Damage {
+    pub target: EntityId,
+    pub amount: i32,
+    pub source: HurtBy,
+    /// Fraction of the damage dealt that heals the source when the damage is applied.
+    pub lifesteal: f32,
+}
+
+/// A message about something that happened to a target, held back until queued damage is applied
+/// so that it can be shown alongside any other messages about the same target.
+struct DamageMessage {
+    target: EntityId,
+    text: String,
+    color: Option<Color>,
+}
+
+/// Damage collected over the course of a turn phase, to be applied in one deterministic pass by
+/// [apply_damage_queue], along with the messages that describe it.
+pub struct DamageQueue {
+    damage: Vec<Damage>,
+    messages: Vec<DamageMessage>,
+}
+
+impl DamageQueue {
+    pub fn new() -> Self {
+        Self {
+            damage: Vec::new(),
+            messages: Vec::new(),
+        }
+    }
+
+    /// Queue damage to be dealt to a target.
+    pub fn push(&mut self, target: EntityId, amount: i32, source: HurtBy) {
+        self.push_with_lifesteal(target, amount, source, 0.0);
+    }
+
+    /// Queue damage to be dealt to a target that heals its source by a fraction of the damage
+    /// actually dealt once it's applied.
+    pub fn push_with_lifesteal(
+        &mut self,
+        target: EntityId,
+        amount: i32,
+        source: HurtBy,
+        lifesteal: f32,
+    ) {
+        self.damage.push(Damage {
+            target,
+            amount,
+            source,
+            lifesteal,
+        });
+    }
+
+    /// Queue a message about a target to be shown when queued damage is applied, grouped with the
+    /// other messages about that target.
+    pub fn push_message(&mut self, target: EntityId, text: String, color: Option<Color>) {
+        self.messages.push(DamageMessage {
+            target,
+            text,
+            color,
+        });
+    }
+
+    /// Total damage queued for a target that hasn't been applied yet.
+    pub fn pending_for(&self, target: EntityId) -> i32 {
+        self.damage
+            .iter()
+            .filter(|d| d.target == target)
+            .map(|d| d.amount)
+            .sum()
+    }
+}
+
+impl Default for DamageQueue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Apply all queued damage in the order it was queued, marking who hurt whom, updating tallies and
+/// healing sources with lifesteal, then show the queued messages grouped by target, in the order
+/// that targets were first mentioned.
+#[allow(clippy::too_many_arguments)]
+pub fn apply_damage_queue(
+    mut damage_queue: UniqueViewMut<DamageQueue>,
+    mut msgs: UniqueViewMut<Messages>,
+    entities: EntitiesView,
+    mut combat_stats: ViewMut<CombatStats>,
+    mut hurt_bys: ViewMut<HurtBy>,
+    names: View<Name>,
+    players: View<Player>,
+    proper_nouns: View<ProperNoun>,
+    mut tallies: ViewMut<Tally>,
+) {
+    for damage in std::mem::take(&mut damage_queue.damage) {
+        let dealt = if let Ok(stats) = (&mut combat_stats).try_get(damage.target) {
+            // Damage beyond what kills the target isn't dealt for the purposes of lifesteal.
+            let dealt = damage.amount.clamp(0, stats.hp.max(0));
+
+            stats.hp -= damage.amount;
+            entities.add_component(&mut hurt_bys, damage.source, damage.target);
+
+            if let HurtBy::Someone(source) = damage.source {
+                if let Ok(source_tally) = (&mut tallies).try_get(source) {
+                    source_tally.damage_dealt += damage.amount.max(0) as u64;
+                }
+            }
+            if let Ok(target_tally) = (&mut tallies).try_get(damage.target) {
+                target_tally.damage_taken += damage.amount.max(0) as u64;
+            }
+
+            dealt
+        } else {
+            continue;
+        };
+
+        // Heal the source by a fraction of the damage dealt for any lifesteal they have.
+        if let HurtBy::Someone(source) = damage.source {
+            let heal_amount = (dealt as f32 * damage.lifesteal).round() as i32;
+
+            if let Ok(source_stats) = (&mut combat_stats).try_get(source) {
+                if heal_amount > 0 && source_stats.hp > 0 && source_stats.hp < source_stats.max_hp {
+                    source_stats.hp = (source_stats.hp + heal_amount).min(source_stats.max_hp);
+
+                    let drain_msg = format!(
+                        "{} drains life from {}.",
+                        message::capitalize(&message::the_name_of(&names, &proper_nouns, source)),
+                        message::the_name_of(&names, &proper_nouns, damage.target)
+                    );
+                    let drain_color = if players.contains(damage.target) {
+                        message::WARNING_COLOR
+                    } else {
+                        Color::GREEN
+                    };
+                    damage_queue.push_message(damage.target, drain_msg, Some(drain_color));
+                }
+            }
+        }
+    }
+
+    let mut targets: Vec<EntityId> = Vec::new();
+    for message in &damage_queue.messages {
+        if !targets.contains(&message.target) {
+            targets.push(message.target);
+        }
+    }
+    damage_queue
+        .messages
+        .sort_by_key(|m| targets.iter().position(|&t| t == m.target));
+
+    for message in damage_queue.messages.drain(..) {
+        match message.color {
+            Some(color) => msgs.add_colored(message.text, color),
+            None => msgs.add(message.text),
+        }
+    }
+}
+
+/// Scale damage of the given type according to the resistances and vulnerabilities of its target.
+pub fn scale_damage_by_type(
+    amount: i32,
+    damage_type: DamageType,
+    target: EntityId,
+    resistances: &View<Resistances>,
+    vulnerabilities: &View<Vulnerabilities>,
+) -> i32 {
+    let mut amount = amount;
+
+    if resistances
+        .try_get(target)
+        .is_ok_and(|r| r.0.contains(&damage_type))
+    {
+        amount /= 2;
+    }
+
+    if vulnerabilities
+        .try_get(target)
+        .is_ok_and(|v| v.0.contains(&damage_type))
+    {
+        amount *= 2;
+    }
+
+    amount
+}
+
+/// Sum of a combat bonus over everything that an entity has equipped.
+fn equipment_bonus(
+    equipments: &View<Equipment>,
+    combat_bonuses: &View<CombatBonus>,
+    id: EntityId,
+    bonus: impl Fn(&CombatBonus) -> f32,
+) -> f32 {
+    equipments.try_get(id).map_or(0.0, |equip| {
+        equip
+            .weapon
+            .iter()
+            .chain(equip.armor.iter())
+            .filter_map(|&e| combat_bonuses.try_get(e).ok())
+            .map(&bonus)
+            .sum()
+    })
+}
+
+/// Melee damage before any random fluctuation.
+fn calc_melee_damage(attack_value: f32, defense_value: f32) -> f32 {
+    // Attack is twice defense most of the time.
+    if attack_value >= defense_value * 2.0 {
+        attack_value - defense_value
+    } else {
+        attack_value * (0.25 + (0.125 * attack_value / defense_value.max(1.0)).min(0.25))
+    }
+}
+
+/// The lowest and highest damage that a melee attack that hits could deal.
+pub fn melee_damage_range(world: &World, attacker: EntityId, defender: EntityId) -> (i32, i32) {
+    let (combat_bonuses, combat_stats, equipments, resistances, vulnerabilities) = world.borrow::<(
+        View<CombatBonus>,
+        View<CombatStats>,
+        View<Equipment>,
+        View<Resistances>,
+        View<Vulnerabilities>,
+    )>();
+    let attack_value = combat_stats.get(attacker).attack
+        + equipment_bonus(&equipments, &combat_bonuses, attacker, |b| b.attack);
+    let defense_value = combat_stats.get(defender).defense
+        + equipment_bonus(&equipments, &combat_bonuses, defender, |b| b.defense);
+    let damage = calc_melee_damage(attack_value, defense_value);
+    let scale = |d: f32| {
+        scale_damage_by_type(
+            d as i32,
+            DamageType::Physical,
+            defender,
+            &resistances,
+            &vulnerabilities,
+        )
+    };
+
+    // Match the fluctuation and random rounding of melee_attack.
+    (scale((damage * 0.5).trunc()), scale((damage * 1.5).ceil()))
+}
 
 pub fn melee_attack(world: &World, attacker: EntityId, defender: EntityId) {
-    let mut msgs = world.borrow::<UniqueViewMut<Messages>>();
-    let entities = world.borrow::<EntitiesView>();
+    // Attacking a disguised monster gives it away before anything is said about it.
+    monster::reveal_disguise(world, defender);
+
+    let mut damage_queue = world.borrow::<UniqueViewMut<DamageQueue>>();
     let asleeps = world.borrow::<View<Asleep>>();
     let combat_bonuses = world.borrow::<View<CombatBonus>>();
-    let mut combat_stats = world.borrow::<ViewMut<CombatStats>>();
+    let combat_stats = world.borrow::<View<CombatStats>>();
     let equipments = world.borrow::<View<Equipment>>();
-    let mut hurt_bys = world.borrow::<ViewMut<HurtBy>>();
     let names = world.borrow::<View<Name>>();
-    let att_name = &names.get(attacker).0;
-    let def_name = &names.get(defender).0;
+    let players = world.borrow::<View<Player>>();
+    let proper_nouns = world.borrow::<View<ProperNoun>>();
+    let att_name = message::the_name_of(&names, &proper_nouns, attacker);
+    let def_name = message::the_name_of(&names, &proper_nouns, defender);
+    let coords = world.borrow::<View<Coord>>();
+
+    if world.borrow::<UniqueView<Options>>().lunge_animations {
+        if let (Ok(attacker_coord), Ok(defender_coord)) =
+            (coords.try_get(attacker), coords.try_get(defender))
+        {
+            world
+                .borrow::<UniqueViewMut<Animations>>()
+                .0
+                .push(Animation::Lunge {
+                    id: attacker,
+                    pos: attacker_coord.0,
+                    target_pos: defender_coord.0,
+                });
+        }
+    }
+
     let mut rng = {
-        let coords = world.borrow::<View<Coord>>();
         let mut hasher = WyHash::with_seed(magicnum::MELEE_ATTACK);
         hasher.write_u64(world.borrow::<UniqueView<GameSeed>>().0);
         hasher.write_u64(world.borrow::<UniqueView<TurnCount>>().0);
@@ -47,36 +314,19 @@ pub fn melee_attack(world: &World, attacker: EntityId, defender: EntityId) {
     };
 
     if !asleeps.contains(defender) && rng.gen_ratio(1, 10) {
-        msgs.add(format!("{} misses {}.", att_name, def_name));
+        damage_queue.push_message(
+            defender,
+            format!("{} misses {}.", message::capitalize(&att_name), def_name),
+            None,
+        );
         return;
     }
 
     let attack_value = combat_stats.get(attacker).attack
-        + equipments.try_get(attacker).map_or(0.0, |equip| {
-            equip
-                .weapon
-                .iter()
-                .chain(equip.armor.iter())
-                .filter_map(|&e| combat_bonuses.try_get(e).ok())
-                .map(|b| b.attack)
-                .sum()
-        });
+        + equipment_bonus(&equipments, &combat_bonuses, attacker, |b| b.attack);
     let defense_value = combat_stats.get(defender).defense
-        + equipments.try_get(defender).map_or(0.0, |equip| {
-            equip
-                .weapon
-                .iter()
-                .chain(equip.armor.iter())
-                .filter_map(|&e| combat_bonuses.try_get(e).ok())
-                .map(|b| b.defense)
-                .sum()
-        });
-    // Attack is twice defense most of the time.
-    let mut damage = if attack_value >= defense_value * 2.0 {
-        attack_value - defense_value
-    } else {
-        attack_value * (0.25 + (0.125 * attack_value / defense_value.max(1.0)).min(0.25))
-    };
+        + equipment_bonus(&equipments, &combat_bonuses, defender, |b| b.defense);
+    let mut damage = calc_melee_damage(attack_value, defense_value);
 
     // Fluctuate damage by a random amount.
     let mut suffix = '!';
@@ -97,32 +347,132 @@ pub fn melee_attack(world: &World, attacker: EntityId, defender: EntityId) {
         } else {
             0
         };
+    let damage = scale_damage_by_type(
+        damage,
+        DamageType::Physical,
+        defender,
+        &world.borrow::<View<Resistances>>(),
+        &world.borrow::<View<Vulnerabilities>>(),
+    );
 
     if damage > 0 {
-        let mut tallies = world.borrow::<ViewMut<Tally>>();
+        // Any lifesteal heals the attacker once the damage is actually dealt.
+        let lifesteals = world.borrow::<View<Lifesteal>>();
+        let lifesteal_fraction = lifesteals.try_get(attacker).map_or(0.0, |l| l.fraction)
+            + equipments.try_get(attacker).map_or(0.0, |equip| {
+                equip
+                    .weapon
+                    .iter()
+                    .chain(equip.armor.iter())
+                    .filter_map(|&e| lifesteals.try_get(e).ok())
+                    .map(|l| l.fraction)
+                    .sum()
+            });
 
-        (&mut combat_stats).get(defender).hp -= damage;
-        entities.add_component(&mut hurt_bys, HurtBy::Someone(attacker), defender);
-        if let Ok(att_tally) = (&mut tallies).try_get(attacker) {
-            att_tally.damage_dealt += damage.max(0) as u64;
-        }
-        if let Ok(def_tally) = (&mut tallies).try_get(defender) {
-            def_tally.damage_taken += damage.max(0) as u64;
-        }
-        msgs.add(format!(
+        damage_queue.push_with_lifesteal(
+            defender,
+            damage,
+            HurtBy::Someone(attacker),
+            lifesteal_fraction,
+        );
+
+        let hit_msg = format!(
             "{} hits {} for {} hp{}",
-            att_name, def_name, damage, suffix
-        ));
+            message::capitalize(&att_name),
+            def_name,
+            damage,
+            suffix
+        );
+        damage_queue.push_message(
+            defender,
+            hit_msg,
+            players.contains(defender).then_some(message::WARNING_COLOR),
+        );
     } else {
-        msgs.add(format!(
-            "{} hits {}, but does no damage.",
-            att_name, def_name
-        ));
+        damage_queue.push_message(
+            defender,
+            format!(
+                "{} hits {}, but does no damage.",
+                message::capitalize(&att_name),
+                def_name
+            ),
+            None,
+        );
+    }
+
+    // Reflect damage back at the attacker for any thorns the defender has.
+    let thorns = world.borrow::<View<Thorns>>();
+    let thorns_amount = thorns.try_get(defender).map_or(0, |t| t.amount)
+        + equipments.try_get(defender).map_or(0, |equip| {
+            equip
+                .weapon
+                .iter()
+                .chain(equip.armor.iter())
+                .filter_map(|&e| thorns.try_get(e).ok())
+                .map(|t| t.amount)
+                .sum()
+        });
+
+    if thorns_amount > 0 {
+        damage_queue.push(attacker, thorns_amount, HurtBy::Someone(defender));
+
+        let pricked_msg = format!(
+            "{} is pricked by {} for {} hp.",
+            message::capitalize(&att_name),
+            def_name,
+            thorns_amount
+        );
+        damage_queue.push_message(
+            attacker,
+            pricked_msg,
+            players.contains(attacker).then_some(message::WARNING_COLOR),
+        );
     }
 }
 
-/// Check for dead entities, do any special handling for them and delete them.
+/// Announce the death of an entity and credit its killer with the kill and any experience.
+fn announce_and_credit_death(all_storages: &mut AllStoragesViewMut, entity: EntityId) {
+    all_storages.run(
+        |mut msgs: UniqueViewMut<Messages>, names: View<Name>, proper_nouns: View<ProperNoun>| {
+            msgs.add(format!(
+                "{} dies!",
+                message::capitalize(&message::the_name_of(&names, &proper_nouns, entity))
+            ));
+        },
+    );
+
+    all_storages.run(
+        |mut exps: ViewMut<Experience>,
+         gives_exps: View<GivesExperience>,
+         hurt_bys: View<HurtBy>,
+         mut tallies: ViewMut<Tally>| {
+            if let Ok(&HurtBy::Someone(receiver)) = hurt_bys.try_get(entity) {
+                // Credit kill to whoever last hurt this entity.
+                if let Ok(receiver_tally) = (&mut tallies).try_get(receiver) {
+                    receiver_tally.kills += 1;
+                }
+
+                // Give experience to whoever last hurt this entity.
+                if let Ok(receiver_exp) = (&mut exps).try_get(receiver) {
+                    if let Ok(gives_exp) = gives_exps.try_get(entity) {
+                        receiver_exp.exp += gives_exp.0;
+                    }
+                }
+            }
+        },
+    );
+}
+
+/// Apply queued damage, then check for dead entities, do any special handling for them and delete
+/// them.
+///
+/// Dead non-player entities are all dealt with before the player, so that everything that dies
+/// alongside the player (e.g. in the same area of effect) is credited and removed exactly once.
 pub fn handle_dead_entities(mut all_storages: AllStoragesViewMut) {
+    all_storages.run(apply_damage_queue);
+
+    let player_id = all_storages.borrow::<UniqueView<PlayerId>>().0;
+
     loop {
         let mut entities = [EntityId::dead(); 10];
         let mut num_entities = 0;
@@ -133,7 +483,7 @@ pub fn handle_dead_entities(mut all_storages: AllStoragesViewMut) {
                 .iter()
                 .with_id()
                 .into_iter()
-                .filter(|(_, stats)| stats.hp <= 0)
+                .filter(|(id, stats)| *id != player_id && stats.hp <= 0)
                 .zip(entities.iter_mut())
             {
                 *entity = id;
@@ -142,72 +492,325 @@ pub fn handle_dead_entities(mut all_storages: AllStoragesViewMut) {
         });
 
         for &entity in entities.iter().take(num_entities) {
-            all_storages.run(|mut msgs: UniqueViewMut<Messages>, names: View<Name>| {
-                msgs.add(format!("{} dies!", &names.get(entity).0));
-            });
+            announce_and_credit_death(&mut all_storages, entity);
 
+            // The boss drops the Present that it was guarding.
+            if all_storages.borrow::<View<Boss>>().contains(entity) {
+                let pos = all_storages.borrow::<View<Coord>>().get(entity).0.into();
+
+                spawn::spawn_present(&all_storages, pos);
+                all_storages
+                    .borrow::<UniqueViewMut<Messages>>()
+                    .add_colored("The Present falls to the floor!".into(), Color::YELLOW);
+            }
+
+            // Remove dead entity from the map.
             all_storages.run(
-                |mut exps: ViewMut<Experience>,
-                 gives_exps: View<GivesExperience>,
-                 hurt_bys: View<HurtBy>,
-                 mut tallies: ViewMut<Tally>| {
-                    if let Ok(&HurtBy::Someone(receiver)) = hurt_bys.try_get(entity) {
-                        // Credit kill to whoever last hurt this entity.
-                        if let Ok(receiver_tally) = (&mut tallies).try_get(receiver) {
-                            receiver_tally.kills += 1;
-                        }
-
-                        // Give experience to whoever last hurt this entity.
-                        if let Ok(receiver_exp) = (&mut exps).try_get(receiver) {
-                            if let Ok(gives_exp) = gives_exps.try_get(entity) {
-                                receiver_exp.exp += gives_exp.0;
-                            }
-                        }
-                    }
+                |mut map: UniqueViewMut<Map>,
+                 blocks_tile: View<BlocksTile>,
+                 coords: View<Coord>| {
+                    map.remove_entity(
+                        entity,
+                        coords.get(entity).0.into(),
+                        blocks_tile.contains(entity),
+                    );
                 },
             );
 
-            if entity == all_storages.borrow::<UniqueView<PlayerId>>().0 {
-                // The player has died.
-                all_storages.run(
-                    |mut msgs: UniqueViewMut<Messages>,
-                     mut player_alive: UniqueViewMut<PlayerAlive>| {
-                        msgs.add("Press SPACE to continue...".into());
-                        player_alive.0 = false;
-                    },
-                );
-
-                saveload::delete_save_file();
-
-                // Don't handle any more dead entities.
-                num_entities = 0;
-                break;
-            } else {
-                // Remove dead entity from the map.
-                all_storages.run(
-                    |mut map: UniqueViewMut<Map>,
-                     blocks_tile: View<BlocksTile>,
-                     coords: View<Coord>| {
-                        map.remove_entity(
-                            entity,
-                            coords.get(entity).0.into(),
-                            blocks_tile.contains(entity),
-                        );
-                    },
-                );
-
-                // Delete the dead entity.
-                spawn::despawn_entity(&mut all_storages, entity);
-            }
+            // Delete the dead entity.
+            spawn::despawn_entity(&mut all_storages, entity);
         }
 
         if num_entities == 0 {
             break;
         }
     }
+
+    let player_died = all_storages.run(
+        |player_alive: UniqueView<PlayerAlive>, combat_stats: View<CombatStats>| {
+            player_alive.0
+                && combat_stats
+                    .try_get(player_id)
+                    .is_ok_and(|stats| stats.hp <= 0)
+        },
+    );
+
+    if player_died {
+        announce_and_credit_death(&mut all_storages, player_id);
+
+        all_storages.run(
+            |mut msgs: UniqueViewMut<Messages>, mut player_alive: UniqueViewMut<PlayerAlive>| {
+                msgs.add("Press SPACE to continue...".into());
+                player_alive.0 = false;
+            },
+        );
+
+        saveload::delete_save_file();
+    }
 }
 
 /// Clear all HurtBy components off of all entities.
 pub fn clear_hurt_bys(mut hurt_bys: ViewMut<HurtBy>) {
     hurt_bys.clear();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{components::Monster, gamesym::GameSym};
+
+    /// A world with the player at (5, 5) and three goblins beside them, returning the goblins' IDs.
+    fn three_goblins() -> (World, Vec<EntityId>) {
+        let world = spawn::test_world(20, 20, (5, 5));
+        let goblins = [(6, 5), (5, 6), (4, 5)]
+            .iter()
+            .map(|&pos| spawn::spawn_monster_kind(&world, GameSym::Goblin, pos).unwrap())
+            .collect();
+
+        (world, goblins)
+    }
+
+    /// Queue a blast from the player that hits everything in the given list for its full health.
+    fn blast(world: &World, targets: &[EntityId]) {
+        let player_id = world.borrow::<UniqueView<PlayerId>>().0;
+        let combat_stats = world.borrow::<View<CombatStats>>();
+        let mut damage_queue = world.borrow::<UniqueViewMut<DamageQueue>>();
+
+        for &target in targets {
+            let hp = combat_stats.get(target).hp;
+            damage_queue.push(target, hp, HurtBy::Someone(player_id));
+        }
+    }
+
+    #[test]
+    fn area_of_effect_kills_are_each_credited_once() {
+        let (world, goblins) = three_goblins();
+        let player_id = world.borrow::<UniqueView<PlayerId>>().0;
+        let exp_each = world.borrow::<View<GivesExperience>>().get(goblins[0]).0;
+
+        blast(&world, &goblins);
+        world.run(handle_dead_entities);
+
+        let entities = world.borrow::<EntitiesView>();
+        assert!(goblins.iter().all(|&id| !entities.is_alive(id)));
+        assert!(world.borrow::<View<Monster>>().is_empty());
+        assert_eq!(world.borrow::<View<Tally>>().get(player_id).kills, 3);
+        assert_eq!(
+            world.borrow::<View<Experience>>().get(player_id).exp,
+            exp_each * 3
+        );
+        assert!(world.borrow::<UniqueView<PlayerAlive>>().0);
+    }
+
+    #[test]
+    fn player_dying_alongside_monsters_ends_the_game() {
+        let (world, goblins) = three_goblins();
+        let player_id = world.borrow::<UniqueView<PlayerId>>().0;
+
+        blast(&world, &goblins);
+        world.borrow::<UniqueViewMut<DamageQueue>>().push(
+            player_id,
+            1000,
+            HurtBy::Someone(goblins[0]),
+        );
+        world.run(handle_dead_entities);
+
+        assert!(world.borrow::<View<Monster>>().is_empty());
+        assert_eq!(world.borrow::<View<Tally>>().get(player_id).kills, 3);
+        assert!(!world.borrow::<UniqueView<PlayerAlive>>().0);
+        assert!(world.borrow::<EntitiesView>().is_alive(player_id));
+    }
+
+    #[test]
+    fn queued_damage_is_applied_before_deaths_resolve_once() {
+        let (world, goblins) = three_goblins();
+        let player_id = world.borrow::<UniqueView<PlayerId>>().0;
+        let hp = world.borrow::<View<CombatStats>>().get(goblins[0]).hp;
+
+        {
+            let mut damage_queue = world.borrow::<UniqueViewMut<DamageQueue>>();
+            let by_player = HurtBy::Someone(player_id);
+
+            damage_queue.push(goblins[0], hp - 1, by_player);
+            damage_queue.push_message(goblins[0], "First hit.".into(), None);
+            damage_queue.push(goblins[1], 1, by_player);
+            damage_queue.push_message(goblins[1], "Other hit.".into(), None);
+            damage_queue.push(goblins[0], 1, by_player);
+            damage_queue.push_message(goblins[0], "Last hit.".into(), Some(Color::RED));
+        }
+        world.run(handle_dead_entities);
+
+        let msgs = world.borrow::<UniqueView<Messages>>();
+        let mut history = msgs.rev_iter().map(|(m, ..)| m).collect::<Vec<_>>();
+        history.reverse();
+
+        assert_eq!(
+            history,
+            ["First hit.", "Last hit.", "Other hit.", "The goblin dies!"]
+        );
+        assert!(!world.borrow::<EntitiesView>().is_alive(goblins[0]));
+        assert_eq!(
+            world.borrow::<View<CombatStats>>().get(goblins[1]).hp,
+            hp - 1
+        );
+        assert_eq!(world.borrow::<View<Tally>>().get(player_id).kills, 1);
+        assert_eq!(
+            world
+                .borrow::<UniqueView<DamageQueue>>()
+                .pending_for(goblins[1]),
+            0
+        );
+    }
+
+    #[test]
+    fn fire_resistance_halves_fire_damage_only() {
+        let world = spawn::test_world(20, 20, (5, 5));
+        let demon = spawn::spawn_monster_kind(&world, GameSym::Demon, (6, 5)).unwrap();
+        let (resistances, vulnerabilities) =
+            world.borrow::<(View<Resistances>, View<Vulnerabilities>)>();
+        let scale = |damage_type| {
+            scale_damage_by_type(10, damage_type, demon, &resistances, &vulnerabilities)
+        };
+
+        assert_eq!(scale(DamageType::Fire), 5);
+        assert_eq!(scale(DamageType::Physical), 10);
+        assert_eq!(scale(DamageType::Poison), 10);
+        assert_eq!(scale(DamageType::Cold), 20);
+    }
+
+    #[test]
+    fn lifesteal_heals_a_fraction_of_damage_dealt_when_applied() {
+        let (world, goblins) = three_goblins();
+        let player_id = world.borrow::<UniqueView<PlayerId>>().0;
+        let max_hp = world.borrow::<View<CombatStats>>().get(player_id).max_hp;
+        let by_player = HurtBy::Someone(player_id);
+        let player_hp = |world: &World| world.borrow::<View<CombatStats>>().get(player_id).hp;
+        let set_player_hp = |world: &World, hp| {
+            (&mut world.borrow::<ViewMut<CombatStats>>())
+                .get(player_id)
+                .hp = hp;
+        };
+
+        // A 50% lifesteal hit for 6 damage heals by 3, but only once the damage is applied.
+        set_player_hp(&world, max_hp - 10);
+        world
+            .borrow::<UniqueViewMut<DamageQueue>>()
+            .push_with_lifesteal(goblins[0], 6, by_player, 0.5);
+        assert_eq!(player_hp(&world), max_hp - 10);
+        world.run(apply_damage_queue);
+        assert_eq!(player_hp(&world), max_hp - 7);
+
+        // Healing is clamped at max HP.
+        set_player_hp(&world, max_hp - 1);
+        world
+            .borrow::<UniqueViewMut<DamageQueue>>()
+            .push_with_lifesteal(goblins[1], 6, by_player, 0.5);
+        world.run(apply_damage_queue);
+        assert_eq!(player_hp(&world), max_hp);
+
+        // Overkill damage isn't dealt, so it doesn't heal.
+        set_player_hp(&world, max_hp - 10);
+        (&mut world.borrow::<ViewMut<CombatStats>>())
+            .get(goblins[2])
+            .hp = 2;
+        world
+            .borrow::<UniqueViewMut<DamageQueue>>()
+            .push_with_lifesteal(goblins[2], 6, by_player, 0.5);
+        world.run(apply_damage_queue);
+        assert_eq!(player_hp(&world), max_hp - 9);
+    }
+
+    /// A world with the player at (5, 5) next to a sleeping crab, so the player's attacks always
+    /// land, returning the crab's ID.
+    fn sleeping_crab() -> (World, EntityId) {
+        let world = spawn::test_world(20, 20, (5, 5));
+        let crab_id = spawn::spawn_monster_kind(&world, GameSym::Crab, (6, 5)).unwrap();
+
+        world.borrow::<EntitiesView>().add_component(
+            &mut world.borrow::<ViewMut<Asleep>>(),
+            Asleep {
+                sleepiness: 100,
+                last_hp: 100,
+            },
+            crab_id,
+        );
+
+        (world, crab_id)
+    }
+
+    #[test]
+    fn damage_preview_matches_melee_damage() {
+        let world = spawn::test_world(20, 20, (5, 5));
+        let player_id = world.borrow::<UniqueView<PlayerId>>().0;
+        let goblin_id = spawn::spawn_monster_kind(&world, GameSym::Goblin, (6, 5)).unwrap();
+
+        world.borrow::<EntitiesView>().add_component(
+            &mut world.borrow::<ViewMut<Asleep>>(),
+            Asleep {
+                sleepiness: 100,
+                last_hp: 100,
+            },
+            goblin_id,
+        );
+        {
+            let mut combat_stats = world.borrow::<ViewMut<CombatStats>>();
+
+            (&mut combat_stats).get(player_id).attack = 10.0;
+            (&mut combat_stats).get(goblin_id).defense = 3.0;
+        }
+
+        // 10 attack against 3 defense deals 7, which fluctuates from half to one and a half times.
+        assert_eq!(melee_damage_range(&world, player_id, goblin_id), (3, 11));
+
+        let mut dealt = Vec::new();
+
+        for turn in 1..200 {
+            world.borrow::<UniqueViewMut<TurnCount>>().0 = turn;
+            melee_attack(&world, player_id, goblin_id);
+
+            let mut damage_queue = world.borrow::<UniqueViewMut<DamageQueue>>();
+
+            dealt.push(damage_queue.pending_for(goblin_id));
+            damage_queue.damage.clear();
+        }
+
+        assert_eq!(dealt.iter().min(), Some(&3));
+        assert_eq!(dealt.iter().max(), Some(&11));
+    }
+
+    #[test]
+    fn attacking_a_thorned_monster_hurts_the_attacker() {
+        let (world, crab_id) = sleeping_crab();
+        let player_id = world.borrow::<UniqueView<PlayerId>>().0;
+        let thorns_amount = world.borrow::<View<Thorns>>().get(crab_id).amount;
+        let start_hp = world.borrow::<View<CombatStats>>().get(player_id).hp;
+
+        melee_attack(&world, player_id, crab_id);
+        assert_eq!(
+            world
+                .borrow::<UniqueView<DamageQueue>>()
+                .pending_for(player_id),
+            thorns_amount
+        );
+
+        world.run(handle_dead_entities);
+        assert_eq!(
+            world.borrow::<View<CombatStats>>().get(player_id).hp,
+            start_hp - thorns_amount
+        );
+    }
+
+    #[test]
+    fn thorns_can_kill_the_attacker() {
+        let (world, crab_id) = sleeping_crab();
+        let player_id = world.borrow::<UniqueView<PlayerId>>().0;
+
+        (&mut world.borrow::<ViewMut<CombatStats>>())
+            .get(player_id)
+            .hp = 1;
+        melee_attack(&world, player_id, crab_id);
+        world.run(handle_dead_entities);
+
+        assert!(!world.borrow::<UniqueView<PlayerAlive>>().0);
+    }
+}
@@ -7,6 +7,7 @@ use crate::{
     message::Messages,
     player::PlayerId,
 };
+use ruggrogue::util::Color;
 
 /// Tracking state that counts total amount of experience points that could be gained at the time
 /// of entering a new dungeon depth, used to determine the approximate level that monsters and
@@ -172,7 +173,10 @@ pub fn gain_levels(
                     stats.defense = new_defense;
 
                     if id == player_id.0 {
-                        msgs.add(format!("{} is now level {}!", &names.get(id).0, exp.level));
+                        msgs.add_colored(
+                            format!("{} is now level {}!", &names.get(id).0, exp.level),
+                            Color::YELLOW,
+                        );
                     }
                 }
             }
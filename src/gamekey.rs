@@ -1,5 +1,9 @@
 use sdl2::keyboard::Keycode;
+use serde::{Deserialize, Serialize};
+use shipyard::{UniqueView, World};
+use std::collections::HashMap;
 
+#[derive(Clone, Copy, Deserialize, Eq, Hash, PartialEq, Serialize)]
 pub enum GameKey {
     Unmapped,
     Up,
@@ -15,50 +19,310 @@ pub enum GameKey {
     Home,
     End,
     Wait,
+    Retreat,
+    AutoExplore,
     Descend,
     Cancel,
     Confirm,
+    NextTarget,
+    PrevTarget,
     ViewMap,
+    MessageLog,
     PickUp,
     Inventory,
     EquipItem,
     RemoveItem,
     UseItem,
+    ThrowItem,
     DropItem,
+    Undo,
+    Examine,
+    Mark,
 }
 
-pub fn from_keycode(key: Keycode, shift: bool) -> GameKey {
-    match key {
-        Keycode::Up | Keycode::K | Keycode::Kp8 => GameKey::Up,
-        Keycode::Down | Keycode::J | Keycode::Kp2 => GameKey::Down,
-        Keycode::Left | Keycode::H | Keycode::Kp4 => GameKey::Left,
-        Keycode::Right | Keycode::L | Keycode::Kp6 => GameKey::Right,
-        Keycode::Y | Keycode::Kp7 => GameKey::UpLeft,
-        Keycode::U | Keycode::Kp9 => GameKey::UpRight,
-        Keycode::B | Keycode::Kp1 => GameKey::DownLeft,
-        Keycode::N | Keycode::Kp3 => GameKey::DownRight,
-        Keycode::PageUp => GameKey::PageUp,
-        Keycode::PageDown => GameKey::PageDown,
-        Keycode::Home => GameKey::Home,
-        Keycode::End => GameKey::End,
-        Keycode::Kp5 | Keycode::Space => GameKey::Wait,
-        Keycode::Period => {
-            if shift {
-                GameKey::Descend
-            } else {
-                GameKey::Wait
+/// Game keys that players can bind keys to, along with their names.
+pub const REBINDABLE_KEYS: [(GameKey, &str); 32] = [
+    (GameKey::Up, "Up"),
+    (GameKey::Down, "Down"),
+    (GameKey::Left, "Left"),
+    (GameKey::Right, "Right"),
+    (GameKey::UpLeft, "Up-left"),
+    (GameKey::UpRight, "Up-right"),
+    (GameKey::DownLeft, "Down-left"),
+    (GameKey::DownRight, "Down-right"),
+    (GameKey::PageUp, "Page up"),
+    (GameKey::PageDown, "Page down"),
+    (GameKey::Home, "Home"),
+    (GameKey::End, "End"),
+    (GameKey::Wait, "Wait"),
+    (GameKey::Retreat, "Retreat"),
+    (GameKey::AutoExplore, "Auto-explore"),
+    (GameKey::Descend, "Descend"),
+    (GameKey::Cancel, "Cancel"),
+    (GameKey::Confirm, "Confirm"),
+    (GameKey::NextTarget, "Next target"),
+    (GameKey::PrevTarget, "Prev target"),
+    (GameKey::ViewMap, "View map"),
+    (GameKey::MessageLog, "Message log"),
+    (GameKey::PickUp, "Pick up"),
+    (GameKey::Inventory, "Inventory"),
+    (GameKey::EquipItem, "Equip"),
+    (GameKey::RemoveItem, "Remove"),
+    (GameKey::UseItem, "Use"),
+    (GameKey::ThrowItem, "Throw"),
+    (GameKey::DropItem, "Drop"),
+    (GameKey::Undo, "Undo"),
+    (GameKey::Examine, "Examine"),
+    (GameKey::Mark, "Mark"),
+];
+
+/// A key press that can be bound to a game key, with or without the shift key held.
+#[derive(Clone, Copy, Eq, PartialEq)]
+pub struct Key {
+    pub code: Keycode,
+    pub shift: bool,
+}
+
+const SHIFT_PREFIX: &str = "Shift+";
+
+impl Key {
+    pub const fn new(code: Keycode) -> Self {
+        Self { code, shift: false }
+    }
+
+    pub const fn shifted(code: Keycode) -> Self {
+        Self { code, shift: true }
+    }
+
+    /// Parse a key from the name given by [Key::name].
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.strip_prefix(SHIFT_PREFIX) {
+            Some(unshifted) => Keycode::from_name(unshifted).map(Self::shifted),
+            None => Keycode::from_name(name).map(Self::new),
+        }
+    }
+
+    /// The name of the key shown to players and stored in the config file, e.g. "Shift+Tab".
+    pub fn name(&self) -> String {
+        if self.shift {
+            format!("{}{}", SHIFT_PREFIX, self.code.name())
+        } else {
+            self.code.name()
+        }
+    }
+}
+
+/// Keys bound to each game key before the player changes anything.
+fn default_keys(gkey: GameKey) -> Vec<Key> {
+    use Keycode::*;
+
+    let keys: &[Keycode] = match gkey {
+        GameKey::Unmapped => &[],
+        GameKey::Up => &[Up, K, Kp8],
+        GameKey::Down => &[Down, J, Kp2],
+        GameKey::Left => &[Left, H, Kp4],
+        GameKey::Right => &[Right, L, Kp6],
+        GameKey::UpLeft => &[Y, Kp7],
+        GameKey::UpRight => &[U, Kp9],
+        GameKey::DownLeft => &[B, Kp1],
+        GameKey::DownRight => &[N, Kp3],
+        GameKey::PageUp => &[PageUp],
+        GameKey::PageDown => &[PageDown],
+        GameKey::Home => &[Home],
+        GameKey::End => &[End],
+        GameKey::Wait => &[Kp5, Space, Period],
+        GameKey::Retreat => &[F],
+        GameKey::AutoExplore => &[O],
+        GameKey::Descend => {
+            return vec![Key::shifted(Period), Key::new(Greater), Key::new(KpGreater)]
+        }
+        GameKey::Cancel => &[Escape],
+        GameKey::Confirm => &[Return, KpEnter],
+        GameKey::NextTarget => &[Tab],
+        GameKey::PrevTarget => return vec![Key::shifted(Tab)],
+        GameKey::ViewMap => &[V],
+        GameKey::MessageLog => &[P],
+        GameKey::PickUp => &[Comma, G],
+        GameKey::Inventory => &[I],
+        GameKey::EquipItem => &[E, W],
+        GameKey::RemoveItem => &[R],
+        GameKey::UseItem => &[A],
+        GameKey::ThrowItem => &[T],
+        GameKey::DropItem => &[D],
+        GameKey::Undo => &[Z],
+        GameKey::Examine => &[X],
+        GameKey::Mark => &[M],
+    };
+
+    keys.iter().copied().map(Key::new).collect()
+}
+
+/// The keys bound to each game key, starting with the defaults, which players can change.
+pub struct KeyBindings(HashMap<GameKey, Vec<Key>>);
+
+impl KeyBindings {
+    /// Key bindings with every game key bound to its default keys.
+    pub fn new() -> Self {
+        Self(
+            REBINDABLE_KEYS
+                .iter()
+                .map(|&(gkey, _)| (gkey, default_keys(gkey)))
+                .collect(),
+        )
+    }
+
+    /// Create key bindings from the names of the keys bound to each game key, as stored in the
+    /// config file.  Game keys missing from the config file keep their defaults, and unknown key
+    /// names are ignored.
+    pub fn from_names(names: &HashMap<GameKey, Vec<String>>) -> Self {
+        let mut bindings = Self::new();
+
+        for (&gkey, keys) in names.iter() {
+            if let Some(bound) = bindings.0.get_mut(&gkey) {
+                *bound = keys
+                    .iter()
+                    .filter_map(|name| Key::from_name(name))
+                    .collect();
             }
         }
-        Keycode::Greater | Keycode::KpGreater => GameKey::Descend,
-        Keycode::Escape => GameKey::Cancel,
-        Keycode::Return | Keycode::KpEnter => GameKey::Confirm,
-        Keycode::V => GameKey::ViewMap,
-        Keycode::Comma | Keycode::G => GameKey::PickUp,
-        Keycode::I => GameKey::Inventory,
-        Keycode::E | Keycode::W => GameKey::EquipItem,
-        Keycode::R => GameKey::RemoveItem,
-        Keycode::A => GameKey::UseItem,
-        Keycode::D => GameKey::DropItem,
-        _ => GameKey::Unmapped,
+
+        bindings
+    }
+
+    /// Names of the keys bound to each game key, for storing in the config file.
+    pub fn to_names(&self) -> HashMap<GameKey, Vec<String>> {
+        self.0
+            .iter()
+            .map(|(&gkey, keys)| (gkey, keys.iter().map(Key::name).collect()))
+            .collect()
+    }
+
+    /// Keys currently bound to a game key.
+    pub fn keys(&self, gkey: GameKey) -> &[Key] {
+        self.0.get(&gkey).map_or(&[], Vec::as_slice)
+    }
+
+    /// The game key that a key press is bound to.  Keys bound without shift also work with shift
+    /// held, unless the shifted key is bound to something else.
+    pub fn game_key(&self, key: Key) -> GameKey {
+        let find = |key: Key| {
+            self.0
+                .iter()
+                .find(|(_, keys)| keys.contains(&key))
+                .map(|(&gkey, _)| gkey)
+        };
+
+        find(key)
+            .or_else(|| {
+                if key.shift {
+                    find(Key::new(key.code))
+                } else {
+                    None
+                }
+            })
+            .unwrap_or(GameKey::Unmapped)
+    }
+
+    /// Bind a key to a game key alongside any keys already bound to it.  Returns false without
+    /// binding anything if the key is already bound to a different game key.
+    pub fn bind(&mut self, gkey: GameKey, key: Key) -> bool {
+        if self
+            .0
+            .iter()
+            .any(|(&g, keys)| g != gkey && keys.contains(&key))
+        {
+            return false;
+        }
+
+        let keys = self.0.entry(gkey).or_default();
+        if !keys.contains(&key) {
+            keys.push(key);
+        }
+
+        true
+    }
+
+    /// Remove all keys bound to a game key, freeing them to be bound to other game keys.
+    pub fn unbind(&mut self, gkey: GameKey) {
+        if let Some(keys) = self.0.get_mut(&gkey) {
+            keys.clear();
+        }
+    }
+
+    /// Bind a game key back to its default keys, except for any now bound to other game keys.
+    pub fn reset(&mut self, gkey: GameKey) {
+        self.unbind(gkey);
+        for key in default_keys(gkey) {
+            self.bind(gkey, key);
+        }
+    }
+}
+
+/// Translate a key press into a game key according to the current key bindings.
+pub fn from_keycode(world: &World, key: Keycode, shift: bool) -> GameKey {
+    world
+        .borrow::<UniqueView<KeyBindings>>()
+        .game_key(Key { code: key, shift })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_match_original_keys() {
+        let bindings = KeyBindings::new();
+
+        assert!(bindings.game_key(Key::new(Keycode::K)) == GameKey::Up);
+        assert!(bindings.game_key(Key::new(Keycode::Period)) == GameKey::Wait);
+        assert!(bindings.game_key(Key::shifted(Keycode::Period)) == GameKey::Descend);
+        assert!(bindings.game_key(Key::shifted(Keycode::Tab)) == GameKey::PrevTarget);
+        assert!(bindings.game_key(Key::shifted(Keycode::Space)) == GameKey::Wait);
+        assert!(bindings.game_key(Key::new(Keycode::Q)) == GameKey::Unmapped);
+    }
+
+    #[test]
+    fn rebind_confirm_to_space() {
+        let world = World::new();
+        let mut bindings = KeyBindings::new();
+
+        // Space is bound to Wait by default, so it has to be freed up first.
+        assert!(!bindings.bind(GameKey::Confirm, Key::new(Keycode::Space)));
+        bindings.unbind(GameKey::Wait);
+        assert!(bindings.bind(GameKey::Confirm, Key::new(Keycode::Space)));
+
+        world.add_unique(bindings);
+
+        assert!(from_keycode(&world, Keycode::Space, false) == GameKey::Confirm);
+        assert!(from_keycode(&world, Keycode::Return, false) == GameKey::Confirm);
+        assert!(from_keycode(&world, Keycode::Kp5, false) == GameKey::Unmapped);
+    }
+
+    #[test]
+    fn reset_skips_keys_bound_elsewhere() {
+        let mut bindings = KeyBindings::new();
+
+        bindings.unbind(GameKey::Wait);
+        bindings.bind(GameKey::Confirm, Key::new(Keycode::Space));
+        bindings.reset(GameKey::Wait);
+
+        assert!(
+            bindings.keys(GameKey::Wait) == [Key::new(Keycode::Kp5), Key::new(Keycode::Period)]
+        );
+        assert!(bindings.game_key(Key::new(Keycode::Space)) == GameKey::Confirm);
+    }
+
+    #[test]
+    fn names_round_trip() {
+        let mut bindings = KeyBindings::new();
+
+        bindings.unbind(GameKey::PrevTarget);
+        bindings.bind(GameKey::PrevTarget, Key::shifted(Keycode::Q));
+
+        let reloaded = KeyBindings::from_names(&bindings.to_names());
+
+        assert!(reloaded.keys(GameKey::PrevTarget) == [Key::shifted(Keycode::Q)]);
+        assert!(reloaded.keys(GameKey::Up) == bindings.keys(GameKey::Up));
+        assert_eq!(Key::shifted(Keycode::Tab).name(), "Shift+Tab");
     }
 }
@@ -23,6 +23,7 @@ pub enum GameSym {
     WallEsw,
     WallNesw,
     WallOther,
+    WallBlock,
     DownStairs,
     Player,
     Ration,
@@ -30,6 +31,7 @@ pub enum GameSym {
     MagicMissileScroll,
     FireballScroll,
     SleepScroll,
+    ConfusionScroll,
     Knife,
     Club,
     Hatchet,
@@ -76,6 +78,7 @@ pub enum GameSym {
     Dweller,
     LittleHelper,
     BigHelper,
+    Mimic,
 }
 
 impl Symbol for GameSym {
@@ -101,6 +104,7 @@ impl Symbol for GameSym {
             WallEsw => '╦',
             WallNesw => '╬',
             WallOther => '#',
+            WallBlock => '█',
             DownStairs => '>',
             Player => '@',
             Ration => '%',
@@ -108,6 +112,7 @@ impl Symbol for GameSym {
             MagicMissileScroll => '?',
             FireballScroll => '?',
             SleepScroll => '?',
+            ConfusionScroll => '?',
             Knife => ')',
             Club => ')',
             Hatchet => ')',
@@ -154,6 +159,7 @@ impl Symbol for GameSym {
             Dweller => 'D',
             LittleHelper => 'h',
             BigHelper => 'H',
+            Mimic => 'm',
         }
     }
 }
@@ -239,6 +245,7 @@ pub fn urizen_tileset_info() -> TilesetInfo<GameSym> {
         symbol_map.insert(WallEsw, (1, 0));
         symbol_map.insert(WallNesw, (1, 0));
         symbol_map.insert(WallOther, (1, 0));
+        symbol_map.insert(WallBlock, (0, 0));
         symbol_map.insert(DownStairs, (10, 0));
         symbol_map.insert(Player, (29, 0));
         symbol_map.insert(Ration, (5, 16));
@@ -246,6 +253,7 @@ pub fn urizen_tileset_info() -> TilesetInfo<GameSym> {
         symbol_map.insert(MagicMissileScroll, (28, 25));
         symbol_map.insert(FireballScroll, (28, 25));
         symbol_map.insert(SleepScroll, (28, 25));
+        symbol_map.insert(ConfusionScroll, (28, 25));
         symbol_map.insert(Knife, (14, 23));
         symbol_map.insert(Club, (37, 21));
         symbol_map.insert(Hatchet, (42, 21));
@@ -292,6 +300,7 @@ pub fn urizen_tileset_info() -> TilesetInfo<GameSym> {
         symbol_map.insert(Dweller, (1, 41));
         symbol_map.insert(LittleHelper, (26, 30));
         symbol_map.insert(BigHelper, (25, 30));
+        symbol_map.insert(Mimic, (27, 26));
     }
 
     TilesetInfo::<GameSym> {
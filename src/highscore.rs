@@ -0,0 +1,253 @@
+use serde::{Deserialize, Serialize};
+use shipyard::{Get, UniqueView, View, World};
+use std::{
+    error,
+    fs::{self, File},
+    io::{BufReader, BufWriter, Write},
+    process,
+    sync::atomic::{AtomicU32, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    components::{Experience, Name, Tally},
+    map::Map,
+    player::PlayerId,
+    TurnCount,
+};
+
+#[cfg(target_os = "emscripten")]
+const HIGH_SCORES_FILENAME: &str = "/ruggrogue/highscores.txt";
+
+#[cfg(not(target_os = "emscripten"))]
+const HIGH_SCORES_FILENAME: &str = "highscores.txt";
+
+/// Maximum number of entries kept in the high score table.
+const MAX_HIGH_SCORES: usize = 10;
+
+const POINTS_PER_DEPTH: u64 = 100;
+const POINTS_PER_KILL: u64 = 10;
+const EXP_PER_POINT: u64 = 10;
+
+/// Counts writes of the high score table, so that each one gets its own temporary file.
+static SAVE_COUNT: AtomicU32 = AtomicU32::new(0);
+
+type BoxedError = Box<dyn error::Error>;
+
+/// A single finished run in the high score table.
+#[derive(Deserialize, Serialize)]
+pub struct HighScore {
+    pub name: String,
+    pub depth: i32,
+    pub turns: u64,
+    pub score: u64,
+    /// Date that the run ended on, in YYYY-MM-DD form.
+    pub date: String,
+}
+
+/// The best finished runs, highest score first.
+#[derive(Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct HighScores {
+    pub entries: Vec<HighScore>,
+}
+
+impl HighScores {
+    /// Insert a new entry in sorted order, returning its rank starting from zero, or `None` if it
+    /// didn't score high enough to be kept.  Ties go to the older entry.
+    pub fn insert(&mut self, entry: HighScore) -> Option<usize> {
+        let rank = self
+            .entries
+            .iter()
+            .position(|e| entry.score > e.score)
+            .unwrap_or(self.entries.len());
+
+        if rank < MAX_HIGH_SCORES {
+            self.entries.insert(rank, entry);
+            self.entries.truncate(MAX_HIGH_SCORES);
+            Some(rank)
+        } else {
+            None
+        }
+    }
+
+    /// Lines of text describing the table, suitable for a message box.
+    pub fn describe(&self) -> Vec<String> {
+        let mut lines = vec!["High Scores".to_string(), "".to_string()];
+
+        if self.entries.is_empty() {
+            lines.push("No games have been finished yet.".to_string());
+        } else {
+            lines.push(" #  Name          Depth  Turns  Score  Date".to_string());
+            for (i, e) in self.entries.iter().enumerate() {
+                lines.push(format!(
+                    "{:>2}  {:<12.12} {:>6} {:>6} {:>6}  {}",
+                    i + 1,
+                    e.name,
+                    e.depth,
+                    e.turns,
+                    e.score,
+                    e.date,
+                ));
+            }
+        }
+
+        lines
+    }
+}
+
+/// Calculate the score of a run.
+fn calc_score(depth: i32, kills: u64, exp: u64) -> u64 {
+    depth.max(0) as u64 * POINTS_PER_DEPTH + kills * POINTS_PER_KILL + exp / EXP_PER_POINT
+}
+
+/// Today's date in YYYY-MM-DD form.
+fn today() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+
+    // Convert days since the Unix epoch into a civil date in the proleptic Gregorian calendar.
+    let days = (secs / 86400) as i64 + 719468;
+    let era = days / 146097;
+    let day_of_era = days - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+fn try_load_high_scores() -> Result<HighScores, BoxedError> {
+    let reader = BufReader::new(File::open(HIGH_SCORES_FILENAME)?);
+
+    Ok(serde_json::from_reader(reader)?)
+}
+
+/// Load the high score table, falling back to an empty one if it's missing or unreadable.
+pub fn load_high_scores() -> HighScores {
+    try_load_high_scores().unwrap_or_default()
+}
+
+/// Save the high score table.  The table is written out to a temporary file first and then moved
+/// over the old one so that a crash or another running copy of the game can't leave it half
+/// written.  The temporary file is named after the process and the write so that two writes never
+/// share one.
+fn save_high_scores(high_scores: &HighScores) -> Result<(), BoxedError> {
+    let temp_filename = format!(
+        "{}.{}-{}.tmp",
+        HIGH_SCORES_FILENAME,
+        process::id(),
+        SAVE_COUNT.fetch_add(1, Ordering::Relaxed)
+    );
+
+    {
+        let mut writer = BufWriter::new(File::create(&temp_filename)?);
+
+        serde_json::to_writer(&mut writer, high_scores)?;
+        writer.write_all(b"\n")?;
+        writer.flush()?;
+        writer.get_ref().sync_all()?;
+    }
+
+    fs::rename(&temp_filename, HIGH_SCORES_FILENAME)?;
+
+    #[cfg(target_os = "emscripten")]
+    unsafe {
+        crate::ruggrogue_sync_idbfs();
+    }
+
+    Ok(())
+}
+
+/// Add the player's current run to the high score table, returning the resulting table and the
+/// rank of the run in it, if any.
+pub fn record_run(world: &World) -> (HighScores, Option<usize>) {
+    let entry = {
+        let (map, turn_count, player_id, exps, names, tallies) = world.borrow::<(
+            UniqueView<Map>,
+            UniqueView<TurnCount>,
+            UniqueView<PlayerId>,
+            View<Experience>,
+            View<Name>,
+            View<Tally>,
+        )>();
+        let player_exp = exps.get(player_id.0);
+
+        HighScore {
+            name: names.get(player_id.0).0.clone(),
+            depth: map.depth,
+            turns: turn_count.0,
+            score: calc_score(
+                map.depth,
+                tallies.get(player_id.0).kills,
+                player_exp.base + player_exp.exp,
+            ),
+            date: today(),
+        }
+    };
+    let mut high_scores = load_high_scores();
+    let rank = high_scores.insert(entry);
+
+    if rank.is_some() {
+        if let Err(e) = save_high_scores(&high_scores) {
+            eprintln!("Warning: highscore::save_high_scores: {}", e);
+        }
+    }
+
+    (high_scores, rank)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, score: u64) -> HighScore {
+        HighScore {
+            name: name.to_string(),
+            depth: 1,
+            turns: 1,
+            score,
+            date: "2020-01-01".to_string(),
+        }
+    }
+
+    fn names(high_scores: &HighScores) -> Vec<&str> {
+        high_scores
+            .entries
+            .iter()
+            .map(|e| e.name.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn scores_are_inserted_at_their_rank() {
+        let mut high_scores = HighScores::default();
+
+        assert_eq!(high_scores.insert(entry("b", 200)), Some(0));
+        assert_eq!(high_scores.insert(entry("d", 50)), Some(1));
+        assert_eq!(high_scores.insert(entry("a", 300)), Some(0));
+        assert_eq!(high_scores.insert(entry("c", 100)), Some(2));
+        assert_eq!(high_scores.insert(entry("c2", 100)), Some(3));
+        assert_eq!(names(&high_scores), ["a", "b", "c", "c2", "d"]);
+    }
+
+    #[test]
+    fn scores_below_a_full_table_are_not_kept() {
+        let mut high_scores = HighScores::default();
+
+        for i in 0..MAX_HIGH_SCORES as u64 {
+            high_scores.insert(entry("old", 100 + i));
+        }
+
+        assert_eq!(high_scores.insert(entry("low", 100)), None);
+        assert_eq!(high_scores.insert(entry("mid", 105)), Some(5));
+        assert_eq!(high_scores.entries.len(), MAX_HIGH_SCORES);
+        assert!(!names(&high_scores).contains(&"low"));
+    }
+}
@@ -1,11 +1,11 @@
 use shipyard::{
-    EntitiesView, EntityId, Get, IntoIter, Shiperator, UniqueView, UniqueViewMut, View, ViewMut,
-    World,
+    EntityId, Get, IntoIter, Shiperator, UniqueView, UniqueViewMut, View, ViewMut, World,
 };
 
 use crate::{
-    components::{CombatStats, HurtBy, Name, Player, Stomach, Tally},
-    message::Messages,
+    components::{CombatStats, HurtBy, Name, Player, Poison, Stomach},
+    damage::DamageQueue,
+    message::{self, Messages},
     player::PlayerId,
 };
 use ruggrogue::util::Color;
@@ -97,6 +97,10 @@ pub fn can_regen(world: &World, entity_id: EntityId) -> CanRegenResult {
         return CanRegenResult::FullyRested;
     }
 
+    if world.borrow::<View<Poison>>().contains(entity_id) {
+        return CanRegenResult::NoRegen;
+    }
+
     if HungerState::from(stomach.fullness)
         .turns_to_regen_to_max_hp()
         .is_none()
@@ -130,14 +134,13 @@ pub fn player_hunger_label(
 /// Perform per-turn hunger effects like emptying stomachs, regeneration and starvation.
 pub fn tick_hunger(
     mut msgs: UniqueViewMut<Messages>,
+    mut damage_queue: UniqueViewMut<DamageQueue>,
     player_id: UniqueView<PlayerId>,
-    entities: EntitiesView,
     mut combat_stats: ViewMut<CombatStats>,
-    mut hurt_bys: ViewMut<HurtBy>,
     names: View<Name>,
     mut players: ViewMut<Player>,
+    poisons: View<Poison>,
     mut stomachs: ViewMut<Stomach>,
-    mut tallies: ViewMut<Tally>,
 ) {
     for (id, stomach) in (&mut stomachs).iter().with_id() {
         let name = names.get(id);
@@ -148,8 +151,9 @@ pub fn tick_hunger(
 
             if let Ok(stats) = (&mut combat_stats).try_get(id) {
                 if stats.hp > 0 {
-                    // Regenerate hit points if below max and stomach allows it.
-                    if stats.hp < stats.max_hp && stomach.fullness > 0 {
+                    // Regenerate hit points if below max and stomach allows it, but not while
+                    // poisoned.
+                    if stats.hp < stats.max_hp && stomach.fullness > 0 && !poisons.contains(id) {
                         if let Some(regen_turns) =
                             HungerState::from(stomach.fullness).turns_to_regen_to_max_hp()
                         {
@@ -194,12 +198,8 @@ pub fn tick_hunger(
                     stomach.sub_hp -= stats.max_hp;
                     if -stomach.sub_hp >= starve_turns && starve_turns > 0 {
                         let amount = -stomach.sub_hp / starve_turns;
-                        stats.hp -= amount;
                         stomach.sub_hp += starve_turns * amount;
-                        entities.add_component(&mut hurt_bys, HurtBy::Starvation, id);
-                        if let Ok(tally) = (&mut tallies).try_get(id) {
-                            tally.damage_taken += amount.max(0) as u64;
-                        }
+                        damage_queue.push(id, amount, HurtBy::Starvation);
 
                         // Stop auto-run when taking damage from starvation.
                         if let Ok(player) = (&mut players).try_get(id) {
@@ -208,7 +208,10 @@ pub fn tick_hunger(
 
                         // Tell the player when they take damage from starvation.
                         if id == player_id.0 {
-                            msgs.add(format!("{} aches with hunger!", &name.0));
+                            msgs.add_colored(
+                                format!("{} aches with hunger!", &name.0),
+                                message::WARNING_COLOR,
+                            );
                         }
                     }
                 }
@@ -216,3 +219,59 @@ pub fn tick_hunger(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use shipyard::EntitiesView;
+
+    use super::*;
+    use crate::spawn;
+
+    /// Set the player's hit points to half of their maximum and let 30 turns pass, returning how
+    /// many hit points the player regained and their maximum hit points.
+    fn regen_over_30_turns(world: &World) -> (i32, i32) {
+        let player_id = world.borrow::<UniqueView<PlayerId>>().0;
+        let player_stats = || world.borrow::<View<CombatStats>>().get(player_id).clone();
+        let max_hp = {
+            let mut combat_stats = world.borrow::<ViewMut<CombatStats>>();
+            let stats = (&mut combat_stats).get(player_id);
+
+            stats.hp = stats.max_hp / 2;
+            stats.max_hp
+        };
+        let start_hp = player_stats().hp;
+
+        for _ in 0..30 {
+            world.run(tick_hunger);
+        }
+
+        (player_stats().hp - start_hp, max_hp)
+    }
+
+    #[test]
+    fn hit_points_regenerate_over_turns() {
+        let world = spawn::test_world(20, 20, (5, 5));
+        let (regained, max_hp) = regen_over_30_turns(&world);
+
+        // A well-fed player regenerates their maximum hit points over 300 turns.
+        assert!(regained > 0);
+        assert_eq!(regained, 30 * max_hp / 300);
+    }
+
+    #[test]
+    fn poison_stops_regeneration() {
+        let world = spawn::test_world(20, 20, (5, 5));
+        let player_id = world.borrow::<UniqueView<PlayerId>>().0;
+
+        world.borrow::<EntitiesView>().add_component(
+            &mut world.borrow::<ViewMut<Poison>>(),
+            Poison {
+                damage: 0,
+                turns: 100,
+            },
+            player_id,
+        );
+
+        assert_eq!(regen_over_30_turns(&world).0, 0);
+    }
+}
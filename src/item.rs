@@ -6,15 +6,22 @@ use std::cmp::Ordering;
 
 use crate::{
     components::*,
+    damage::{self, DamageQueue},
     map::Map,
-    message::Messages,
+    message::{self, Messages},
+    monster,
     player::{self, PlayerId},
-    saveload, Wins,
+    saveload,
+    vision::{self, DistanceMetric},
+    Wins,
 };
-use ruggrogue::FovShape;
+use ruggrogue::{util::Color, FovShape};
 
 pub struct PickUpHint(pub bool);
 
+/// Damage dealt by thrown items that lack a [ThrowDamage] of their own.
+const DEFAULT_THROW_DAMAGE: i32 = 1;
+
 pub fn add_item_to_map(world: &World, item_id: EntityId, pos: (i32, i32)) {
     let (mut map, entities, mut coords, mut render_on_floors) = world.borrow::<(
         UniqueViewMut<Map>,
@@ -72,11 +79,12 @@ pub fn remove_equipment(world: &World, remover_id: EntityId, item_id: EntityId)
 
         let mut msgs = world.borrow::<UniqueViewMut<Messages>>();
         let names = world.borrow::<View<Name>>();
+        let proper_nouns = world.borrow::<View<ProperNoun>>();
 
         msgs.add(format!(
             "{} removes {}.",
-            &names.get(remover_id).0,
-            &names.get(item_id).0
+            message::capitalize(&message::the_name_of(&names, &proper_nouns, remover_id)),
+            message::the_name_of(&names, &proper_nouns, item_id)
         ));
     } else {
         // Remover has no inventory, so attempt dropping the equipment instead.
@@ -95,11 +103,12 @@ pub fn drop_equipment(world: &World, dropper_id: EntityId, item_id: EntityId) {
 
     let mut msgs = world.borrow::<UniqueViewMut<Messages>>();
     let names = world.borrow::<View<Name>>();
+    let proper_nouns = world.borrow::<View<ProperNoun>>();
 
     msgs.add(format!(
         "{} drops {}.",
-        &names.get(dropper_id).0,
-        &names.get(item_id).0
+        message::capitalize(&message::the_name_of(&names, &proper_nouns, dropper_id)),
+        message::the_name_of(&names, &proper_nouns, item_id)
     ));
 }
 
@@ -129,17 +138,19 @@ pub fn equip_item(world: &World, equipper_id: EntityId, item_id: EntityId) {
 
     let mut msgs = world.borrow::<UniqueViewMut<Messages>>();
     let names = world.borrow::<View<Name>>();
+    let proper_nouns = world.borrow::<View<ProperNoun>>();
 
     msgs.add(format!(
         "{} equips {}.",
-        &names.get(equipper_id).0,
-        &names.get(item_id).0
+        message::capitalize(&message::the_name_of(&names, &proper_nouns, equipper_id)),
+        message::the_name_of(&names, &proper_nouns, item_id)
     ));
 }
 
 pub fn sort_inventory(world: &World, holder: EntityId) {
     let aoes = world.borrow::<View<AreaOfEffect>>();
     let combat_bonuses = world.borrow::<View<CombatBonus>>();
+    let inflicts_confusions = world.borrow::<View<InflictsConfusion>>();
     let inflicts_damages = world.borrow::<View<InflictsDamage>>();
     let inflicts_sleeps = world.borrow::<View<InflictsSleep>>();
     let names = world.borrow::<View<Name>>();
@@ -218,6 +229,20 @@ pub fn sort_inventory(world: &World, holder: EntityId) {
             }
         }
 
+        // Confusion Scroll
+        {
+            let a_is_confusion = inflicts_confusions.contains(a);
+            let b_is_confusion = inflicts_confusions.contains(b);
+
+            if a_is_confusion && b_is_confusion {
+                return Ordering::Equal;
+            } else if a_is_confusion {
+                return Ordering::Less;
+            } else if b_is_confusion {
+                return Ordering::Greater;
+            }
+        }
+
         // Fireball Scroll
         {
             let a_is_fs = rangeds.contains(a) && aoes.contains(a) && inflicts_damages.contains(a);
@@ -287,6 +312,55 @@ pub fn sort_inventory(world: &World, holder: EntityId) {
     holder_inv.items.sort_unstable_by(item_order);
 }
 
+/// Describe an item and what it does in a few lines, e.g. for examining it.
+pub fn describe_item(world: &World, item_id: EntityId) -> Vec<String> {
+    let mut lines = vec![world.borrow::<View<Name>>().get(item_id).0.clone()];
+
+    if let Ok(bonus) = world.borrow::<View<CombatBonus>>().try_get(item_id) {
+        if bonus.attack != 0.0 {
+            lines.push(format!("Attack: {:+.1}", bonus.attack));
+        }
+        if bonus.defense != 0.0 {
+            lines.push(format!("Defense: {:+.1}", bonus.defense));
+        }
+    }
+    if let Ok(healing) = world.borrow::<View<ProvidesHealing>>().try_get(item_id) {
+        lines.push(format!("Heals: {} hp", healing.heal_amount));
+    }
+    if let Ok(nutrition) = world.borrow::<View<Nutrition>>().try_get(item_id) {
+        lines.push(format!("Nutrition: {}", nutrition.0));
+    }
+    if let Ok(inflicts) = world.borrow::<View<InflictsDamage>>().try_get(item_id) {
+        lines.push(format!(
+            "Damage: {} {}",
+            inflicts.damage,
+            match inflicts.damage_type {
+                DamageType::Physical => "physical",
+                DamageType::Fire => "fire",
+                DamageType::Cold => "cold",
+                DamageType::Poison => "poison",
+            }
+        ));
+    }
+    if let Ok(inflicts) = world.borrow::<View<InflictsSleep>>().try_get(item_id) {
+        lines.push(format!("Sleep: {} turns", inflicts.sleepiness));
+    }
+    if let Ok(inflicts) = world.borrow::<View<InflictsConfusion>>().try_get(item_id) {
+        lines.push(format!("Confusion: {} turns", inflicts.turns));
+    }
+    if let Ok(ranged) = world.borrow::<View<Ranged>>().try_get(item_id) {
+        lines.push(format!("Range: {}", ranged.range));
+    }
+    if let Ok(aoe) = world.borrow::<View<AreaOfEffect>>().try_get(item_id) {
+        lines.push(format!("Radius: {}", aoe.radius));
+    }
+    if world.borrow::<View<Consumable>>().contains(item_id) {
+        lines.push("Used up when used.".into());
+    }
+
+    lines
+}
+
 /// Returns true if the game should end after the item is used.
 pub fn use_item(
     world: &World,
@@ -307,14 +381,39 @@ pub fn use_item(
         world.borrow::<UniqueViewMut<Wins>>().0 += 1;
         return true;
     } else {
-        let map = world.borrow::<UniqueView<Map>>();
+        let targets: Vec<EntityId> = {
+            let map = world.borrow::<UniqueView<Map>>();
+            let aoes = world.borrow::<View<AreaOfEffect>>();
+            let coords = world.borrow::<View<Coord>>();
+            let monsters = world.borrow::<View<Monster>>();
+            let players = world.borrow::<View<Player>>();
+            let rangeds = world.borrow::<View<Ranged>>();
+            let center = target.unwrap_or_else(|| coords.get(user_id).0.into());
+            let radius = aoes.try_get(item_id).map_or(0, |aoe| aoe.radius);
+            let metric = rangeds
+                .try_get(item_id)
+                .map_or(DistanceMetric::Euclidean, |ranged| ranged.metric);
+
+            // Match the area of effect highlighted while the target was picked.
+            ruggrogue::field_of_view(&*map, center, radius, FovShape::Square)
+                .filter(|&(x, y, symmetric)| symmetric && metric.within((x, y), center, radius))
+                .flat_map(|(x, y, _)| map.iter_entities_at(x, y))
+                .filter(|id| monsters.contains(*id) || players.contains(*id))
+                .collect()
+        };
+
+        // Disguised monsters caught by the item give themselves away before any messages.
+        for &target_id in &targets {
+            monster::reveal_disguise(world, target_id);
+        }
+
         let mut msgs = world.borrow::<UniqueViewMut<Messages>>();
+        let mut damage_queue = world.borrow::<UniqueViewMut<DamageQueue>>();
         let entities = world.borrow::<EntitiesView>();
-        let aoes = world.borrow::<View<AreaOfEffect>>();
         let mut asleeps = world.borrow::<ViewMut<Asleep>>();
         let mut combat_stats = world.borrow::<ViewMut<CombatStats>>();
-        let coords = world.borrow::<View<Coord>>();
-        let mut hurt_bys = world.borrow::<ViewMut<HurtBy>>();
+        let mut confusions = world.borrow::<ViewMut<Confusion>>();
+        let inflicts_confusions = world.borrow::<View<InflictsConfusion>>();
         let inflicts_damages = world.borrow::<View<InflictsDamage>>();
         let inflicts_sleeps = world.borrow::<View<InflictsSleep>>();
         let monsters = world.borrow::<View<Monster>>();
@@ -322,15 +421,10 @@ pub fn use_item(
         let nutritions = world.borrow::<View<Nutrition>>();
         let players = world.borrow::<View<Player>>();
         let provides_healings = world.borrow::<View<ProvidesHealing>>();
+        let resistances = world.borrow::<View<Resistances>>();
         let mut stomachs = world.borrow::<ViewMut<Stomach>>();
-        let mut tallies = world.borrow::<ViewMut<Tally>>();
-
-        let center = target.unwrap_or_else(|| coords.get(user_id).0.into());
-        let radius = aoes.try_get(item_id).map_or(0, |aoe| aoe.radius);
-        let targets = ruggrogue::field_of_view(&*map, center, radius, FovShape::CirclePlus)
-            .filter(|(_, _, symmetric)| *symmetric)
-            .flat_map(|(x, y, _)| map.iter_entities_at(x, y))
-            .filter(|id| monsters.contains(*id) || players.contains(*id));
+        let vulnerabilities = world.borrow::<View<Vulnerabilities>>();
+
         let user_name = &names.get(user_id).0;
         let item_name = &names.get(item_id).0;
 
@@ -349,34 +443,46 @@ pub fn use_item(
                 if let Ok(ProvidesHealing { heal_amount }) = provides_healings.try_get(item_id) {
                     if stats.hp < stats.max_hp {
                         stats.hp = (stats.hp + heal_amount).min(stats.max_hp);
-                        msgs.add(format!(
-                            "{} heals {} for {} hp.",
-                            item_name, target_name, heal_amount,
-                        ));
+                        msgs.add_colored(
+                            format!(
+                                "{} heals {} for {} hp.",
+                                item_name, target_name, heal_amount,
+                            ),
+                            Color::GREEN,
+                        );
                     } else {
                         let amount = 2;
                         stats.hp += amount;
                         stats.max_hp += amount;
-                        msgs.add(format!(
-                            "{} grants {} max hp to {}.",
-                            item_name, amount, target_name,
-                        ));
+                        msgs.add_colored(
+                            format!("{} grants {} max hp to {}.", item_name, amount, target_name,),
+                            Color::GREEN,
+                        );
                     }
                 }
 
-                if let Ok(InflictsDamage { damage }) = inflicts_damages.try_get(item_id) {
-                    stats.hp -= damage;
-                    entities.add_component(&mut hurt_bys, HurtBy::Someone(user_id), target_id);
-                    if let Ok(user_tally) = (&mut tallies).try_get(user_id) {
-                        user_tally.damage_dealt += *damage.max(&0) as u64;
-                    }
-                    if let Ok(target_tally) = (&mut tallies).try_get(target_id) {
-                        target_tally.damage_taken += *damage.max(&0) as u64;
-                    }
-                    msgs.add(format!(
-                        "{} hits {} for {} hp.",
-                        item_name, target_name, damage,
-                    ));
+                if let Ok(InflictsDamage {
+                    damage,
+                    damage_type,
+                }) = inflicts_damages.try_get(item_id)
+                {
+                    let damage = damage::scale_damage_by_type(
+                        *damage,
+                        *damage_type,
+                        target_id,
+                        &resistances,
+                        &vulnerabilities,
+                    );
+
+                    damage_queue.push(target_id, damage, HurtBy::Someone(user_id));
+
+                    damage_queue.push_message(
+                        target_id,
+                        format!("{} hits {} for {} hp.", item_name, target_name, damage),
+                        players
+                            .contains(target_id)
+                            .then_some(message::WARNING_COLOR),
+                    );
                 }
 
                 if let Ok(InflictsSleep { sleepiness }) = inflicts_sleeps.try_get(item_id) {
@@ -390,6 +496,17 @@ pub fn use_item(
                     );
                     msgs.add(format!("{} sends {} to sleep.", item_name, target_name));
                 }
+
+                if let Ok(InflictsConfusion { turns }) = inflicts_confusions.try_get(item_id) {
+                    if monsters.contains(target_id) {
+                        entities.add_component(
+                            &mut confusions,
+                            Confusion { turns: *turns },
+                            target_id,
+                        );
+                        msgs.add(format!("{} confuses {}.", item_name, target_name));
+                    }
+                }
             }
         }
     }
@@ -402,6 +519,90 @@ pub fn use_item(
     false
 }
 
+/// Throw an item from an inventory towards a target position.  The item flies in a straight line,
+/// hitting the first monster or player in its path and landing where it stops, short of any wall.
+pub fn throw_item(world: &World, thrower_id: EntityId, item_id: EntityId, target: (i32, i32)) {
+    let start: (i32, i32) = world.borrow::<View<Coord>>().get(thrower_id).0.into();
+    let (land_pos, hit_id) = {
+        let map = world.borrow::<UniqueView<Map>>();
+        let monsters = world.borrow::<View<Monster>>();
+        let players = world.borrow::<View<Player>>();
+        let mut land_pos = start;
+        let mut hit_id = None;
+
+        for pos in vision::line_between(start, target).into_iter().skip(1) {
+            if map.wall_or_oob(pos.0, pos.1) {
+                break;
+            }
+
+            land_pos = pos;
+            hit_id = map
+                .iter_entities_at(pos.0, pos.1)
+                .find(|id| *id != thrower_id && (monsters.contains(*id) || players.contains(*id)));
+            if hit_id.is_some() {
+                break;
+            }
+        }
+
+        (land_pos, hit_id)
+    };
+
+    remove_item_from_inventory(world, thrower_id, item_id);
+
+    // A disguised monster that gets hit gives itself away before the hit is announced.
+    if let Some(hit_id) = hit_id {
+        monster::reveal_disguise(world, hit_id);
+    }
+
+    {
+        let mut msgs = world.borrow::<UniqueViewMut<Messages>>();
+        let names = world.borrow::<View<Name>>();
+        let proper_nouns = world.borrow::<View<ProperNoun>>();
+        let thrower_name =
+            message::capitalize(&message::the_name_of(&names, &proper_nouns, thrower_id));
+        let item_name = message::the_name_of(&names, &proper_nouns, item_id);
+
+        if let Some(hit_id) = hit_id {
+            let damage = damage::scale_damage_by_type(
+                world
+                    .borrow::<View<ThrowDamage>>()
+                    .try_get(item_id)
+                    .map_or(DEFAULT_THROW_DAMAGE, |td| td.0),
+                DamageType::Physical,
+                hit_id,
+                &world.borrow::<View<Resistances>>(),
+                &world.borrow::<View<Vulnerabilities>>(),
+            );
+
+            let mut damage_queue = world.borrow::<UniqueViewMut<DamageQueue>>();
+
+            damage_queue.push(hit_id, damage, HurtBy::Someone(thrower_id));
+            damage_queue.push_message(
+                hit_id,
+                format!(
+                    "{} throws {} at {} for {} hp.",
+                    thrower_name,
+                    item_name,
+                    message::the_name_of(&names, &proper_nouns, hit_id),
+                    damage,
+                ),
+                world
+                    .borrow::<View<Player>>()
+                    .contains(hit_id)
+                    .then_some(message::WARNING_COLOR),
+            );
+        } else {
+            msgs.add(format!("{} throws {}.", thrower_name, item_name));
+        }
+    }
+
+    add_item_to_map(world, item_id, land_pos);
+}
+
+pub fn is_confused(world: &World, who: EntityId) -> bool {
+    world.borrow::<View<Confusion>>().contains(who)
+}
+
 pub fn is_asleep(world: &World, who: EntityId) -> bool {
     world.borrow::<View<Asleep>>().contains(who)
 }
@@ -409,7 +610,7 @@ pub fn is_asleep(world: &World, who: EntityId) -> bool {
 pub fn handle_sleep_turn(world: &World, who: EntityId) {
     let mut asleeps = world.borrow::<ViewMut<Asleep>>();
 
-    if let Ok(mut asleep) = (&mut asleeps).try_get(who) {
+    if let Ok(asleep) = (&mut asleeps).try_get(who) {
         let (mut msgs, player_id, combat_stats, coords, fovs, names) = world.borrow::<(
             UniqueViewMut<Messages>,
             UniqueView<PlayerId>,
@@ -449,3 +650,76 @@ pub fn handle_sleep_turn(world: &World, who: EntityId) {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{gamesym::GameSym, spawn};
+
+    /// Put a new throwable item that deals the given damage into an inventory.
+    fn give_throwable(world: &World, holder_id: EntityId, damage: i32) -> EntityId {
+        let item_id = spawn::spawn_item_kind(world, GameSym::Ration, (0, 0)).unwrap();
+
+        remove_item_from_map(world, item_id);
+        add_item_to_inventory(world, holder_id, item_id);
+        world.borrow::<EntitiesView>().add_component(
+            &mut world.borrow::<ViewMut<ThrowDamage>>(),
+            ThrowDamage(damage),
+            item_id,
+        );
+
+        item_id
+    }
+
+    #[test]
+    fn thrown_items_are_resisted_like_other_damage() {
+        let world = spawn::test_world(20, 20, (5, 5));
+        let player_id = world.borrow::<UniqueView<PlayerId>>().0;
+        let ghost_id = spawn::spawn_monster_kind(&world, GameSym::Ghost, (9, 5)).unwrap();
+        let item_id = give_throwable(&world, player_id, 8);
+
+        throw_item(&world, player_id, item_id, (9, 5));
+
+        assert_eq!(
+            world
+                .borrow::<UniqueView<DamageQueue>>()
+                .pending_for(ghost_id),
+            4
+        );
+    }
+
+    #[test]
+    fn thrown_items_hit_their_target_and_land_where_it_stands() {
+        let world = spawn::test_world(20, 20, (5, 5));
+        let player_id = world.borrow::<UniqueView<PlayerId>>().0;
+        let goblin_id = spawn::spawn_monster_kind(&world, GameSym::Goblin, (9, 7)).unwrap();
+        let item_id = give_throwable(&world, player_id, 3);
+
+        throw_item(&world, player_id, item_id, (9, 7));
+
+        let coords = world.borrow::<View<Coord>>();
+        let inventories = world.borrow::<View<Inventory>>();
+
+        assert_eq!(
+            world
+                .borrow::<UniqueView<DamageQueue>>()
+                .pending_for(goblin_id),
+            3
+        );
+        assert_eq!(coords.get(item_id).0.x, 9);
+        assert_eq!(coords.get(item_id).0.y, 7);
+        assert!(world
+            .borrow::<UniqueView<Map>>()
+            .iter_entities_at(9, 7)
+            .any(|id| id == item_id));
+        assert!(!inventories.get(player_id).items.contains(&item_id));
+
+        world.run(damage::apply_damage_queue);
+        let goblin_stats = world.borrow::<View<CombatStats>>();
+
+        assert_eq!(
+            goblin_stats.get(goblin_id).hp,
+            goblin_stats.get(goblin_id).max_hp - 3
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use sdl2::{event::Event, keyboard::Keycode};
+use sdl2::{event::Event, keyboard::Keycode, mouse::MouseButton};
 use std::collections::VecDeque;
 
 /// Input events buffered by and emitted from an [InputBuffer].
@@ -7,6 +7,8 @@ pub enum InputEvent {
     AppQuit,
     Press(Keycode),
     Release(Keycode),
+    /// The left mouse button was clicked at the given pixel position in the window.
+    Click(i32, i32),
 }
 
 bitflags! {
@@ -82,6 +84,12 @@ impl InputBuffer {
             Event::KeyUp {
                 keycode: Some(key), ..
             } => self.buffer.push_back(InputEvent::Release(*key)),
+            Event::MouseButtonDown {
+                mouse_btn: MouseButton::Left,
+                x,
+                y,
+                ..
+            } => self.buffer.push_back(InputEvent::Click(*x, *y)),
             Event::Quit { .. } => self.buffer.push_back(InputEvent::AppQuit),
             _ => {}
         }
@@ -9,6 +9,12 @@ use super::BoundedMap;
 pub trait PathableMap {
     /// Returns `true` if the tile at the given coordinates is blocked.
     fn is_blocked(&self, x: i32, y: i32) -> bool;
+
+    /// Returns `true` if a diagonal step from the given coordinates by `dx` and `dy` isn't allowed,
+    /// even if the tile being stepped to isn't blocked.  Every diagonal step is allowed by default.
+    fn is_diagonal_blocked(&self, _x: i32, _y: i32, _dx: i32, _dy: i32) -> bool {
+        false
+    }
 }
 
 /// Iterator that steps through each tile in a path found by [find_path].
@@ -121,7 +127,9 @@ fn a_star<T: BoundedMap + PathableMap>(
             if next_x >= min_x && next_x <= max_x && next_y >= min_y && next_y <= max_y {
                 let next = (next_x, next_y);
 
-                if next == dest || !map.is_blocked(next_x, next_y) {
+                if (i < 4 || !map.is_diagonal_blocked(current.0, current.1, *dx, *dy))
+                    && (next == dest || !map.is_blocked(next_x, next_y))
+                {
                     let next_cost = current_cost + if i >= 4 { 141 } else { 100 };
 
                     if next_cost < *cost_so_far.get(&next).unwrap_or(&i32::MAX) {
@@ -25,7 +25,8 @@ pub enum RunControl {
 pub struct RunSettings<Y: Symbol> {
     /// Window title.
     pub title: String,
-    /// Initial pixel width and height of the window.
+    /// Initial pixel width and height of the window, clamped to the minimum window size and the
+    /// size of the display.
     pub window_size: Size,
     /// Minimum pixel width and height of the window.
     pub min_window_size: Size,
@@ -38,7 +39,9 @@ pub struct RunSettings<Y: Symbol> {
 /// Create a window and run a main event loop that calls `update` repeatedly.
 ///
 /// `update` should return a [RunControl] enum variant to control the loop behavior.
-pub fn run<U, Y>(settings: RunSettings<Y>, mut update: U)
+///
+/// Returns the size of the window when the loop ends, so that it can be restored later.
+pub fn run<U, Y>(settings: RunSettings<Y>, mut update: U) -> Size
 where
     U: FnMut(&mut InputBuffer, &mut Vec<TileGridLayer<Y>>, &[Tileset<Y>], Size) -> RunControl,
     Y: Symbol,
@@ -47,18 +50,29 @@ where
     let video_subsystem = sdl_context.video().unwrap();
     let _image_context = sdl2::image::init(sdl2::image::InitFlag::PNG).unwrap();
 
-    assert!(settings.window_size.w > 0 && settings.window_size.w <= i32::MAX as u32);
-    assert!(settings.window_size.h > 0 && settings.window_size.h <= i32::MAX as u32);
     assert!(settings.min_window_size.w > 0 && settings.min_window_size.w <= i32::MAX as u32);
     assert!(settings.min_window_size.h > 0 && settings.min_window_size.h <= i32::MAX as u32);
-    assert!(settings.window_size.w >= settings.min_window_size.w);
-    assert!(settings.window_size.h >= settings.min_window_size.h);
+
+    // Shrink the initial window size to fit the display, but no smaller than the minimum size.
+    let mut initial_window_size = settings.window_size;
+    if let Ok(display_mode) = video_subsystem.desktop_display_mode(0) {
+        if display_mode.w > 0 && display_mode.h > 0 {
+            initial_window_size.w = initial_window_size.w.min(display_mode.w as u32);
+            initial_window_size.h = initial_window_size.h.min(display_mode.h as u32);
+        }
+    }
+    initial_window_size.w = initial_window_size
+        .w
+        .clamp(settings.min_window_size.w, i32::MAX as u32);
+    initial_window_size.h = initial_window_size
+        .h
+        .clamp(settings.min_window_size.h, i32::MAX as u32);
 
     let mut window = video_subsystem
         .window(
             &settings.title,
-            settings.window_size.w,
-            settings.window_size.h,
+            initial_window_size.w,
+            initial_window_size.h,
         )
         .resizable()
         .position_centered()
@@ -66,7 +80,7 @@ where
         .unwrap();
 
     window
-        .set_minimum_size(settings.window_size.w, settings.window_size.h)
+        .set_minimum_size(settings.min_window_size.w, settings.min_window_size.h)
         .unwrap();
 
     let mut canvas = window.into_canvas().build().unwrap();
@@ -264,4 +278,6 @@ where
             std::thread::sleep(frame_time - elapsed);
         }
     }
+
+    canvas.window().size().into()
 }
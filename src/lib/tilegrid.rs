@@ -1015,8 +1015,8 @@ impl<'b, 'r, Y: Symbol> TileGrid<'b, 'r, Y> {
         };
 
         let grid_width = self.front.size.w as i32;
-        let cell_width = tileset.tile_size.w as u32;
-        let cell_height = tileset.tile_size.h as u32;
+        let cell_width = tileset.tile_size.w;
+        let cell_height = tileset.tile_size.h;
 
         // Check the grid for positions to (re)render and (re)render them.
         for (i, (fcell, bcell)) in self
@@ -1,7 +1,13 @@
-///! Arbitrary constants to seed hashers whose output is in turn used to seed RNGs.
+//! Arbitrary constants to seed hashers whose output is in turn used to seed RNGs.
 
 pub const GENERATE_ROOMS_AND_CORRIDORS: u64 = 0x3fdc77fb4d7f5d2f;
 pub const SPAWN_GUARANTEED_WEAPON: u64 = 0x67caf3e7b16e9df2;
 pub const SPAWN_GUARANTEED_ARMOR: u64 = 0x74e90549dbcadfd0;
 pub const FILL_ROOM_WITH_SPAWNS: u64 = 0xd85af3d2cf6dcbc5;
 pub const MELEE_ATTACK: u64 = 0x258890651a33d5d;
+pub const SPAWN_WANDERING_MONSTER: u64 = 0x9e3b5c07a41f62d8;
+pub const LIGHT_ROOMS: u64 = 0x5b1f0e8c2d47a936;
+pub const STUMBLE_RANDOMLY: u64 = 0x86f4d21b9c3e07a5;
+pub const SEED_FROM_TEXT: u64 = 0xc3a8e1947f02b65d;
+#[cfg(test)]
+pub const WORLD_HASH: u64 = 0x1b6d93e0f4a7c258;
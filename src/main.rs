@@ -1,10 +1,13 @@
+mod animation;
 mod bitgrid;
 mod chunked;
 mod components;
+mod config;
 mod damage;
 mod experience;
 mod gamekey;
 mod gamesym;
+mod highscore;
 mod hunger;
 mod item;
 mod magicnum;
@@ -17,16 +20,23 @@ mod player;
 mod render;
 mod saveload;
 mod spawn;
+#[cfg(test)]
+mod statehash;
+mod status;
 mod ui;
+mod undo;
 mod vision;
 
 use serde::{Deserialize, Serialize};
-use shipyard::World;
+use shipyard::{UniqueView, World};
 use std::{collections::HashMap, path::PathBuf};
 
 use crate::{
+    animation::Animations,
     chunked::Camera,
+    damage::DamageQueue,
     experience::Difficulty,
+    gamekey::KeyBindings,
     gamesym::GameSym,
     item::PickUpHint,
     map::Map,
@@ -63,20 +73,23 @@ fn main() {
         .and_then(|arg| arg.as_str().parse().ok())
         .unwrap_or_else(rand::random);
 
-    world.add_unique(Options {
-        tileset: 2,
-        font: 0,
-        map_zoom: 1,
-        text_zoom: 1,
-    });
+    let mut config = config::load_config();
+
+    // Keep a hand-edited config file from breaking anything built from the options.
+    config.options.clamp_to_limits();
+
+    world.add_unique(config.options.clone());
+    world.add_unique(KeyBindings::from_names(&config.key_bindings));
     world.add_unique(GameSeed(game_seed));
     world.add_unique(TurnCount(0));
     world.add_unique(Wins(0));
     world.add_unique(BaseEquipmentLevel(0));
+    world.add_unique(Animations::new());
     world.add_unique(Camera::new());
+    world.add_unique(DamageQueue::new());
     world.add_unique(Difficulty::new(world.run(spawn::spawn_difficulty)));
     world.add_unique(MenuMemory::new());
-    world.add_unique(Messages::new(100));
+    world.add_unique(Messages::new(1000, 100));
     world.add_unique(Map::new(80, 50));
     world.add_unique(PickUpHint(true));
     world.add_unique(PlayerId(world.run(spawn::spawn_player)));
@@ -87,7 +100,7 @@ fn main() {
 
     let settings = RunSettings {
         title: "RuggRogue".into(),
-        window_size: (896, 560).into(),
+        window_size: config.window_size.unwrap_or((896, 560)).into(),
         min_window_size: (640, 192).into(),
         fps: 30,
         tileset_infos: vec![
@@ -111,10 +124,18 @@ fn main() {
         ],
     };
 
-    ruggrogue::run(settings, |inputs, layers, tilesets, window_size| {
+    let final_window_size = ruggrogue::run(settings, |inputs, layers, tilesets, window_size| {
         mode_stack.update(&world, inputs, layers, tilesets, window_size)
     });
 
+    // Remember the window size, key bindings and options for next time.
+    config.window_size = Some((final_window_size.w, final_window_size.h));
+    config.key_bindings = world.borrow::<UniqueView<KeyBindings>>().to_names();
+    config.options = world.borrow::<UniqueView<Options>>().clone();
+    if let Err(e) = config::save_config(&config) {
+        eprintln!("Warning: config::save_config: {}", e);
+    }
+
     #[cfg(target_os = "emscripten")]
     unsafe {
         ruggrogue_sync_idbfs();
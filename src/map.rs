@@ -2,20 +2,70 @@ use rand::{seq::IteratorRandom, Rng, SeedableRng};
 use rand_xoshiro::Xoshiro128PlusPlus as GameRng;
 use serde::{Deserialize, Serialize};
 use shipyard::{EntityId, Get, UniqueView, UniqueViewMut, View, ViewMut, World};
-use std::{collections::HashMap, hash::Hasher};
+use std::{
+    collections::{HashMap, VecDeque},
+    hash::Hasher,
+};
 use wyhash::WyHash;
 
 use crate::{
     bitgrid::BitGrid,
-    components::{Coord, Experience, FieldOfView, Item, Monster, Name, Player},
+    components::{
+        CombatStats, Coord, Disguise, Experience, FieldOfView, Item, Monster, Name, Player,
+    },
     experience::Difficulty,
     gamesym::GameSym,
     magicnum,
     player::PlayerId,
+    saveload::LoadError,
+    ui::{MapGenerator, Options, WallStyle},
     GameSeed,
 };
 use ruggrogue::util::Color;
 
+/// Version of the saved form of [Map]; bump this and handle the old version in [Map::migrate]
+/// whenever the saved form changes.
+const MAP_FORMAT_VERSION: u32 = 3;
+
+/// Smallest width of a piece of the map that the BSP generator carves a room in.
+const BSP_MIN_LEAF_WIDTH: i32 = 10;
+
+/// Smallest height of a piece of the map that the BSP generator carves a room in.
+const BSP_MIN_LEAF_HEIGHT: i32 = 8;
+
+/// Pieces of the map within this width and height may be left unsplit by the BSP generator.
+const BSP_MAX_LEAF_WIDTH: i32 = 20;
+const BSP_MAX_LEAF_HEIGHT: i32 = 14;
+
+/// Percent chance of each tile of a cave level starting out as a wall before being smoothed.
+const CAVE_WALL_PERCENT: u32 = 45;
+
+/// Number of times that a cave level is smoothed out into open areas after being randomized.
+const CAVE_SMOOTHING_STEPS: usize = 5;
+
+/// Size of the square blocks that a cave level is divided into to find pseudo-rooms in.
+const CAVE_BLOCK_SIZE: i32 = 10;
+
+/// Smallest area of open floor in a block of a cave level that is treated as a room.
+const CAVE_MIN_ROOM_AREA: i32 = 9;
+
+/// Ambient light that leaves tile colors as they are.
+const NEUTRAL_LIGHT: Color = Color::WHITE;
+
+/// Ambient light of a cold, crypt-like room.
+const COLD_LIGHT: Color = Color {
+    r: 160,
+    g: 190,
+    b: 255,
+};
+
+/// Ambient light of a warm, forge-like room.
+const WARM_LIGHT: Color = Color {
+    r: 255,
+    g: 190,
+    b: 140,
+};
+
 #[derive(Clone, Copy, Eq, PartialEq, Deserialize, Serialize)]
 pub enum Tile {
     #[serde(rename = "F")]
@@ -69,6 +119,11 @@ impl Rect {
             && other.y1 - margin <= self.y2
     }
 
+    /// Number of tiles covered by this Rect.
+    pub fn area(&self) -> i32 {
+        (self.x2 - self.x1 + 1) * (self.y2 - self.y1 + 1)
+    }
+
     pub fn center(&self) -> (i32, i32) {
         (
             (self.x2 - self.x1) / 2 + self.x1,
@@ -76,6 +131,29 @@ impl Rect {
         )
     }
 
+    /// Returns true if the given position lies within this Rect.
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        x >= self.x1 && x <= self.x2 && y >= self.y1 && y <= self.y2
+    }
+
+    /// Returns a copy of this Rect grown by `n` tiles on every side.  A negative `n` shrinks it
+    /// instead, but never below a single row or column about its center.
+    pub fn expand(&self, n: i32) -> Rect {
+        let (center_x, center_y) = self.center();
+        let (x1, x2) = if self.x1 - n <= self.x2 + n {
+            (self.x1 - n, self.x2 + n)
+        } else {
+            (center_x, center_x)
+        };
+        let (y1, y2) = if self.y1 - n <= self.y2 + n {
+            (self.y1 - n, self.y2 + n)
+        } else {
+            (center_y, center_y)
+        };
+
+        Rect { x1, y1, x2, y2 }
+    }
+
     pub fn iter_xy(&self) -> impl Iterator<Item = (i32, i32)> + '_ {
         (self.y1..=self.y2)
             .flat_map(move |y| std::iter::repeat(y).zip(self.x1..=self.x2))
@@ -83,15 +161,54 @@ impl Rect {
     }
 }
 
+/// The wall symbol for a mask of directions that a wall connects to, as made by `Map::wall_mask`.
+fn wall_mask_sym(mask: u8) -> GameSym {
+    match mask {
+        0 => GameSym::WallPillar,
+        1 => GameSym::WallN,
+        2 => GameSym::WallS,
+        3 => GameSym::WallNs,
+        4 => GameSym::WallW,
+        5 => GameSym::WallNw,
+        6 => GameSym::WallSw,
+        7 => GameSym::WallNsw,
+        8 => GameSym::WallE,
+        9 => GameSym::WallNe,
+        10 => GameSym::WallEs,
+        11 => GameSym::WallNes,
+        12 => GameSym::WallEw,
+        13 => GameSym::WallNew,
+        14 => GameSym::WallEsw,
+        15 => GameSym::WallNesw,
+        _ => GameSym::WallOther,
+    }
+}
+
+/// A note left by the player on a tile of the map.
+#[derive(Deserialize, Serialize)]
+pub struct MapNote {
+    pub x: i32,
+    pub y: i32,
+    pub text: String,
+}
+
 #[derive(Deserialize, Serialize)]
 pub struct Map {
+    // Maps saved before versioning was introduced lack this, so they're treated as version 0.
+    #[serde(default)]
+    format_version: u32,
     pub depth: i32,
     pub width: i32,
     pub height: i32,
     #[serde(with = "crate::saveload::run_length_encoded")]
     tiles: Vec<Tile>,
     pub rooms: Vec<Rect>,
+    /// Ambient light color of each room in `rooms`, tinting the room and its walls.
+    #[serde(default)]
+    room_lights: Vec<Color>,
     pub seen: BitGrid,
+    #[serde(default)]
+    notes: Vec<MapNote>,
 
     // (x, y) -> (blocking_entity_count, entities_here)
     #[serde(skip)]
@@ -107,34 +224,61 @@ impl Map {
         assert!(width > 0 && height > 0);
 
         Self {
+            format_version: MAP_FORMAT_VERSION,
             depth: 0,
             width,
             height,
             tiles: vec![Tile::Floor; (width * height) as usize],
             rooms: Vec::new(),
+            room_lights: Vec::new(),
             seen: BitGrid::new(width, height),
+            notes: Vec::new(),
             tile_entities: HashMap::new(),
             empty_entity_vecs: Vec::new(),
         }
     }
 
     pub fn replace(&mut self, replacement: Self) {
+        self.format_version = replacement.format_version;
         self.depth = replacement.depth;
         self.width = replacement.width;
         self.height = replacement.height;
         self.tiles = replacement.tiles;
         self.rooms = replacement.rooms;
+        self.room_lights = replacement.room_lights;
         self.seen = replacement.seen;
+        self.notes = replacement.notes;
         self.tile_entities = replacement.tile_entities;
         self.empty_entity_vecs = replacement.empty_entity_vecs;
     }
 
+    /// Bring a freshly-loaded map up to the current format version, rejecting maps from versions
+    /// newer than this one instead of misreading them.
+    pub fn migrate(&mut self) -> Result<(), LoadError> {
+        if self.format_version > MAP_FORMAT_VERSION {
+            return Err(LoadError::UnsupportedVersion("Map", self.format_version));
+        }
+
+        // Version 0 maps only differ from version 1 maps by lacking a version number.
+        // Version 1 maps lack room lights, so light their rooms neutrally.
+        if self.format_version < 2 {
+            self.room_lights = vec![NEUTRAL_LIGHT; self.rooms.len()];
+        }
+        // Version 2 maps lack notes, which are safely left empty.
+
+        self.format_version = MAP_FORMAT_VERSION;
+
+        Ok(())
+    }
+
     pub fn clear(&mut self) {
         self.tiles.clear();
         self.tiles
             .resize((self.width * self.height) as usize, Tile::Floor);
         self.rooms.clear();
+        self.room_lights.clear();
         self.seen.zero_out_bits();
+        self.notes.clear();
         self.tile_entities.clear();
     }
 
@@ -189,6 +333,12 @@ impl Map {
         }
     }
 
+    /// Returns true if stepping diagonally from the given position squeezes between two walls that
+    /// meet at a corner.
+    pub fn squeezes_between_walls(&self, x: i32, y: i32, dx: i32, dy: i32) -> bool {
+        dx != 0 && dy != 0 && self.wall_or_oob(x + dx, y) && self.wall_or_oob(x, y + dy)
+    }
+
     #[inline]
     pub fn wall_or_oob(&self, x: i32, y: i32) -> bool {
         x < 0
@@ -198,8 +348,10 @@ impl Map {
             || matches!(self.get_tile(x, y), Tile::Wall)
     }
 
+    /// The directions that a wall should extend its stems in to connect with neighboring walls,
+    /// as a mask of north = 1, south = 2, west = 4 and east = 8.
     #[allow(clippy::many_single_char_names)]
-    fn wall_sym(&self, x: i32, y: i32) -> GameSym {
+    fn wall_mask(&self, x: i32, y: i32) -> u8 {
         let n = self.wall_or_oob(x, y - 1);
         let s = self.wall_or_oob(x, y + 1);
         let e = self.wall_or_oob(x + 1, y);
@@ -226,33 +378,54 @@ impl Map {
             mask += 8;
         }
 
-        match mask {
-            0 => GameSym::WallPillar,
-            1 => GameSym::WallN,
-            2 => GameSym::WallS,
-            3 => GameSym::WallNs,
-            4 => GameSym::WallW,
-            5 => GameSym::WallNw,
-            6 => GameSym::WallSw,
-            7 => GameSym::WallNsw,
-            8 => GameSym::WallE,
-            9 => GameSym::WallNe,
-            10 => GameSym::WallEs,
-            11 => GameSym::WallNes,
-            12 => GameSym::WallEw,
-            13 => GameSym::WallNew,
-            14 => GameSym::WallEsw,
-            15 => GameSym::WallNesw,
-            _ => GameSym::WallOther,
+        mask
+    }
+
+    fn wall_sym(&self, x: i32, y: i32, wall_style: WallStyle) -> GameSym {
+        match wall_style {
+            WallStyle::Lines => wall_mask_sym(self.wall_mask(x, y)),
+            WallStyle::Hash => GameSym::WallOther,
+            WallStyle::Block => GameSym::WallBlock,
+        }
+    }
+
+    /// Ambient light color at the given position, taken from the first room that covers it.
+    pub fn light_at(&self, x: i32, y: i32) -> Color {
+        self.rooms
+            .iter()
+            .zip(self.room_lights.iter())
+            .find(|(room, _)| room.expand(1).contains(x, y))
+            .map_or(NEUTRAL_LIGHT, |(_, light)| *light)
+    }
+
+    /// The text of the player's note at the given position, if any.
+    pub fn note_at(&self, x: i32, y: i32) -> Option<&str> {
+        self.notes
+            .iter()
+            .find(|note| note.x == x && note.y == y)
+            .map(|note| note.text.as_str())
+    }
+
+    /// Leave a note at the given position, replacing any note already there.  Empty text removes
+    /// the note instead.
+    pub fn set_note(&mut self, x: i32, y: i32, text: String) {
+        self.notes.retain(|note| note.x != x || note.y != y);
+        if !text.is_empty() {
+            self.notes.push(MapNote { x, y, text });
         }
     }
 
+    pub fn iter_notes(&self) -> impl Iterator<Item = &MapNote> {
+        self.notes.iter()
+    }
+
     pub fn iter_bounds(
         &self,
         x1: i32,
         y1: i32,
         x2: i32,
         y2: i32,
+        wall_style: WallStyle,
     ) -> impl Iterator<Item = (i32, i32, Option<(GameSym, Color)>)> + '_ {
         let ys = if y1 <= y2 { y1..=y2 } else { y2..=y1 };
 
@@ -276,7 +449,7 @@ impl Map {
                             },
                         ),
                         Tile::Wall => (
-                            self.wall_sym(x, y),
+                            self.wall_sym(x, y, wall_style),
                             Color {
                                 r: 134,
                                 g: 77,
@@ -380,15 +553,23 @@ impl Map {
                 fovs.get(player_id.0).get((x, y))
             };
 
-            if in_player_fov {
+            let (desc, recalled) = if in_player_fov {
                 let names = world.borrow::<View<Name>>();
                 let mut desc_vec = Vec::new();
 
-                if let Some(monster) = self
-                    .iter_entities_at(x, y)
-                    .find(|id| world.borrow::<View<Monster>>().contains(*id))
-                {
-                    desc_vec.push(names.get(monster).0.clone());
+                if let Some(monster) = self.iter_entities_at(x, y).find(|id| {
+                    world.borrow::<View<Monster>>().contains(*id)
+                        && !world.borrow::<View<Disguise>>().contains(*id)
+                }) {
+                    let name = &names.get(monster).0;
+
+                    if let Ok(stats) = world.borrow::<View<CombatStats>>().try_get(monster) {
+                        let exact = world.borrow::<UniqueView<Options>>().show_exact_hp;
+
+                        desc_vec.push(format!("{} ({})", name, describe_health(stats, exact)));
+                    } else {
+                        desc_vec.push(name.clone());
+                    }
                 }
 
                 if !omit_player {
@@ -401,9 +582,11 @@ impl Map {
                 }
 
                 if desc_vec.is_empty() || !focus_on_target {
-                    let mut items_at_pos = self
-                        .iter_entities_at(x, y)
-                        .filter(|id| world.borrow::<View<Item>>().contains(*id));
+                    let mut items_at_pos = self.iter_entities_at(x, y).filter(|id| {
+                        // Disguised monsters pass themselves off as items.
+                        world.borrow::<View<Item>>().contains(*id)
+                            || world.borrow::<View<Disguise>>().contains(*id)
+                    });
 
                     if let Some(item) = items_at_pos.next() {
                         let more_items_count = items_at_pos.count();
@@ -428,6 +611,12 @@ impl Map {
                 (desc_vec.join(", "), false)
             } else {
                 (self.get_tile(x, y).to_string(), true)
+            };
+
+            if let Some(note) = self.note_at(x, y) {
+                (format!("{} \"{}\"", desc, note), recalled)
+            } else {
+                (desc, recalled)
             }
         } else {
             ("nothing".to_string(), true)
@@ -453,30 +642,74 @@ impl ruggrogue::PathableMap for Map {
             || self
                 .tile_entities
                 .get(&(x, y))
-                .map_or(false, |(block_count, _)| *block_count > 0)
+                .is_some_and(|(block_count, _)| *block_count > 0)
     }
 }
 
-/// Returns the position to spawn the victory item if the game has progressed far enough.
-pub fn generate_rooms_and_corridors(
-    difficulty: UniqueView<Difficulty>,
-    game_seed: UniqueView<GameSeed>,
-    mut map: UniqueViewMut<Map>,
-    exps: View<Experience>,
-) -> Option<(i32, i32)> {
-    {
-        let w = map.width;
-        let h = map.height;
-        map.set_rect(&Rect::new(0, 0, w, h), Tile::Wall);
+/// A map that paths through the same tiles as [Map], but can forbid squeezing diagonally between
+/// walls.
+pub struct StepMap<'a> {
+    pub map: &'a Map,
+    pub strict_diagonals: bool,
+}
+
+impl ruggrogue::BoundedMap for StepMap<'_> {
+    fn bounds(&self) -> (i32, i32, i32, i32) {
+        self.map.bounds()
     }
+}
 
-    let mut rng = {
-        let mut hasher = WyHash::with_seed(magicnum::GENERATE_ROOMS_AND_CORRIDORS);
-        hasher.write_u64(game_seed.0);
-        hasher.write_i32(map.depth);
-        GameRng::seed_from_u64(hasher.finish())
+impl ruggrogue::PathableMap for StepMap<'_> {
+    fn is_blocked(&self, x: i32, y: i32) -> bool {
+        self.map.is_blocked(x, y)
+    }
+
+    fn is_diagonal_blocked(&self, x: i32, y: i32, dx: i32, dy: i32) -> bool {
+        self.strict_diagonals && self.map.squeezes_between_walls(x, y, dx, dy)
+    }
+}
+
+/// Describe how hurt something is, either roughly or with exact hit points.
+fn describe_health(stats: &CombatStats, exact: bool) -> String {
+    if exact {
+        return format!("{}/{} HP", stats.hp, stats.max_hp);
+    }
+
+    let percent = if stats.max_hp > 0 {
+        stats.hp * 100 / stats.max_hp
+    } else {
+        0
     };
 
+    if stats.hp >= stats.max_hp {
+        "unhurt"
+    } else if percent > 66 {
+        "lightly wounded"
+    } else if percent > 33 {
+        "wounded"
+    } else if percent > 10 {
+        "badly wounded"
+    } else {
+        "near death"
+    }
+    .to_string()
+}
+
+/// Carve an L-shaped corridor between the centers of two rooms.
+fn connect_rooms(map: &mut Map, r1: usize, r2: usize, h_then_v: bool) {
+    let (r1x, r1y) = map.rooms[r1].center();
+    let (r2x, r2y) = map.rooms[r2].center();
+    if h_then_v {
+        map.set_hline(r2x, r1x, r2y, Tile::Floor);
+        map.set_vline(r2y, r1y, r1x, Tile::Floor);
+    } else {
+        map.set_vline(r2y, r1y, r2x, Tile::Floor);
+        map.set_hline(r2x, r1x, r1y, Tile::Floor);
+    }
+}
+
+/// Scatter non-overlapping rooms across the map and connect them with corridors.
+fn carve_rooms_and_corridors(map: &mut Map, rng: &mut GameRng) {
     for _ in 0..30 {
         let w: i32 = rng.gen_range(6i32..15i32);
         let h: i32 = rng.gen_range(6i32..11i32);
@@ -490,17 +723,6 @@ pub fn generate_rooms_and_corridors(
         }
     }
 
-    let connect_rooms = |map: &mut UniqueViewMut<Map>, r1: usize, r2: usize, h_then_v: bool| {
-        let (r1x, r1y) = map.rooms[r1].center();
-        let (r2x, r2y) = map.rooms[r2].center();
-        if h_then_v {
-            map.set_hline(r2x, r1x, r2y, Tile::Floor);
-            map.set_vline(r2y, r1y, r1x, Tile::Floor);
-        } else {
-            map.set_vline(r2y, r1y, r2x, Tile::Floor);
-            map.set_hline(r2x, r1x, r1y, Tile::Floor);
-        }
-    };
     let mut connected: Vec<usize> = Vec::new();
     let mut disconnected: Vec<usize> = Vec::new();
 
@@ -529,7 +751,7 @@ pub fn generate_rooms_and_corridors(
 
         // Connect the closest connected and disconnected rooms together.
         connect_rooms(
-            &mut map,
+            map,
             connected[closest_connected],
             disconnected[closest_disconnected],
             rng.gen::<bool>(),
@@ -547,11 +769,347 @@ pub fn generate_rooms_and_corridors(
 
     // Connect random extra rooms.
     for (extra_rooms, extra_corridor) in (0..map.rooms.len())
-        .choose_multiple(&mut rng, extra_corridors.len() * 2)
+        .choose_multiple(rng, extra_corridors.len() * 2)
         .chunks_exact(2)
         .zip(&extra_corridors)
     {
-        connect_rooms(&mut map, extra_rooms[0], extra_rooms[1], *extra_corridor);
+        connect_rooms(map, extra_rooms[0], extra_rooms[1], *extra_corridor);
+    }
+}
+
+/// Recursively split an area of the map in two until the pieces are small enough, then carve a
+/// room in each piece and connect every pair of sibling pieces with a corridor.
+///
+/// Returns the index of a room in the area for its parent to connect to.
+fn carve_bsp_area(map: &mut Map, rng: &mut GameRng, area: Rect) -> usize {
+    let w = area.x2 - area.x1 + 1;
+    let h = area.y2 - area.y1 + 1;
+    let can_split_x = w >= 2 * BSP_MIN_LEAF_WIDTH;
+    let can_split_y = h >= 2 * BSP_MIN_LEAF_HEIGHT;
+
+    if !(can_split_x || can_split_y)
+        || (w <= BSP_MAX_LEAF_WIDTH && h <= BSP_MAX_LEAF_HEIGHT && rng.gen_ratio(1, 3))
+    {
+        // Leave at least a tile of wall inside the edges of the area so neighboring rooms never
+        // touch.
+        let room_w = rng.gen_range(4..=(w - 2).min(14));
+        let room_h = rng.gen_range(4..=(h - 2).min(10));
+        let x = rng.gen_range((area.x1 + 1)..=(area.x2 - room_w));
+        let y = rng.gen_range((area.y1 + 1)..=(area.y2 - room_h));
+        let room = Rect::new(x, y, room_w, room_h);
+
+        map.set_rect(&room, Tile::Floor);
+        map.rooms.push(room);
+        return map.rooms.len() - 1;
+    }
+
+    let (first, second) = if can_split_x && (!can_split_y || w >= h) {
+        let cut = rng.gen_range(BSP_MIN_LEAF_WIDTH..=(w - BSP_MIN_LEAF_WIDTH));
+        (
+            Rect::new(area.x1, area.y1, cut, h),
+            Rect::new(area.x1 + cut, area.y1, w - cut, h),
+        )
+    } else {
+        let cut = rng.gen_range(BSP_MIN_LEAF_HEIGHT..=(h - BSP_MIN_LEAF_HEIGHT));
+        (
+            Rect::new(area.x1, area.y1, w, cut),
+            Rect::new(area.x1, area.y1 + cut, w, h - cut),
+        )
+    };
+    let first_room = carve_bsp_area(map, rng, first);
+    let second_room = carve_bsp_area(map, rng, second);
+
+    connect_rooms(map, first_room, second_room, rng.gen::<bool>());
+
+    if rng.gen::<bool>() {
+        first_room
+    } else {
+        second_room
+    }
+}
+
+/// Number of walls, counting the edge of the map, among the eight tiles around a position.
+fn count_wall_neighbors(map: &Map, x: i32, y: i32) -> usize {
+    (-1..=1)
+        .flat_map(|dy| (-1..=1).map(move |dx| (dx, dy)))
+        .filter(|&(dx, dy)| (dx, dy) != (0, 0) && map.wall_or_oob(x + dx, y + dy))
+        .count()
+}
+
+/// The largest rectangle of floor tiles within `area`, if any.
+fn largest_floor_rect(map: &Map, area: &Rect) -> Option<Rect> {
+    let w = area.x2 - area.x1 + 1;
+    let h = area.y2 - area.y1 + 1;
+    // Number of wall tiles above and to the left of each position, for counting walls in any
+    // rectangle quickly.
+    let mut walls = vec![0; ((w + 1) * (h + 1)) as usize];
+    let index = |x: i32, y: i32| (y * (w + 1) + x) as usize;
+
+    for y in 0..h {
+        for x in 0..w {
+            let wall = i32::from(map.wall_or_oob(area.x1 + x, area.y1 + y));
+            walls[index(x + 1, y + 1)] =
+                wall + walls[index(x, y + 1)] + walls[index(x + 1, y)] - walls[index(x, y)];
+        }
+    }
+
+    let mut best: Option<Rect> = None;
+
+    for y1 in 0..h {
+        for x1 in 0..w {
+            for y2 in (y1 + 1)..=h {
+                for x2 in (x1 + 1)..=w {
+                    let wall_count =
+                        walls[index(x2, y2)] - walls[index(x1, y2)] - walls[index(x2, y1)]
+                            + walls[index(x1, y1)];
+
+                    if wall_count == 0 && best.is_none_or(|b| (x2 - x1) * (y2 - y1) > b.area()) {
+                        best = Some(Rect::new(area.x1 + x1, area.y1 + y1, x2 - x1, y2 - y1));
+                    }
+                }
+            }
+        }
+    }
+
+    best
+}
+
+/// Carve out open caves by randomizing walls and smoothing them out, keeping only the largest
+/// connected cave.  The biggest open rectangles of the cave are treated as rooms for spawning.
+fn carve_caves(map: &mut Map, rng: &mut GameRng) {
+    let w = map.width;
+    let h = map.height;
+
+    for y in 1..(h - 1) {
+        for x in 1..(w - 1) {
+            if !rng.gen_ratio(CAVE_WALL_PERCENT, 100) {
+                map.set_tile(x, y, Tile::Floor);
+            }
+        }
+    }
+
+    for _ in 0..CAVE_SMOOTHING_STEPS {
+        let new_walls = (0..h)
+            .flat_map(|y| (0..w).map(move |x| (x, y)))
+            .map(|(x, y)| {
+                let neighbors = count_wall_neighbors(map, x, y);
+                neighbors >= 5 || (map.wall_or_oob(x, y) && neighbors >= 4)
+            })
+            .collect::<Vec<_>>();
+
+        for y in 1..(h - 1) {
+            for x in 1..(w - 1) {
+                let tile = if new_walls[(y * w + x) as usize] {
+                    Tile::Wall
+                } else {
+                    Tile::Floor
+                };
+                map.set_tile(x, y, tile);
+            }
+        }
+    }
+
+    // Label each cave reachable by cardinal steps, then fill in all but the largest one.
+    let mut labels = vec![0usize; (w * h) as usize];
+    let mut sizes = vec![0usize];
+
+    for start_y in 0..h {
+        for start_x in 0..w {
+            if map.wall_or_oob(start_x, start_y) || labels[(start_y * w + start_x) as usize] != 0 {
+                continue;
+            }
+
+            let label = sizes.len();
+            let mut size = 0;
+            let mut stack = vec![(start_x, start_y)];
+
+            labels[(start_y * w + start_x) as usize] = label;
+            while let Some((x, y)) = stack.pop() {
+                size += 1;
+                for (nx, ny) in [(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)] {
+                    if !map.wall_or_oob(nx, ny) && labels[(ny * w + nx) as usize] == 0 {
+                        labels[(ny * w + nx) as usize] = label;
+                        stack.push((nx, ny));
+                    }
+                }
+            }
+            sizes.push(size);
+        }
+    }
+
+    let largest = (1..sizes.len()).max_by_key(|&label| sizes[label]);
+
+    for y in 0..h {
+        for x in 0..w {
+            let label = labels[(y * w + x) as usize];
+            if label != 0 && Some(label) != largest {
+                map.set_tile(x, y, Tile::Wall);
+            }
+        }
+    }
+
+    // The cave left over is fully connected, so any rooms found in it are connected too.
+    for block_y in (0..h).step_by(CAVE_BLOCK_SIZE as usize) {
+        for block_x in (0..w).step_by(CAVE_BLOCK_SIZE as usize) {
+            let block = Rect::new(
+                block_x,
+                block_y,
+                CAVE_BLOCK_SIZE.min(w - block_x),
+                CAVE_BLOCK_SIZE.min(h - block_y),
+            );
+
+            if let Some(room) = largest_floor_rect(map, &block) {
+                if room.area() >= CAVE_MIN_ROOM_AREA {
+                    map.rooms.push(room);
+                }
+            }
+        }
+    }
+
+    // Make sure that there's somewhere for the player to start, even in a sparse cave.
+    if map.rooms.is_empty() {
+        let (x, y) = (0..h)
+            .flat_map(|y| (0..w).map(move |x| (x, y)))
+            .find(|&(x, y)| !map.wall_or_oob(x, y))
+            .unwrap_or((w / 2, h / 2));
+
+        map.set_tile(x, y, Tile::Floor);
+        map.rooms.push(Rect::new(x, y, 1, 1));
+    }
+}
+
+/// Positions reachable by cardinal steps across non-wall tiles from any of the given positions
+/// that aren't walls themselves.
+fn flood_fill(map: &Map, starts: impl IntoIterator<Item = (i32, i32)>) -> BitGrid {
+    let mut reached = BitGrid::new(map.width, map.height);
+    let mut stack = starts
+        .into_iter()
+        .filter(|&(x, y)| !map.wall_or_oob(x, y))
+        .collect::<Vec<_>>();
+
+    for &(x, y) in &stack {
+        reached.set_bit(x, y, true);
+    }
+
+    while let Some((x, y)) = stack.pop() {
+        for (nx, ny) in [(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)] {
+            if !map.wall_or_oob(nx, ny) && !reached.get_bit(nx, ny) {
+                reached.set_bit(nx, ny, true);
+                stack.push((nx, ny));
+            }
+        }
+    }
+
+    reached
+}
+
+/// Carve corridors to join every open pocket of the map that can't be reached from the first room
+/// back to it, taking the shortest way through the walls in between.
+fn ensure_connected(map: &mut Map) {
+    let start = match map.rooms.first() {
+        Some(room) => room.center(),
+        None => return,
+    };
+    let w = map.width;
+    let h = map.height;
+
+    loop {
+        let reached = flood_fill(map, [start]);
+        let pocket_start = match (0..h)
+            .flat_map(|y| (0..w).map(move |x| (x, y)))
+            .find(|&(x, y)| !map.wall_or_oob(x, y) && !reached.get_bit(x, y))
+        {
+            Some(pos) => pos,
+            None => break,
+        };
+        let pocket = flood_fill(map, [pocket_start]);
+
+        // Search outwards from the whole pocket through anything but the edge of the map until a
+        // reachable tile is found, then carve the path back to the pocket.
+        let mut came_from = HashMap::new();
+        let mut frontier = (0..h)
+            .flat_map(|y| (0..w).map(move |x| (x, y)))
+            .filter(|&(x, y)| pocket.get_bit(x, y))
+            .collect::<VecDeque<_>>();
+        let mut visited = pocket;
+        let mut end = None;
+
+        while let Some((x, y)) = frontier.pop_front() {
+            if reached.get_bit(x, y) {
+                end = Some((x, y));
+                break;
+            }
+
+            for (nx, ny) in [(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)] {
+                if nx > 0 && ny > 0 && nx < w - 1 && ny < h - 1 && !visited.get_bit(nx, ny) {
+                    visited.set_bit(nx, ny, true);
+                    came_from.insert((nx, ny), (x, y));
+                    frontier.push_back((nx, ny));
+                }
+            }
+        }
+
+        let mut pos = match end {
+            Some(pos) => pos,
+            None => break,
+        };
+
+        while let Some(&prev) = came_from.get(&pos) {
+            map.set_tile(pos.0, pos.1, Tile::Floor);
+            pos = prev;
+        }
+    }
+}
+
+/// Returns the position to spawn the victory item if the game has progressed far enough.
+pub fn generate_rooms_and_corridors(
+    difficulty: UniqueView<Difficulty>,
+    game_seed: UniqueView<GameSeed>,
+    options: UniqueView<Options>,
+    mut map: UniqueViewMut<Map>,
+    exps: View<Experience>,
+) -> Option<(i32, i32)> {
+    {
+        let w = map.width;
+        let h = map.height;
+        map.set_rect(&Rect::new(0, 0, w, h), Tile::Wall);
+    }
+
+    let mut rng = {
+        let mut hasher = WyHash::with_seed(magicnum::GENERATE_ROOMS_AND_CORRIDORS);
+        hasher.write_u64(game_seed.0);
+        hasher.write_i32(map.depth);
+        GameRng::seed_from_u64(hasher.finish())
+    };
+
+    match options.map_generator {
+        MapGenerator::Rooms => carve_rooms_and_corridors(&mut map, &mut rng),
+        MapGenerator::Bsp => {
+            let w = map.width;
+            let h = map.height;
+            carve_bsp_area(&mut map, &mut rng, Rect::new(0, 0, w, h));
+        }
+        MapGenerator::Caves => carve_caves(&mut map, &mut rng),
+    }
+
+    ensure_connected(&mut map);
+
+    // Light rooms with their own RNG so that room layouts stay the same for a given game seed.
+    {
+        let mut light_rng = {
+            let mut hasher = WyHash::with_seed(magicnum::LIGHT_ROOMS);
+            hasher.write_u64(game_seed.0);
+            hasher.write_i32(map.depth);
+            GameRng::seed_from_u64(hasher.finish())
+        };
+
+        map.room_lights = (0..map.rooms.len())
+            .map(|_| match light_rng.gen_range(0..8) {
+                0 => COLD_LIGHT,
+                1 => WARM_LIGHT,
+                _ => NEUTRAL_LIGHT,
+            })
+            .collect();
     }
 
     if let Some(last_room) = map.rooms.last() {
@@ -560,22 +1118,343 @@ pub fn generate_rooms_and_corridors(
         if exps.get(difficulty.id).level < 25 {
             map.set_tile(center_x, center_y, Tile::DownStairs);
             None
-        } else {
+        } else if map.rooms.len() > 1 {
             Some((center_x, center_y))
+        } else {
+            // The player starts in the only room, so keep the boss away from them.
+            Some(farthest_floor_from(&map, (center_x, center_y)))
         }
     } else {
         None
     }
 }
 
+/// The floor tile reachable from `pos` that's farthest away from it, or `pos` itself if nowhere
+/// else can be reached.
+fn farthest_floor_from(map: &Map, pos: (i32, i32)) -> (i32, i32) {
+    let reached = flood_fill(map, [pos]);
+    let dist2 = |(x, y): (i32, i32)| (x - pos.0).pow(2) + (y - pos.1).pow(2);
+
+    (0..map.height)
+        .flat_map(|y| (0..map.width).map(move |x| (x, y)))
+        .filter(|&(x, y)| reached.get_bit(x, y))
+        .max_by_key(|&xy| dist2(xy))
+        .unwrap_or(pos)
+}
+
 pub fn place_player_in_first_room(
     mut map: UniqueViewMut<Map>,
     player_id: UniqueView<PlayerId>,
     mut coords: ViewMut<Coord>,
 ) {
-    let room_center = map.rooms.first().unwrap().center();
-    let mut player_coord = (&mut coords).get(player_id.0);
+    // Fall back to the middle of the map if there are somehow no rooms.
+    let room_center = map
+        .rooms
+        .first()
+        .map_or((map.width / 2, map.height / 2), |room| room.center());
+    let player_coord = (&mut coords).get(player_id.0);
 
     map.place_entity(player_id.0, room_center, false);
     player_coord.0 = room_center.into();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A map of the usual size filled with walls, as the generators expect to start with.
+    fn walled_map() -> Map {
+        let mut map = Map::new(80, 50);
+
+        map.set_rect(&Rect::new(0, 0, 80, 50), Tile::Wall);
+        map
+    }
+
+    /// Returns true if every floor tile of the map can be reached from the center of the first
+    /// room, where the player starts.
+    fn fully_connected(map: &Map) -> bool {
+        let reached = flood_fill(map, map.rooms.first().map(Rect::center));
+
+        (0..map.height)
+            .flat_map(|y| (0..map.width).map(move |x| (x, y)))
+            .all(|(x, y)| map.wall_or_oob(x, y) || reached.get_bit(x, y))
+    }
+
+    #[test]
+    fn rect_basics() {
+        let rect = Rect::new(2, 3, 4, 5);
+
+        assert_eq!((rect.x2, rect.y2), (5, 7));
+        assert_eq!(rect.area(), 20);
+        assert_eq!(rect.center(), (3, 5));
+        assert!(rect.contains(2, 3) && rect.contains(5, 7));
+        assert!(!rect.contains(1, 3) && !rect.contains(6, 7) && !rect.contains(2, 8));
+        assert_eq!(rect.iter_xy().count(), 20);
+    }
+
+    #[test]
+    fn rect_intersects_with_margin() {
+        let a = Rect::new(0, 0, 3, 3);
+        let touching = Rect::new(3, 0, 3, 3);
+        let apart = Rect::new(4, 0, 3, 3);
+
+        assert!(a.intersects(&a, 0));
+        assert!(!a.intersects(&touching, 0));
+        assert!(a.intersects(&touching, 1));
+        assert!(!a.intersects(&apart, 1));
+        assert!(a.intersects(&apart, 2));
+    }
+
+    #[test]
+    fn single_tile_rect() {
+        let rect = Rect::new(4, 4, 1, 1);
+
+        assert_eq!(rect.area(), 1);
+        assert_eq!(rect.center(), (4, 4));
+        assert!(rect.contains(4, 4) && !rect.contains(5, 4));
+        assert!(rect.iter_xy().eq([(4, 4)]));
+    }
+
+    #[test]
+    fn farthest_floor_stays_within_reach() {
+        let mut map = walled_map();
+
+        map.set_rect(&Rect::new(10, 10, 5, 1), Tile::Floor);
+        map.set_rect(&Rect::new(40, 10, 5, 1), Tile::Floor);
+
+        assert_eq!(farthest_floor_from(&map, (11, 10)), (14, 10));
+        assert_eq!(farthest_floor_from(&map, (1, 1)), (1, 1));
+    }
+
+    #[test]
+    fn rect_expand_and_shrink() {
+        let rect = Rect::new(2, 2, 5, 3);
+        let grown = rect.expand(2);
+
+        assert_eq!((grown.x1, grown.y1, grown.x2, grown.y2), (0, 0, 8, 6));
+
+        // Shrinking past a single row keeps the center row, but columns can still shrink.
+        let shrunk = rect.expand(-2);
+        assert_eq!((shrunk.x1, shrunk.y1, shrunk.x2, shrunk.y2), (4, 3, 4, 3));
+        let shrunk = rect.expand(-1);
+        assert_eq!((shrunk.x1, shrunk.y1, shrunk.x2, shrunk.y2), (3, 3, 5, 3));
+        assert_eq!(Rect::new(4, 4, 1, 1).expand(-3).area(), 1);
+    }
+
+    #[test]
+    fn bsp_maps_are_fully_connected() {
+        for seed in 0..20 {
+            let mut map = walled_map();
+            let mut rng = GameRng::seed_from_u64(seed);
+
+            carve_bsp_area(&mut map, &mut rng, Rect::new(0, 0, 80, 50));
+
+            assert!(!map.rooms.is_empty(), "seed {}", seed);
+            assert!(fully_connected(&map), "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn caves_are_connected_and_not_empty() {
+        for seed in 0..20 {
+            let mut map = walled_map();
+            let mut rng = GameRng::seed_from_u64(seed);
+
+            carve_caves(&mut map, &mut rng);
+
+            let (x, y) = map.rooms[0].center();
+            assert!(!map.wall_or_oob(x, y), "seed {}", seed);
+            assert!(fully_connected(&map), "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn ensure_connected_reaches_isolated_pockets() {
+        let mut map = walled_map();
+        let start_room = Rect::new(2, 2, 6, 6);
+
+        map.set_rect(&start_room, Tile::Floor);
+        map.rooms.push(start_room);
+        map.set_rect(&Rect::new(30, 20, 5, 5), Tile::Floor);
+        map.set_rect(&Rect::new(60, 40, 3, 3), Tile::Floor);
+
+        assert!(!fully_connected(&map));
+
+        ensure_connected(&mut map);
+
+        assert!(fully_connected(&map));
+        assert!((0..map.width).all(|x| map.wall_or_oob(x, 0) && map.wall_or_oob(x, 49)));
+        assert!((0..map.height).all(|y| map.wall_or_oob(0, y) && map.wall_or_oob(79, y)));
+    }
+
+    #[test]
+    fn flood_fill_stops_at_walls() {
+        let mut map = walled_map();
+
+        map.set_rect(&Rect::new(1, 1, 3, 1), Tile::Floor);
+        map.set_tile(10, 10, Tile::Floor);
+
+        let reached = flood_fill(&map, [(2, 1), (0, 0)]);
+        let count = (0..map.height)
+            .flat_map(|y| (0..map.width).map(move |x| (x, y)))
+            .filter(|&(x, y)| reached.get_bit(x, y))
+            .count();
+
+        assert_eq!(count, 3);
+        assert!(!reached.get_bit(10, 10));
+    }
+
+    #[test]
+    fn generated_maps_survive_a_round_trip() {
+        let mut map = walled_map();
+        let mut rng = GameRng::seed_from_u64(4);
+
+        carve_bsp_area(&mut map, &mut rng, Rect::new(0, 0, 80, 50));
+        map.depth = 3;
+        map.seen.set_bit(10, 12, true);
+        map.set_note(4, 5, "Stash".into());
+
+        let saved = serde_json::to_string(&map).unwrap();
+        let mut loaded: Map = serde_json::from_str(&saved).unwrap();
+
+        assert!(loaded.migrate().is_ok());
+        assert_eq!(loaded.format_version, MAP_FORMAT_VERSION);
+        assert_eq!(serde_json::to_string(&loaded).unwrap(), saved);
+    }
+
+    #[test]
+    fn wall_lines_connect_to_neighboring_walls() {
+        let mut map = Map::new(10, 10);
+
+        map.set_tile(1, 1, Tile::Wall);
+        map.set_hline(3, 6, 4, Tile::Wall);
+        map.set_vline(5, 7, 6, Tile::Wall);
+
+        let sym = |x, y| map.wall_sym(x, y, WallStyle::Lines);
+
+        assert!(sym(1, 1) == GameSym::WallPillar);
+        assert!(sym(3, 4) == GameSym::WallE);
+        assert!(sym(4, 4) == GameSym::WallEw);
+        assert!(sym(6, 4) == GameSym::WallSw);
+        assert!(sym(6, 6) == GameSym::WallNs);
+        assert!(sym(6, 7) == GameSym::WallN);
+        assert!(map.wall_sym(4, 4, WallStyle::Hash) == GameSym::WallOther);
+        assert!(map.wall_sym(4, 4, WallStyle::Block) == GameSym::WallBlock);
+    }
+
+    #[test]
+    fn health_descriptions_follow_hp_fraction() {
+        let describe = |hp, max_hp, exact| {
+            let stats = CombatStats {
+                max_hp,
+                hp,
+                attack: 0.0,
+                defense: 0.0,
+            };
+
+            describe_health(&stats, exact)
+        };
+
+        assert_eq!(describe(30, 30, false), "unhurt");
+        assert_eq!(describe(21, 30, false), "lightly wounded");
+        assert_eq!(describe(20, 30, false), "wounded");
+        assert_eq!(describe(11, 30, false), "wounded");
+        assert_eq!(describe(10, 30, false), "badly wounded");
+        assert_eq!(describe(4, 30, false), "badly wounded");
+        assert_eq!(describe(3, 30, false), "near death");
+        assert_eq!(describe(0, 0, false), "unhurt");
+        assert_eq!(describe(3, 30, true), "3/30 HP");
+    }
+
+    #[test]
+    fn rooms_light_their_floors_and_walls_only() {
+        let mut map = walled_map();
+
+        map.rooms.push(Rect::new(2, 2, 6, 6));
+        map.room_lights.push(WARM_LIGHT);
+
+        assert!(map.light_at(4, 4) == WARM_LIGHT);
+        assert!(map.light_at(1, 1) == WARM_LIGHT);
+        assert!(map.light_at(0, 0) == NEUTRAL_LIGHT);
+        assert!(map.light_at(20, 20) == NEUTRAL_LIGHT);
+    }
+
+    #[test]
+    fn strict_diagonals_stop_squeezing_between_corners() {
+        use ruggrogue::PathableMap;
+
+        let mut map = Map::new(10, 10);
+
+        map.set_tile(5, 4, Tile::Wall);
+        map.set_tile(4, 5, Tile::Wall);
+
+        let permissive = StepMap {
+            map: &map,
+            strict_diagonals: false,
+        };
+        let strict = StepMap {
+            map: &map,
+            strict_diagonals: true,
+        };
+
+        // Between two walls that meet at a corner.
+        assert!(!permissive.is_diagonal_blocked(4, 4, 1, 1));
+        assert!(strict.is_diagonal_blocked(4, 4, 1, 1));
+        assert!(strict.is_diagonal_blocked(5, 5, -1, -1));
+
+        // Past only one of the walls, or straight along them.
+        assert!(!strict.is_diagonal_blocked(5, 3, -1, 1));
+        assert!(!strict.is_diagonal_blocked(4, 4, 1, 0));
+        assert!(!strict.is_diagonal_blocked(5, 5, 0, -1));
+
+        // The edges of the map count as walls.
+        assert!(strict.is_diagonal_blocked(0, 0, -1, -1));
+        assert!(!permissive.is_diagonal_blocked(0, 0, -1, -1));
+    }
+
+    #[test]
+    fn targeting_a_monster_on_an_item_names_the_monster() {
+        use crate::{gamesym::GameSym, spawn, vision};
+
+        let world = spawn::test_world(20, 20, (5, 5));
+        let goblin_id = spawn::spawn_monster_kind(&world, GameSym::Goblin, (8, 5)).unwrap();
+        let potion_id = spawn::spawn_item_kind(&world, GameSym::HealthPotion, (8, 5)).unwrap();
+
+        world.run(vision::recalculate_fields_of_view);
+
+        let (goblin_name, potion_name) = {
+            let names = world.borrow::<View<Name>>();
+
+            (
+                names.get(goblin_id).0.clone(),
+                names.get(potion_id).0.clone(),
+            )
+        };
+        let map = world.borrow::<UniqueView<Map>>();
+        let (focused, recalled) = map.describe_pos(&world, 8, 5, true, false, false);
+        let (full, _) = map.describe_pos(&world, 8, 5, false, false, false);
+
+        assert!(!recalled);
+        assert!(focused.starts_with(&goblin_name));
+        assert!(!focused.contains(&potion_name));
+        assert!(full.starts_with(&goblin_name));
+        assert!(full.contains(&potion_name));
+    }
+
+    #[test]
+    fn migrate_upgrades_old_maps_and_rejects_newer_ones() {
+        let mut map = walled_map();
+
+        map.rooms.push(Rect::new(2, 2, 6, 6));
+        map.format_version = 1;
+        assert!(map.migrate().is_ok());
+        assert_eq!(map.format_version, MAP_FORMAT_VERSION);
+        assert_eq!(map.room_lights.len(), 1);
+
+        map.format_version = MAP_FORMAT_VERSION + 1;
+        assert!(matches!(
+            map.migrate(),
+            Err(LoadError::UnsupportedVersion("Map", v)) if v == MAP_FORMAT_VERSION + 1
+        ));
+    }
+}
@@ -3,7 +3,7 @@ use std::ops::{Index, IndexMut};
 use ruggrogue::util::Position;
 
 pub struct MenuMemory {
-    menu: [i32; 7],
+    menu: [i32; 8],
     pub pick_up_pos: Position,
 }
 
@@ -15,10 +15,11 @@ impl MenuMemory {
     pub const EQUIPMENT_SHORTCUT_REMOVE: usize = 4;
     pub const EQUIPMENT_SHORTCUT_DROP: usize = 5;
     pub const PICK_UP: usize = 6;
+    pub const INVENTORY_SHORTCUT_THROW: usize = 7;
 
     pub fn new() -> Self {
         Self {
-            menu: [0; 7],
+            menu: [0; 8],
             pick_up_pos: Position { x: 0, y: 0 },
         }
     }
@@ -1,52 +1,139 @@
 use serde::{Deserialize, Serialize};
+use shipyard::{EntityId, Get, View};
 use std::collections::VecDeque;
 
-#[derive(Deserialize, Serialize)]
+use crate::components::{Name, ProperNoun};
+use ruggrogue::util::Color;
+
+/// Color of warnings, i.e. messages about the player coming to harm, which interrupt resting.
+pub const WARNING_COLOR: Color = Color::RED;
+
+#[derive(Clone, Deserialize, Serialize)]
 pub struct Messages {
+    /// Maximum number of messages kept in the history.
     capacity: u16,
+    /// Maximum number of recent messages shown at once.
+    #[serde(default)]
+    display_count: u16,
     msg_queue: VecDeque<String>,
+    /// Colors of the messages in `msg_queue`, or `None` for messages drawn in the usual color.
+    #[serde(default)]
+    msg_colors: VecDeque<Option<Color>>,
+    /// Turns that the messages in `msg_queue` occurred on, or `None` if unknown.
+    #[serde(default)]
+    msg_turns: VecDeque<Option<u64>>,
+    /// Turn that newly-added messages are recorded as occurring on.
+    #[serde(default)]
+    turn: u64,
     num_highlighted: usize,
     want_separator: bool,
+    /// True if a warning has been added since the last call to [Messages::take_warning].
+    #[serde(skip)]
+    warned: bool,
 }
 
 impl Messages {
-    pub fn new(capacity: u16) -> Self {
+    pub fn new(capacity: u16, display_count: u16) -> Self {
         assert!(capacity > 0);
+        assert!(display_count <= capacity);
 
         Self {
             capacity,
+            display_count,
             msg_queue: VecDeque::with_capacity(capacity as usize),
+            msg_colors: VecDeque::with_capacity(capacity as usize),
+            msg_turns: VecDeque::with_capacity(capacity as usize),
+            turn: 0,
             num_highlighted: 0,
             want_separator: false,
+            warned: false,
         }
     }
 
     pub fn replace(&mut self, replacement: Self) {
         self.msg_queue = replacement.msg_queue;
+        self.msg_colors = replacement.msg_colors;
+        self.msg_turns = replacement.msg_turns;
+        self.turn = replacement.turn;
         self.num_highlighted = replacement.num_highlighted;
+
+        // Messages from older saves have no colors or turns.
+        if self.msg_colors.len() != self.msg_queue.len() {
+            self.msg_colors = self.msg_queue.iter().map(|_| None).collect();
+        }
+        if self.msg_turns.len() != self.msg_queue.len() {
+            self.msg_turns = self.msg_queue.iter().map(|_| None).collect();
+        }
     }
 
     pub fn reset(&mut self) {
         self.msg_queue.clear();
+        self.msg_colors.clear();
+        self.msg_turns.clear();
         self.num_highlighted = 0;
     }
 
+    /// Set the turn that messages added from now on are recorded as occurring on.
+    pub fn set_turn(&mut self, turn: u64) {
+        self.turn = turn;
+    }
+
     pub fn add(&mut self, msg: String) {
+        self.push(msg, None);
+    }
+
+    /// Add a message that is drawn in a specific color while it's highlighted.
+    pub fn add_colored(&mut self, msg: String, color: Color) {
+        self.push(msg, Some(color));
+    }
+
+    /// Returns true if a warning has been added since this was last called.
+    pub fn take_warning(&mut self) -> bool {
+        std::mem::take(&mut self.warned)
+    }
+
+    fn push(&mut self, msg: String, color: Option<Color>) {
+        if color == Some(WARNING_COLOR) {
+            self.warned = true;
+        }
+
+        // Fold a repeat of the most recent message into it with a count, e.g. "Foo. (x3)", even
+        // across a separator, so the same thing happening turn after turn doesn't flood the log.
+        if let Some(last) = self.msg_queue.back_mut() {
+            if let Some(count) = repeat_count(last, &msg) {
+                *last = format!("{} (x{})", msg, count + 1);
+                if let Some(last_color) = self.msg_colors.back_mut() {
+                    *last_color = color;
+                }
+                if let Some(last_turn) = self.msg_turns.back_mut() {
+                    *last_turn = Some(self.turn);
+                }
+                self.num_highlighted = self.num_highlighted.max(1);
+                return;
+            }
+        }
+
         let space_needed = if self.want_separator { 2 } else { 1 };
 
         if self.msg_queue.len() + space_needed >= self.capacity as usize {
             for _ in 0..space_needed {
                 self.msg_queue.pop_front();
+                self.msg_colors.pop_front();
+                self.msg_turns.pop_front();
             }
             self.num_highlighted = self.num_highlighted.min(self.msg_queue.len());
         }
 
         if self.want_separator {
             self.msg_queue.push_back("".to_string());
+            self.msg_colors.push_back(None);
+            self.msg_turns.push_back(None);
             self.want_separator = false;
         }
 
         self.msg_queue.push_back(msg);
+        self.msg_colors.push_back(color);
+        self.msg_turns.push_back(Some(self.turn));
         self.num_highlighted = self.num_highlighted.saturating_add(1);
     }
 
@@ -54,16 +141,194 @@ impl Messages {
         self.want_separator = true;
     }
 
-    /// Returns an iterator over messages in reverse order, each with a highlight flag.
-    pub fn rev_iter(&self) -> impl Iterator<Item = (&str, bool)> {
+    /// Returns an iterator over the entire message history in reverse order, each with a
+    /// highlight flag, its color and the turn it occurred on, if any.
+    pub fn rev_iter(&self) -> impl Iterator<Item = (&str, bool, Option<Color>, Option<u64>)> {
         self.msg_queue
             .iter()
+            .zip(self.msg_colors.iter())
+            .zip(self.msg_turns.iter())
             .rev()
             .enumerate()
-            .map(move |(i, s)| (s.as_str(), i < self.num_highlighted))
+            .map(move |(i, ((s, c), t))| (s.as_str(), i < self.num_highlighted, *c, *t))
+    }
+
+    /// Like [Messages::rev_iter], but limited to the most recent messages that should be shown.
+    pub fn recent_rev_iter(
+        &self,
+    ) -> impl Iterator<Item = (&str, bool, Option<Color>, Option<u64>)> {
+        self.rev_iter().take(self.display_count as usize)
     }
 
     pub fn reset_highlight(&mut self) {
         self.num_highlighted = 0;
     }
 }
+
+/// How many times a message has been repeated in a log entry, or `None` if the entry isn't the
+/// message at all, with or without a repeat count.
+fn repeat_count(entry: &str, msg: &str) -> Option<u32> {
+    if msg.is_empty() {
+        None
+    } else if entry == msg {
+        Some(1)
+    } else {
+        entry
+            .strip_prefix(msg)?
+            .strip_prefix(" (x")?
+            .strip_suffix(')')?
+            .parse()
+            .ok()
+    }
+}
+
+/// Lowercase a name for use in the middle of a sentence, unless it's a proper noun.
+fn common_name(name: &str, proper: bool) -> String {
+    if proper {
+        name.to_string()
+    } else {
+        name.to_lowercase()
+    }
+}
+
+/// A name with a definite article, e.g. "the goblin", or just the name if it's a proper noun.
+pub fn the_name(name: &str, proper: bool) -> String {
+    if proper {
+        common_name(name, proper)
+    } else {
+        format!("the {}", common_name(name, proper))
+    }
+}
+
+/// A name with an indefinite article, e.g. "a goblin" or "an orc", or just the name if it's a
+/// proper noun.
+pub fn a_name(name: &str, proper: bool) -> String {
+    if proper {
+        common_name(name, proper)
+    } else {
+        let name = common_name(name, proper);
+        let starts_with_vowel = name.chars().next().is_some_and(|c| "aeiou".contains(c));
+
+        format!("{} {}", if starts_with_vowel { "an" } else { "a" }, name)
+    }
+}
+
+/// Capitalize the first letter of some text so it can start a sentence.
+pub fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+
+    chars.next().map_or_else(String::new, |first| {
+        first.to_uppercase().chain(chars).collect()
+    })
+}
+
+/// [the_name] for an entity, looking up whether its name is a proper noun.
+pub fn the_name_of(names: &View<Name>, proper_nouns: &View<ProperNoun>, id: EntityId) -> String {
+    the_name(&names.get(id).0, proper_nouns.contains(id))
+}
+
+/// [a_name] for an entity, looking up whether its name is a proper noun.
+pub fn a_name_of(names: &View<Name>, proper_nouns: &View<ProperNoun>, id: EntityId) -> String {
+    a_name(&names.get(id).0, proper_nouns.contains(id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn history(msgs: &Messages) -> Vec<(&str, Option<Color>)> {
+        let mut history = msgs
+            .rev_iter()
+            .map(|(m, _, c, _)| (m, c))
+            .collect::<Vec<_>>();
+
+        history.reverse();
+        history
+    }
+
+    #[test]
+    fn colors_survive_rotation() {
+        let mut msgs = Messages::new(4, 2);
+
+        msgs.add_colored("Ouch!".into(), Color::RED);
+        msgs.add("Plain 0.".into());
+        msgs.add("Plain 1.".into());
+        msgs.add_colored("Healed.".into(), Color::GREEN);
+        msgs.add("Plain 2.".into());
+
+        assert!(
+            history(&msgs)
+                == [
+                    ("Plain 1.", None),
+                    ("Healed.", Some(Color::GREEN)),
+                    ("Plain 2.", None),
+                ]
+        );
+    }
+
+    #[test]
+    fn repeats_fold_into_one_entry() {
+        let mut msgs = Messages::new(10, 5);
+
+        for _ in 0..3 {
+            msgs.add("The goblin misses you.".into());
+        }
+
+        assert!(history(&msgs) == [("The goblin misses you. (x3)", None)]);
+
+        msgs.add("You hit the goblin.".into());
+        msgs.add("The goblin misses you.".into());
+
+        assert!(
+            history(&msgs)
+                == [
+                    ("The goblin misses you. (x3)", None),
+                    ("You hit the goblin.", None),
+                    ("The goblin misses you.", None),
+                ]
+        );
+    }
+
+    #[test]
+    fn repeat_counts_only_match_the_same_message() {
+        assert_eq!(repeat_count("Foo.", "Foo."), Some(1));
+        assert_eq!(repeat_count("Foo. (x4)", "Foo."), Some(4));
+        assert_eq!(repeat_count("Foo. (x4)", "Foo. (x4)"), Some(1));
+        assert_eq!(repeat_count("Foo. (x)", "Foo."), None);
+        assert_eq!(repeat_count("Foo. bar", "Foo."), None);
+        assert_eq!(repeat_count("Bar.", "Foo."), None);
+        assert_eq!(repeat_count("", ""), None);
+    }
+
+    #[test]
+    fn history_keeps_messages_beyond_the_displayed_count() {
+        let mut msgs = Messages::new(10, 3);
+
+        for i in 0..6 {
+            msgs.add(format!("Message {}.", i));
+        }
+
+        let recent = msgs.recent_rev_iter().map(|(m, ..)| m).collect::<Vec<_>>();
+        assert_eq!(recent, ["Message 5.", "Message 4.", "Message 3."]);
+        assert_eq!(msgs.rev_iter().count(), 6);
+        assert_eq!(history(&msgs)[0].0, "Message 0.");
+    }
+
+    #[test]
+    fn articles_follow_the_first_letter() {
+        assert_eq!(a_name("Goblin", false), "a goblin");
+        assert_eq!(a_name("Orc", false), "an orc");
+        assert_eq!(a_name("Health Potion", false), "a health potion");
+        assert_eq!(a_name("Iron Sword", false), "an iron sword");
+        assert_eq!(the_name("Goblin", false), "the goblin");
+        assert_eq!(the_name("Orc", false), "the orc");
+    }
+
+    #[test]
+    fn proper_nouns_keep_their_name() {
+        assert_eq!(a_name("Uriel", true), "Uriel");
+        assert_eq!(the_name("Uriel", true), "Uriel");
+        assert_eq!(capitalize(&the_name("Goblin", false)), "The goblin");
+        assert_eq!(capitalize(""), "");
+    }
+}
@@ -1,8 +1,9 @@
-use shipyard::{Get, UniqueView, UniqueViewMut, View, World};
+use shipyard::{EntitiesView, EntityId, Get, UniqueView, UniqueViewMut, View, ViewMut, World};
 
 use crate::{
+    animation::{Animation, Animations},
     chunked::{Camera, ChunkedMapGrid},
-    components::{Coord, FieldOfView},
+    components::{Coord, FieldOfView, Renderable},
     damage, experience,
     gamesym::GameSym,
     hunger, item,
@@ -10,11 +11,14 @@ use crate::{
     message::Messages,
     monster,
     player::{self, PlayerId, PlayerInputResult},
-    render, saveload, ui, vision, TurnCount,
+    render, saveload, spawn, status,
+    ui::{self, Options},
+    undo::{self, UndoSnapshot},
+    vision, TurnCount,
 };
 use ruggrogue::{
     util::{Color, Position, Size},
-    InputBuffer, TileGrid, Tileset,
+    InputBuffer, InputEvent, TileGrid, Tileset,
 };
 
 use super::{
@@ -25,6 +29,7 @@ use super::{
     inventory::{InventoryMode, InventoryModeResult},
     inventory_action::InventoryAction,
     inventory_shortcut::{InventoryShortcutMode, InventoryShortcutModeResult},
+    message_log::{MessageLogMode, MessageLogModeResult},
     options_menu::{OptionsMenuMode, OptionsMenuModeResult},
     pick_up_menu::{PickUpMenuMode, PickUpMenuModeResult},
     title::{self, TitleMode},
@@ -37,10 +42,38 @@ pub enum DungeonModeResult {
     Done,
 }
 
+/// Number of frames to flash the status display for when the player's health becomes critical.
+const LOW_HP_FLASH_FRAMES: u32 = 48;
+
+/// Number of frames that each on and off phase of the low health flash lasts.
+const LOW_HP_FLASH_PERIOD: u32 = 8;
+
+/// Color to tint the status display with during the "on" phases of the low health flash.
+const LOW_HP_FLASH_COLOR: Color = Color {
+    r: 255,
+    g: 64,
+    b: 64,
+};
+
+/// Number of frames to spend revealing the player's field of view upon arriving at a new level.
+const FOV_REVEAL_FRAMES: u32 = 20;
+
+/// Number of frames that the field of view reveal takes to expand by one tile.
+const FOV_REVEAL_FRAMES_PER_TILE: u32 = 2;
+
 pub struct DungeonMode {
     chunked_map_grid: ChunkedMapGrid,
     old_msg_frame_size: Size,
     redraw_msg_frame_grid: bool,
+    /// Animations being played out, each with the frame that it starts on.
+    animations: Vec<(u32, Animation)>,
+    animation_frame: u32,
+    /// Frames left to flash the status display for after warning the player about low health.
+    low_hp_flash: u32,
+    /// Frames left to reveal the player's field of view for after arriving at a new level.
+    fov_reveal: u32,
+    turn_snapshot: Option<Box<UndoSnapshot>>,
+    undo_snapshot: Option<Box<UndoSnapshot>>,
 }
 
 fn app_quit_dialog(inputs: &mut InputBuffer) -> (ModeControl, ModeUpdate) {
@@ -51,6 +84,19 @@ fn app_quit_dialog(inputs: &mut InputBuffer) -> (ModeControl, ModeUpdate) {
     )
 }
 
+/// Save the game when quitting, as a quicksave if the player wants one.
+fn save_on_exit(world: &World) {
+    let result = if world.borrow::<UniqueView<Options>>().quicksave_on_exit {
+        saveload::quicksave_game(world)
+    } else {
+        saveload::save_game(world)
+    };
+
+    if let Err(e) = result {
+        eprintln!("Warning: saveload::save_game: {}", e);
+    }
+}
+
 fn get_player_fov(player_id: UniqueView<PlayerId>, fovs: View<FieldOfView>) -> (Position, Size) {
     let player_fov = fovs.get(player_id.0);
 
@@ -78,6 +124,12 @@ impl DungeonMode {
             chunked_map_grid: ChunkedMapGrid::new(),
             old_msg_frame_size: (0, 0).into(),
             redraw_msg_frame_grid: true,
+            animations: Vec::new(),
+            animation_frame: 0,
+            low_hp_flash: 0,
+            fov_reveal: 0,
+            turn_snapshot: None,
+            undo_snapshot: None,
         }
     }
 
@@ -101,16 +153,71 @@ impl DungeonMode {
             || grids[ui::MSG_FRAME_GRID].height() != self.old_msg_frame_size.h;
         self.old_msg_frame_size.w = grids[ui::MSG_FRAME_GRID].width();
         self.old_msg_frame_size.h = grids[ui::MSG_FRAME_GRID].height();
+
+        // Prepare a one-tile grid above the map for each animated entity to be drawn into.
+        let Options {
+            tileset: map_tileset_index,
+            map_zoom,
+            ..
+        } = *world.borrow::<UniqueView<Options>>();
+        let num_grids = ui::MSG_GRID + 1 + self.animations.len();
+
+        grids.truncate(num_grids);
+        while grids.len() < num_grids {
+            let mut grid = TileGrid::new(Size { w: 1, h: 1 }, tilesets, map_tileset_index as usize);
+            grid.view.clear_color = None;
+            grids.push(grid);
+        }
+
+        for grid in grids.iter_mut().skip(ui::MSG_GRID + 1) {
+            grid.set_tileset(tilesets, map_tileset_index as usize);
+            grid.view.zoom = map_zoom;
+            grid.view.visible = false;
+        }
     }
 
     pub fn update(
         &mut self,
         world: &World,
         inputs: &mut InputBuffer,
-        _grids: &[TileGrid<GameSym>],
+        grids: &[TileGrid<GameSym>],
         pop_result: &Option<ModeResult>,
     ) -> (ModeControl, ModeUpdate) {
+        if !self.animations.is_empty() || self.low_hp_flash > 0 || self.fov_reveal > 0 {
+            // Key releases shouldn't cut animations short.
+            inputs.prepare_input();
+            while let Some(InputEvent::Release(_)) = inputs.get_input() {
+                inputs.clear_input();
+                inputs.prepare_input();
+            }
+
+            // Play out animations unless the player wants to do something, in which case they're
+            // finished early so that input is never held up.
+            if pop_result.is_none() && inputs.get_input().is_none() {
+                self.animation_frame += 1;
+                self.low_hp_flash = self.low_hp_flash.saturating_sub(1);
+                self.fov_reveal = self.fov_reveal.saturating_sub(1);
+                if self.low_hp_flash > 0
+                    || self.fov_reveal > 0
+                    || self
+                        .animations
+                        .iter()
+                        .any(|(start, a)| self.animation_frame < start + a.frames())
+                {
+                    return (ModeControl::Stay, ModeUpdate::Update);
+                }
+            }
+            self.animations.clear();
+            self.low_hp_flash = 0;
+            self.fov_reveal = 0;
+        }
+
         if world.run(player::player_is_alive) {
+            // Remember the state before the player acts so that it can be returned to.
+            if world.borrow::<UniqueView<Options>>().allow_undo && self.turn_snapshot.is_none() {
+                self.turn_snapshot = Some(Box::new(undo::take_snapshot(world)));
+            }
+
             let old_player_fov = world.run(get_player_fov);
             let old_player_pos = world.run(get_player_pos);
             let old_depth = world.borrow::<UniqueView<Map>>().depth;
@@ -118,9 +225,7 @@ impl DungeonMode {
                 match result {
                     ModeResult::AppQuitDialogModeResult(result) => match result {
                         AppQuitDialogModeResult::Confirmed => {
-                            if let Err(e) = saveload::save_game(world) {
-                                eprintln!("Warning: saveload::save_game: {}", e);
-                            }
+                            save_on_exit(world);
                             return (
                                 ModeControl::Pop(DungeonModeResult::Done.into()),
                                 ModeUpdate::Immediate,
@@ -141,13 +246,27 @@ impl DungeonMode {
                         YesNoDialogModeResult::No => false,
                     },
 
+                    ModeResult::MessageLogModeResult(result) => match result {
+                        MessageLogModeResult::AppQuit => return app_quit_dialog(inputs),
+                        MessageLogModeResult::Done => false,
+                    },
+
                     ModeResult::OptionsMenuModeResult(result) => match result {
                         OptionsMenuModeResult::AppQuit => return app_quit_dialog(inputs),
-                        OptionsMenuModeResult::Closed => false,
-                        OptionsMenuModeResult::ReallyQuit => {
-                            if let Err(e) = saveload::save_game(world) {
-                                eprintln!("Warning: saveload::save_game: {}", e);
+                        OptionsMenuModeResult::Closed => {
+                            // The shape of the player's field of view may have been changed, which
+                            // takes effect when it's next recalculated, so that changing options
+                            // doesn't reveal anything without taking a turn.
+                            {
+                                let (player_id, mut fovs) =
+                                    world.borrow::<(UniqueView<PlayerId>, ViewMut<FieldOfView>)>();
+                                (&mut fovs).get(player_id.0).dirty = true;
                             }
+                            self.chunked_map_grid.mark_all_dirty();
+                            false
+                        }
+                        OptionsMenuModeResult::ReallyQuit => {
+                            save_on_exit(world);
                             title::post_game_cleanup(world, true);
                             inputs.clear_input();
                             return (
@@ -194,6 +313,10 @@ impl DungeonMode {
                                 }
                                 true
                             }
+                            InventoryModeResult::ThrowItem(item_id, target) => {
+                                item::throw_item(world, player_id, *item_id, *target);
+                                true
+                            }
                             InventoryModeResult::DropItem(item_id) => {
                                 player::player_drop_item(world, *item_id);
                                 true
@@ -221,6 +344,10 @@ impl DungeonMode {
                                 }
                                 true
                             }
+                            InventoryShortcutModeResult::ThrowItem(item_id, target) => {
+                                item::throw_item(world, player_id, *item_id, *target);
+                                true
+                            }
                             InventoryShortcutModeResult::DropItem(item_id) => {
                                 player::player_drop_item(world, *item_id);
                                 true
@@ -247,7 +374,11 @@ impl DungeonMode {
 
                     ModeResult::ViewMapModeResult(result) => match result {
                         ViewMapModeResult::AppQuit => return app_quit_dialog(inputs),
-                        ViewMapModeResult::Done => false,
+                        ViewMapModeResult::Done => {
+                            // Notes may have been left or removed while viewing the map.
+                            self.chunked_map_grid.mark_all_dirty();
+                            false
+                        }
                     },
 
                     _ => unreachable!(),
@@ -257,6 +388,26 @@ impl DungeonMode {
                     PlayerInputResult::AppQuit => return app_quit_dialog(inputs),
                     PlayerInputResult::NoResult => false,
                     PlayerInputResult::TurnDone => true,
+                    PlayerInputResult::Click(x, y) => {
+                        // Travel to the clicked tile.
+                        if let Some(dest) = self.chunked_map_grid.screen_to_map_pos(
+                            world,
+                            &grids[ui::MAP_GRID],
+                            x,
+                            y,
+                        ) {
+                            matches!(
+                                player::travel_player(world, dest),
+                                PlayerInputResult::TurnDone
+                            )
+                        } else {
+                            false
+                        }
+                    }
+                    PlayerInputResult::Undo => {
+                        self.undo_last_turn(world);
+                        false
+                    }
                     PlayerInputResult::ShowOptionsMenu => {
                         inputs.clear_input();
                         return (
@@ -271,6 +422,13 @@ impl DungeonMode {
                             ModeUpdate::Immediate,
                         );
                     }
+                    PlayerInputResult::ShowMessageLog => {
+                        inputs.clear_input();
+                        return (
+                            ModeControl::Push(MessageLogMode::new().into()),
+                            ModeUpdate::Immediate,
+                        );
+                    }
                     PlayerInputResult::TryDescend => {
                         if world.run(player::player_try_descend) {
                             inputs.clear_input();
@@ -328,6 +486,8 @@ impl DungeonMode {
             };
 
             if time_passed {
+                self.undo_snapshot = self.turn_snapshot.take();
+
                 world.run(damage::handle_dead_entities);
                 world.run(experience::gain_levels);
                 world.run(vision::recalculate_fields_of_view);
@@ -341,18 +501,33 @@ impl DungeonMode {
 
                     if world.run(player::player_is_alive) {
                         world.run(hunger::tick_hunger);
+                        status::tick_status_effects(world);
                         world.run(damage::handle_dead_entities);
                         world.run(experience::gain_levels);
                         world.run(vision::recalculate_fields_of_view);
 
                         if world.run(player::player_is_alive) {
+                            if world.run(player::player_warn_low_hp)
+                                && world.borrow::<UniqueView<Options>>().screen_effects
+                            {
+                                self.low_hp_flash = LOW_HP_FLASH_FRAMES;
+                            }
+
+                            spawn::spawn_wandering_monster(world);
                             world.run(damage::clear_hurt_bys);
                             world.borrow::<UniqueViewMut<TurnCount>>().0 += 1;
-                            world.borrow::<UniqueViewMut<Messages>>().separator();
+
+                            let mut msgs = world.borrow::<UniqueViewMut<Messages>>();
+                            msgs.set_turn(world.borrow::<UniqueView<TurnCount>>().0);
+                            msgs.separator();
                         }
                     }
                 }
 
+                if world.borrow::<UniqueView<Options>>().allow_undo {
+                    self.turn_snapshot = Some(Box::new(undo::take_snapshot(world)));
+                }
+
                 // Redraw map chunks containing the player's old and new fields of view.
                 let new_player_fov = world.run(get_player_fov);
                 self.chunked_map_grid
@@ -365,9 +540,15 @@ impl DungeonMode {
                 let new_depth = world.borrow::<UniqueView<Map>>().depth;
                 let new_player_pos = world.run(get_player_pos);
 
-                // Redraw all map chunks when changing levels.
+                // Redraw all map chunks and forget undo history when changing levels.
                 if new_depth != old_depth {
                     self.chunked_map_grid.mark_all_dirty();
+                    self.turn_snapshot = None;
+                    self.undo_snapshot = None;
+
+                    if world.borrow::<UniqueView<Options>>().screen_effects {
+                        self.fov_reveal = FOV_REVEAL_FRAMES;
+                    }
                 }
 
                 if new_depth != old_depth || new_player_pos != old_player_pos {
@@ -381,9 +562,47 @@ impl DungeonMode {
                 }
             }
 
+            // Play out animations queued during the turn for entities that the player can see.
+            {
+                let (entities, player_id, fovs, mut animations) = world.borrow::<(
+                    EntitiesView,
+                    UniqueView<PlayerId>,
+                    View<FieldOfView>,
+                    UniqueViewMut<Animations>,
+                )>();
+                let player_fov = fovs.get(player_id.0);
+                let delay = world.borrow::<UniqueView<Options>>().monster_action_delay;
+                let mut animated: Vec<EntityId> = Vec::new();
+
+                // Stagger the animations of each entity in the order that they acted so that it's
+                // easier to follow who did what.
+                self.animations = animations
+                    .0
+                    .drain(..)
+                    .filter(|a| entities.is_alive(a.entity()) && player_fov.get(a.pos().into()))
+                    .map(|a| {
+                        let order = animated
+                            .iter()
+                            .position(|&id| id == a.entity())
+                            .unwrap_or_else(|| {
+                                animated.push(a.entity());
+                                animated.len() - 1
+                            });
+
+                        (order as u32 * delay, a)
+                    })
+                    .collect();
+                self.animation_frame = 0;
+            }
+
             (
                 ModeControl::Stay,
-                if world.run(player::player_is_alive) && world.run(player::player_is_auto_running) {
+                if !self.animations.is_empty()
+                    || self.low_hp_flash > 0
+                    || self.fov_reveal > 0
+                    || (world.run(player::player_is_alive)
+                        && world.run(player::player_is_auto_running))
+                {
                     ModeUpdate::Update
                 } else {
                     ModeUpdate::WaitForEvent
@@ -399,12 +618,89 @@ impl DungeonMode {
         }
     }
 
+    /// Take back the last turn if undo is enabled and nothing irreversible happened during it.
+    fn undo_last_turn(&mut self, world: &World) {
+        if !world.borrow::<UniqueView<Options>>().allow_undo {
+            world
+                .borrow::<UniqueViewMut<Messages>>()
+                .add("Undo is disabled in the options.".into());
+            return;
+        }
+
+        let undone = self
+            .undo_snapshot
+            .as_ref()
+            .is_some_and(|snapshot| undo::restore_snapshot(world, snapshot));
+
+        if undone {
+            self.turn_snapshot = self.undo_snapshot.take();
+            world.run(vision::recalculate_fields_of_view);
+            self.chunked_map_grid.mark_all_dirty();
+            world
+                .borrow::<UniqueViewMut<Messages>>()
+                .add("You take back your last turn.".into());
+        } else {
+            world
+                .borrow::<UniqueViewMut<Messages>>()
+                .add("That can't be undone.".into());
+        }
+    }
+
+    fn draw_animations(
+        &mut self,
+        world: &World,
+        map_grid: &mut TileGrid<GameSym>,
+        anim_grids: &mut [TileGrid<GameSym>],
+    ) {
+        let (options, renderables) = world.borrow::<(UniqueView<Options>, View<Renderable>)>();
+        let tile_px_w = self.chunked_map_grid.tile_size().w as i32;
+        let tile_px_h = self.chunked_map_grid.tile_size().h as i32;
+
+        for (i, (start, anim)) in self.animations.iter().enumerate() {
+            let render = match renderables.try_get(anim.entity()) {
+                Ok(render) => render,
+                Err(_) => continue,
+            };
+            let fg = render::renderable_fg(&options, render);
+            let (offset_x, offset_y) = anim.offset(self.animation_frame.saturating_sub(*start));
+
+            if let Some(anim_grid) = anim_grids.get_mut(i) {
+                // Position the grid at the in-between pixel position, clipped to the map.
+                if let Some(grid_pos) = self.chunked_map_grid.map_to_grid_pos(world, anim.pos()) {
+                    anim_grid.view.pos = map_grid.view.pos;
+                    anim_grid.view.size = map_grid.view.size;
+                    anim_grid.view.dx = map_grid.view.dx
+                        + grid_pos.x * tile_px_w
+                        + (offset_x * tile_px_w as f32).round() as i32;
+                    anim_grid.view.dy = map_grid.view.dy
+                        + grid_pos.y * tile_px_h
+                        + (offset_y * tile_px_h as f32).round() as i32;
+                    anim_grid.view.visible = true;
+                    anim_grid.view.color_mod = map_grid.view.color_mod;
+                    anim_grid.put_sym_color((0, 0), render.sym, fg, render.bg);
+                }
+            } else {
+                // The grid for this animation won't exist until the next frame, so draw it on the
+                // nearest map tile for now.
+                let pos = Position {
+                    x: anim.pos().x + offset_x.round() as i32,
+                    y: anim.pos().y + offset_y.round() as i32,
+                };
+
+                if let Some(grid_pos) = self.chunked_map_grid.map_to_grid_pos(world, pos) {
+                    map_grid.put_sym_color(grid_pos, render.sym, fg, render.bg);
+                    self.chunked_map_grid.mark_dirty(pos, Size { w: 1, h: 1 });
+                }
+            }
+        }
+    }
+
     pub fn draw(&mut self, world: &World, grids: &mut [TileGrid<GameSym>], active: bool) {
         let (map_grid, grids) = grids.split_first_mut().unwrap(); // ui::MAP_GRID
         let (status_grid, grids) = grids.split_first_mut().unwrap(); // ui::STATUS_GRID
         let (item_grid, grids) = grids.split_first_mut().unwrap(); // ui::ITEM_GRID
         let (msg_frame_grid, grids) = grids.split_first_mut().unwrap(); // ui::MSG_FRAME_GRID
-        let (msg_grid, _) = grids.split_first_mut().unwrap(); // ui::MSG_GRID
+        let (msg_grid, anim_grids) = grids.split_first_mut().unwrap(); // ui::MSG_GRID
 
         if active {
             map_grid.view.color_mod = Color::WHITE;
@@ -412,6 +708,11 @@ impl DungeonMode {
             item_grid.view.color_mod = Color::WHITE;
             msg_frame_grid.view.color_mod = Color::WHITE;
             msg_grid.view.color_mod = Color::WHITE;
+
+            // Alternate between tinted and normal to flash the status display.
+            if (self.low_hp_flash / LOW_HP_FLASH_PERIOD) % 2 == 1 {
+                status_grid.view.color_mod = LOW_HP_FLASH_COLOR;
+            }
         } else {
             map_grid.view.color_mod = Color::GRAY;
             status_grid.view.color_mod = Color::GRAY;
@@ -420,8 +721,29 @@ impl DungeonMode {
             msg_grid.view.color_mod = Color::GRAY;
         }
 
+        // Animated entities are drawn separately, so redraw the map underneath them.
+        for (_, anim) in self.animations.iter() {
+            self.chunked_map_grid
+                .mark_dirty(anim.pos(), Size { w: 1, h: 1 });
+        }
+
+        // Expand the visible part of the map outwards from the player while it's being revealed.
+        self.chunked_map_grid
+            .set_reveal_radius(if self.fov_reveal > 0 {
+                Some(((FOV_REVEAL_FRAMES - self.fov_reveal) / FOV_REVEAL_FRAMES_PER_TILE) as i32)
+            } else {
+                None
+            });
+
         self.chunked_map_grid.draw(world, map_grid);
-        render::draw_renderables(&self.chunked_map_grid, world, map_grid);
+        if self.animations.is_empty() {
+            render::draw_renderables(&self.chunked_map_grid, world, map_grid);
+        } else {
+            let hidden: Vec<EntityId> = self.animations.iter().map(|(_, a)| a.entity()).collect();
+
+            render::draw_renderables_except(&self.chunked_map_grid, world, map_grid, &hidden);
+            self.draw_animations(world, map_grid, anim_grids);
+        }
 
         if self.redraw_msg_frame_grid {
             ui::draw_msg_frame(msg_frame_grid, false);
@@ -431,3 +753,356 @@ impl DungeonMode {
         ui::draw_ui(world, status_grid, item_grid, msg_grid, None);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use sdl2::{
+        event::Event,
+        keyboard::{Keycode, Mod},
+    };
+
+    use super::super::Mode;
+    use super::*;
+    use crate::{
+        components::{CombatStats, Poison},
+        gamesym::GameSym,
+        message,
+        player::PlayerAlive,
+        statehash,
+    };
+
+    /// Update the dungeon mode with a single key press.
+    fn press(mode: &mut DungeonMode, world: &World, key: Keycode) -> (ModeControl, ModeUpdate) {
+        let mut inputs = InputBuffer::new();
+
+        inputs.handle_event(&Event::KeyDown {
+            timestamp: 0,
+            window_id: 0,
+            keycode: Some(key),
+            scancode: None,
+            keymod: Mod::NOMOD,
+            repeat: false,
+        });
+        mode.update(world, &mut inputs, &[], &None)
+    }
+
+    /// Start resting with shift and space, then keep updating until resting stops, calling
+    /// `each_turn` after every update while the player is resting.
+    fn rest(mode: &mut DungeonMode, world: &World, mut each_turn: impl FnMut(&World)) {
+        let mut inputs = InputBuffer::new();
+
+        for key in [Keycode::LShift, Keycode::Space] {
+            inputs.handle_event(&Event::KeyDown {
+                timestamp: 0,
+                window_id: 0,
+                keycode: Some(key),
+                scancode: None,
+                keymod: Mod::NOMOD,
+                repeat: false,
+            });
+            mode.update(world, &mut inputs, &[], &None);
+            inputs.clear_input();
+        }
+        assert!(world.run(player::player_is_auto_running));
+
+        for _ in 0..1000 {
+            if !world.run(player::player_is_auto_running) {
+                return;
+            }
+            each_turn(world);
+            mode.update(world, &mut InputBuffer::new(), &[], &None);
+        }
+        panic!("resting never stopped");
+    }
+
+    /// Set the player's hit points to a percentage of their maximum, returning the maximum.
+    fn hurt_player(world: &World, percent: i32) -> i32 {
+        let player_id = world.borrow::<UniqueView<PlayerId>>().0;
+        let mut combat_stats = world.borrow::<ViewMut<CombatStats>>();
+        let stats = (&mut combat_stats).get(player_id);
+
+        stats.hp = stats.max_hp * percent / 100;
+        stats.max_hp
+    }
+
+    fn player_hp(world: &World) -> i32 {
+        let player_id = world.borrow::<UniqueView<PlayerId>>().0;
+
+        world.borrow::<View<CombatStats>>().get(player_id).hp
+    }
+
+    #[test]
+    fn no_input_is_taken_after_dying_to_poison() {
+        let world = spawn::test_world(20, 20, (5, 5));
+        let player_id = world.borrow::<UniqueView<PlayerId>>().0;
+        let mut mode = DungeonMode::new();
+
+        world.run(vision::recalculate_fields_of_view);
+        (&mut world.borrow::<ViewMut<CombatStats>>())
+            .get(player_id)
+            .hp = 1;
+        world.borrow::<EntitiesView>().add_component(
+            &mut world.borrow::<ViewMut<Poison>>(),
+            Poison {
+                damage: 5,
+                turns: 3,
+            },
+            player_id,
+        );
+
+        press(&mut mode, &world, Keycode::Period);
+        assert!(!world.borrow::<UniqueView<PlayerAlive>>().0);
+
+        let turn = world.borrow::<UniqueView<TurnCount>>().0;
+        let (control, _) = press(&mut mode, &world, Keycode::Right);
+        let pos = world.run(get_player_pos);
+
+        assert!(matches!(control, ModeControl::Stay));
+        assert_eq!((pos.x, pos.y), (5, 5));
+        assert_eq!(world.borrow::<UniqueView<TurnCount>>().0, turn);
+
+        let (control, _) = press(&mut mode, &world, Keycode::Space);
+        assert!(matches!(
+            control,
+            ModeControl::Switch(Mode::GameOverMode(_))
+        ));
+    }
+
+    /// Keep updating without input until any animations have played out.
+    fn settle(mode: &mut DungeonMode, world: &World) {
+        for _ in 0..1000 {
+            let (_, update) = mode.update(world, &mut InputBuffer::new(), &[], &None);
+
+            if matches!(update, ModeUpdate::WaitForEvent) {
+                return;
+            }
+        }
+        panic!("animations never finished");
+    }
+
+    #[test]
+    fn only_actions_that_take_a_turn_advance_the_turn_count() {
+        let world = spawn::test_world(20, 20, (5, 5));
+        let mut mode = DungeonMode::new();
+        let turn_count = || world.borrow::<UniqueView<TurnCount>>().0;
+
+        world.run(vision::recalculate_fields_of_view);
+        let start = turn_count();
+
+        for _ in 0..4 {
+            press(&mut mode, &world, Keycode::Right);
+            settle(&mut mode, &world);
+        }
+
+        let pos = world.run(get_player_pos);
+        assert_eq!((pos.x, pos.y), (9, 5));
+        assert_eq!(turn_count(), start + 4);
+
+        let (control, _) = press(&mut mode, &world, Keycode::Escape);
+        assert!(matches!(
+            control,
+            ModeControl::Push(Mode::OptionsMenuMode(_))
+        ));
+        mode.update(
+            &world,
+            &mut InputBuffer::new(),
+            &[],
+            &Some(ModeResult::OptionsMenuModeResult(
+                OptionsMenuModeResult::Closed,
+            )),
+        );
+        settle(&mut mode, &world);
+
+        assert_eq!(turn_count(), start + 4);
+    }
+
+    #[test]
+    fn low_hp_is_warned_about_once_until_it_recovers() {
+        let world = spawn::test_world(20, 20, (5, 5));
+        let mut mode = DungeonMode::new();
+        let mut warnings = Vec::new();
+        let mut flashes = Vec::new();
+
+        world.borrow::<UniqueViewMut<Options>>().screen_effects = true;
+        world.run(vision::recalculate_fields_of_view);
+
+        for percent in [20, 20, 20, 100, 20] {
+            hurt_player(&world, percent);
+            press(&mut mode, &world, Keycode::Period);
+            warnings.push(world.borrow::<UniqueViewMut<Messages>>().take_warning());
+            flashes.push(mode.low_hp_flash > 0);
+        }
+
+        assert_eq!(warnings, [true, false, false, false, true]);
+        assert_eq!(flashes, warnings);
+    }
+
+    /// Descend to a new level with screen effects on or off and let any reveal play out,
+    /// returning whether there was a reveal and which tiles of the new level were seen.
+    fn descend_and_settle(screen_effects: bool) -> (bool, Vec<bool>) {
+        let world = spawn::test_world(80, 50, (5, 5));
+        let mut mode = DungeonMode::new();
+        let pop_result = Some(ModeResult::YesNoDialogModeResult(
+            YesNoDialogModeResult::Yes,
+        ));
+
+        world.borrow::<UniqueViewMut<Options>>().screen_effects = screen_effects;
+        world.run(vision::recalculate_fields_of_view);
+        mode.update(&world, &mut InputBuffer::new(), &[], &pop_result);
+        saveload::delete_save_file();
+
+        let revealed = mode.fov_reveal > 0;
+
+        for _ in 0..100 {
+            let (_, update) = mode.update(&world, &mut InputBuffer::new(), &[], &None);
+
+            if matches!(update, ModeUpdate::WaitForEvent) {
+                break;
+            }
+        }
+        assert_eq!(mode.fov_reveal, 0);
+
+        let map = world.borrow::<UniqueView<Map>>();
+        let seen = (0..map.height)
+            .flat_map(|y| (0..map.width).map(move |x| (x, y)))
+            .map(|(x, y)| map.seen.get_bit(x, y))
+            .collect();
+
+        (revealed, seen)
+    }
+
+    #[test]
+    fn revealing_a_new_level_explores_what_an_instant_reveal_would() {
+        let (revealed, seen) = descend_and_settle(true);
+        let (instant_revealed, instant_seen) = descend_and_settle(false);
+
+        assert!(revealed);
+        assert!(!instant_revealed);
+        assert!(seen.contains(&true));
+        assert_eq!(seen, instant_seen);
+    }
+
+    #[test]
+    fn resting_stops_at_the_configured_threshold() {
+        let world = spawn::test_world(20, 20, (5, 5));
+        let mut mode = DungeonMode::new();
+        let mut hps = Vec::new();
+
+        world.borrow::<UniqueViewMut<Options>>().rest_until_percent = 70;
+        world.run(vision::recalculate_fields_of_view);
+        let max_hp = hurt_player(&world, 40);
+
+        rest(&mut mode, &world, |world| hps.push(player_hp(world)));
+
+        // Resting went on while below the threshold, but stopped as soon as it was reached.
+        let hp = player_hp(&world);
+        let rested_enough = |hp: i32| hp * 100 >= max_hp * 70;
+        assert!(rested_enough(hp));
+        assert!(hps.iter().any(|&hp| !rested_enough(hp)));
+        assert!(hps
+            .iter()
+            .filter(|&&hp| rested_enough(hp))
+            .all(|&h| h == hp));
+        assert!(hp < max_hp);
+    }
+
+    #[test]
+    fn resting_from_half_hp_to_full_takes_the_expected_turns() {
+        let world = spawn::test_world(20, 20, (5, 5));
+        let mut mode = DungeonMode::new();
+
+        world.run(vision::recalculate_fields_of_view);
+        let max_hp = hurt_player(&world, 50);
+        let start_hp = player_hp(&world);
+        let start_turn = world.borrow::<UniqueView<TurnCount>>().0;
+
+        rest(&mut mode, &world, |_| {});
+
+        // A well-fed player regenerates their maximum hit points over 300 turns.
+        let expected_turns = (1..)
+            .find(|turns| turns * max_hp / 300 >= max_hp - start_hp)
+            .unwrap();
+
+        assert_eq!(player_hp(&world), max_hp);
+        assert_eq!(
+            world.borrow::<UniqueView<TurnCount>>().0 - start_turn,
+            expected_turns as u64
+        );
+    }
+
+    #[test]
+    fn warnings_interrupt_resting() {
+        let world = spawn::test_world(20, 20, (5, 5));
+        let mut mode = DungeonMode::new();
+        let mut turns = 0;
+
+        world.run(vision::recalculate_fields_of_view);
+        let max_hp = hurt_player(&world, 40);
+        let start_hp = player_hp(&world);
+
+        rest(&mut mode, &world, |world| {
+            turns += 1;
+            let mut msgs = world.borrow::<UniqueViewMut<Messages>>();
+            if turns == 3 {
+                msgs.add_colored("Something stings you!".into(), message::WARNING_COLOR);
+            } else {
+                msgs.add("Something rustles.".into());
+            }
+        });
+
+        assert_eq!(turns, 3);
+        assert!(player_hp(&world) < max_hp);
+        assert!(player_hp(&world) >= start_hp);
+    }
+
+    /// Play out a few turns of goblins closing in on the player, with the given delay between
+    /// each monster's animations, returning a hash of the game state and the messages shown.
+    fn goblin_ambush(delay: u32) -> (u64, Vec<String>) {
+        let world = spawn::test_world(20, 20, (5, 5));
+        let mut mode = DungeonMode::new();
+        let mut staggered = false;
+
+        {
+            let mut options = world.borrow::<UniqueViewMut<Options>>();
+            options.smooth_scroll = true;
+            options.lunge_animations = true;
+            options.monster_action_delay = delay;
+        }
+        for pos in [(9, 5), (5, 9), (9, 9)] {
+            spawn::spawn_monster_kind(&world, GameSym::Goblin, pos).unwrap();
+        }
+        world.run(vision::recalculate_fields_of_view);
+
+        for _ in 0..6 {
+            press(&mut mode, &world, Keycode::Period);
+            staggered |= mode.animations.iter().any(|&(start, _)| start > 0);
+
+            // Let the animations play out in full.
+            for _ in 0..1000 {
+                let (_, update) = mode.update(&world, &mut InputBuffer::new(), &[], &None);
+                if matches!(update, ModeUpdate::WaitForEvent) {
+                    break;
+                }
+            }
+        }
+        assert_eq!(staggered, delay > 0);
+
+        let msgs = world
+            .borrow::<UniqueView<Messages>>()
+            .rev_iter()
+            .map(|(msg, ..)| msg.to_string())
+            .collect();
+
+        (statehash::world_hash(&world), msgs)
+    }
+
+    #[test]
+    fn monster_action_delay_does_not_change_turn_outcomes() {
+        let (instant_hash, instant_msgs) = goblin_ambush(0);
+        let (delayed_hash, delayed_msgs) = goblin_ambush(8);
+
+        assert_eq!(instant_hash, delayed_hash);
+        assert_eq!(instant_msgs, delayed_msgs);
+        assert!(instant_msgs.iter().any(|msg| msg.starts_with("The goblin")));
+    }
+}
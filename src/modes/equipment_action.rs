@@ -141,7 +141,7 @@ impl EquipmentActionMode {
 
     pub fn update(
         &mut self,
-        _world: &World,
+        world: &World,
         inputs: &mut InputBuffer,
         _grids: &[TileGrid<GameSym>],
         _pop_result: &Option<ModeResult>,
@@ -154,7 +154,7 @@ impl EquipmentActionMode {
                 ModeUpdate::Immediate,
             );
         } else if let Some(InputEvent::Press(keycode)) = inputs.get_input() {
-            match gamekey::from_keycode(keycode, inputs.get_mods(KeyMods::SHIFT)) {
+            match gamekey::from_keycode(world, keycode, inputs.get_mods(KeyMods::SHIFT)) {
                 GameKey::Up => match self.subsection {
                     SubSection::Actions => {
                         if self.selection > 0 {
@@ -159,7 +159,7 @@ impl EquipmentShortcutMode {
                     ModeUpdate::Immediate,
                 );
             } else if let Some(InputEvent::Press(keycode)) = inputs.get_input() {
-                match gamekey::from_keycode(keycode, inputs.get_mods(KeyMods::SHIFT)) {
+                match gamekey::from_keycode(world, keycode, inputs.get_mods(KeyMods::SHIFT)) {
                     GameKey::Up => match self.subsection {
                         SubSection::Items => {
                             if self.selection > 0 {
@@ -188,7 +188,7 @@ impl EquipmentShortcutMode {
                     },
                     GameKey::PageUp => {
                         if matches!(self.subsection, SubSection::Items) {
-                            if let Some(grid) = grids.get(0) {
+                            if let Some(grid) = grids.first() {
                                 self.selection = self
                                     .selection
                                     .saturating_sub(Self::item_list_height(grid))
@@ -198,7 +198,7 @@ impl EquipmentShortcutMode {
                     }
                     GameKey::PageDown => {
                         if matches!(self.subsection, SubSection::Items) {
-                            if let Some(grid) = grids.get(0) {
+                            if let Some(grid) = grids.first() {
                                 let max_selection = (self.items.len() as i32 - 1).max(0);
 
                                 self.selection = self
@@ -4,6 +4,7 @@ use crate::{
     components::{CombatStats, Equipment, Experience, HurtBy, Inventory, Name, Tally},
     gamekey::{self, GameKey},
     gamesym::GameSym,
+    highscore,
     map::Map,
     player::{PlayerAlive, PlayerId},
     ui::Options,
@@ -16,6 +17,7 @@ use ruggrogue::{
 
 use super::{
     dungeon::DungeonMode,
+    message_box::{MessageBoxMode, MessageBoxModeResult},
     title::{self, TitleMode},
     ModeControl, ModeResult, ModeUpdate,
 };
@@ -24,11 +26,38 @@ pub enum GameOverModeResult {
     AppQuit,
 }
 
-pub struct GameOverMode;
+pub struct GameOverMode {
+    /// High score table to show before leaving, once the run has been recorded in it.
+    high_scores: Option<Vec<String>>,
+    high_scores_shown: bool,
+}
 
 impl GameOverMode {
     pub fn new() -> Self {
-        Self {}
+        Self {
+            high_scores: None,
+            high_scores_shown: false,
+        }
+    }
+
+    fn finish(world: &World, inputs: &mut InputBuffer) -> (ModeControl, ModeUpdate) {
+        let player_alive = world.borrow::<UniqueView<PlayerAlive>>().0;
+
+        title::post_game_cleanup(world, !player_alive);
+        if player_alive {
+            title::new_game_setup(world, true);
+        }
+
+        inputs.clear_input();
+        (
+            ModeControl::Switch(if player_alive {
+                // Jump straight into new game plus.
+                DungeonMode::new().into()
+            } else {
+                TitleMode::new().into()
+            }),
+            ModeUpdate::Immediate,
+        )
     }
 
     pub fn prepare_grids(
@@ -60,8 +89,30 @@ impl GameOverMode {
         world: &World,
         inputs: &mut InputBuffer,
         _grids: &[TileGrid<GameSym>],
-        _pop_result: &Option<ModeResult>,
+        pop_result: &Option<ModeResult>,
     ) -> (ModeControl, ModeUpdate) {
+        // Record the run right away, whether it ended in defeat or victory.
+        if self.high_scores.is_none() {
+            let (high_scores, rank) = highscore::record_run(world);
+            let mut lines = high_scores.describe();
+
+            if let Some(rank) = rank {
+                lines.push("".to_string());
+                lines.push(format!("New high score!  You placed #{}.", rank + 1));
+            }
+            self.high_scores = Some(lines);
+        }
+
+        if let Some(ModeResult::MessageBoxModeResult(result)) = pop_result {
+            return match result {
+                MessageBoxModeResult::AppQuit => (
+                    ModeControl::Pop(GameOverModeResult::AppQuit.into()),
+                    ModeUpdate::Immediate,
+                ),
+                MessageBoxModeResult::Done => Self::finish(world, inputs),
+            };
+        }
+
         inputs.prepare_input();
 
         if let Some(InputEvent::AppQuit) = inputs.get_input() {
@@ -70,25 +121,20 @@ impl GameOverMode {
                 ModeUpdate::Immediate,
             );
         } else if let Some(InputEvent::Press(keycode)) = inputs.get_input() {
-            let key = gamekey::from_keycode(keycode, inputs.get_mods(KeyMods::SHIFT));
+            let key = gamekey::from_keycode(world, keycode, inputs.get_mods(KeyMods::SHIFT));
             if matches!(key, GameKey::Confirm | GameKey::Cancel) {
-                let player_alive = world.borrow::<UniqueView<PlayerAlive>>().0;
-
-                title::post_game_cleanup(world, !player_alive);
-                if player_alive {
-                    title::new_game_setup(world, true);
+                if let Some(lines) = &self.high_scores {
+                    if !self.high_scores_shown {
+                        self.high_scores_shown = true;
+                        inputs.clear_input();
+                        return (
+                            ModeControl::Push(MessageBoxMode::new(lines.clone()).into()),
+                            ModeUpdate::Immediate,
+                        );
+                    }
                 }
 
-                inputs.clear_input();
-                return (
-                    ModeControl::Switch(if player_alive {
-                        // Jump straight into new game plus.
-                        DungeonMode::new().into()
-                    } else {
-                        TitleMode::new().into()
-                    }),
-                    ModeUpdate::Immediate,
-                );
+                return Self::finish(world, inputs);
             }
         }
 
@@ -144,6 +190,7 @@ impl GameOverMode {
                         names.get(*hurter).0.as_str()
                     }
                 }
+                Ok(HurtBy::Poison) => "poison",
                 Ok(HurtBy::Starvation) => "starvation",
                 Err(_) => "perfectly natural causes",
             };
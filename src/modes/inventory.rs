@@ -31,6 +31,7 @@ pub enum InventoryModeResult {
     DropEquipment(EntityId),
     EquipItem(EntityId),
     UseItem(EntityId, Option<(i32, i32)>),
+    ThrowItem(EntityId, (i32, i32)),
     DropItem(EntityId),
 }
 
@@ -204,6 +205,10 @@ impl InventoryMode {
                         ModeControl::Pop(InventoryModeResult::UseItem(*item_id, *target).into()),
                         ModeUpdate::Immediate,
                     ),
+                    InventoryActionModeResult::ThrowItem(item_id, target) => (
+                        ModeControl::Pop(InventoryModeResult::ThrowItem(*item_id, *target).into()),
+                        ModeUpdate::Immediate,
+                    ),
                     InventoryActionModeResult::DropItem(item_id) => (
                         ModeControl::Pop(InventoryModeResult::DropItem(*item_id).into()),
                         ModeUpdate::Immediate,
@@ -249,7 +254,10 @@ impl InventoryMode {
             let player_inv = inventories.get(player_id.0);
             let shift = inputs.get_mods(KeyMods::SHIFT);
 
-            match (&self.subsection, gamekey::from_keycode(keycode, shift)) {
+            match (
+                &self.subsection,
+                gamekey::from_keycode(world, keycode, shift),
+            ) {
                 (SubSection::EquipWeapon, GameKey::Up) => {
                     self.subsection = SubSection::Inventory;
                     self.inv_selection = if player_inv.items.is_empty() {
@@ -377,21 +385,19 @@ impl InventoryMode {
                 (SubSection::Inventory, GameKey::End) => {
                     self.inv_selection = (player_inv.items.len() as i32 - 1).max(0);
                 }
-                (SubSection::Inventory, GameKey::Confirm) => {
-                    if !player_inv.items.is_empty() {
-                        inputs.clear_input();
-                        return (
-                            ModeControl::Push(
-                                InventoryActionMode::new(
-                                    world,
-                                    player_inv.items[self.inv_selection as usize],
-                                    None,
-                                )
-                                .into(),
-                            ),
-                            ModeUpdate::Immediate,
-                        );
-                    }
+                (SubSection::Inventory, GameKey::Confirm) if !player_inv.items.is_empty() => {
+                    inputs.clear_input();
+                    return (
+                        ModeControl::Push(
+                            InventoryActionMode::new(
+                                world,
+                                player_inv.items[self.inv_selection as usize],
+                                None,
+                            )
+                            .into(),
+                        ),
+                        ModeUpdate::Immediate,
+                    );
                 }
                 (SubSection::Inventory, key)
                     if matches!(
@@ -5,6 +5,7 @@ use crate::{
     gamekey::{self, GameKey},
     gamesym::GameSym,
     ui::{self, Options},
+    vision::DistanceMetric,
 };
 use ruggrogue::{
     util::{Color, Size},
@@ -18,11 +19,15 @@ use super::{
 
 const CANCEL: &str = "[ Cancel ]";
 
+/// How far away items can be thrown.
+const THROW_RANGE: i32 = 6;
+
 pub enum InventoryActionModeResult {
     AppQuit,
     Cancelled,
     EquipItem(EntityId),
     UseItem(EntityId, Option<(i32, i32)>),
+    ThrowItem(EntityId, (i32, i32)),
     DropItem(EntityId),
 }
 
@@ -36,6 +41,7 @@ enum SubSection {
 pub enum InventoryAction {
     EquipItem,
     UseItem,
+    ThrowItem,
     DropItem,
 }
 
@@ -44,6 +50,7 @@ impl InventoryAction {
         match key {
             GameKey::EquipItem => Some(InventoryAction::EquipItem),
             GameKey::UseItem => Some(InventoryAction::UseItem),
+            GameKey::ThrowItem => Some(InventoryAction::ThrowItem),
             GameKey::DropItem => Some(InventoryAction::DropItem),
             _ => None,
         }
@@ -56,7 +63,7 @@ impl InventoryAction {
                 world.borrow::<View<Consumable>>().contains(item_id)
                     | world.borrow::<View<Victory>>().contains(item_id)
             }
-            InventoryAction::DropItem => true,
+            InventoryAction::ThrowItem | InventoryAction::DropItem => true,
         }
     }
 
@@ -64,6 +71,7 @@ impl InventoryAction {
         match self {
             InventoryAction::EquipItem => "Equip",
             InventoryAction::UseItem => "Apply",
+            InventoryAction::ThrowItem => "Throw",
             InventoryAction::DropItem => "Drop",
         }
     }
@@ -72,17 +80,34 @@ impl InventoryAction {
         match self {
             InventoryAction::EquipItem => "[ Equip ]",
             InventoryAction::UseItem => "[ Apply ]",
+            InventoryAction::ThrowItem => "[ Throw ]",
             InventoryAction::DropItem => "[ Drop ]",
         }
     }
 }
 
+/// Pick a target to throw an item at.
+pub fn throw_target_mode(world: &World, item_id: EntityId) -> TargetMode {
+    let item_name = world.borrow::<View<Name>>().get(item_id).0.clone();
+
+    TargetMode::new(
+        world,
+        format!("throwing {}", item_name),
+        THROW_RANGE,
+        0,
+        DistanceMetric::Euclidean,
+        false,
+    )
+}
+
 pub struct InventoryActionMode {
     item_id: EntityId,
     inner_width: i32,
     actions: Vec<InventoryAction>,
     subsection: SubSection,
     selection: i32,
+    /// True if a target is being picked to throw the item at instead of using it on.
+    throwing: bool,
 }
 
 /// Show a menu of actions for a single item in the player's inventory.
@@ -91,6 +116,7 @@ impl InventoryActionMode {
         let actions = [
             InventoryAction::EquipItem,
             InventoryAction::UseItem,
+            InventoryAction::ThrowItem,
             InventoryAction::DropItem,
         ]
         .iter()
@@ -116,6 +142,7 @@ impl InventoryActionMode {
             actions,
             subsection,
             selection: selection as i32,
+            throwing: false,
         }
     }
 
@@ -146,12 +173,16 @@ impl InventoryActionMode {
         grids[0].view.zoom = text_zoom;
     }
 
-    fn confirm_action(&self, world: &World, inputs: &mut InputBuffer) -> (ModeControl, ModeUpdate) {
+    fn confirm_action(
+        &mut self,
+        world: &World,
+        inputs: &mut InputBuffer,
+    ) -> (ModeControl, ModeUpdate) {
         let result = match self.subsection {
             SubSection::Actions => match self.actions[self.selection as usize] {
                 InventoryAction::EquipItem => InventoryActionModeResult::EquipItem(self.item_id),
                 InventoryAction::UseItem => {
-                    if let Some(Ranged { range }) =
+                    if let Some(Ranged { range, metric }) =
                         &world.borrow::<View<Ranged>>().try_get(self.item_id).ok()
                     {
                         let item_name = world.borrow::<View<Name>>().get(self.item_id).0.clone();
@@ -163,7 +194,8 @@ impl InventoryActionMode {
                         inputs.clear_input();
                         return (
                             ModeControl::Push(
-                                TargetMode::new(world, item_name, *range, radius, true).into(),
+                                TargetMode::new(world, item_name, *range, radius, *metric, true)
+                                    .into(),
                             ),
                             ModeUpdate::Immediate,
                         );
@@ -171,6 +203,14 @@ impl InventoryActionMode {
                         InventoryActionModeResult::UseItem(self.item_id, None)
                     }
                 }
+                InventoryAction::ThrowItem => {
+                    self.throwing = true;
+                    inputs.clear_input();
+                    return (
+                        ModeControl::Push(throw_target_mode(world, self.item_id).into()),
+                        ModeUpdate::Immediate,
+                    );
+                }
                 InventoryAction::DropItem => InventoryActionModeResult::DropItem(self.item_id),
             },
             SubSection::Cancel => InventoryActionModeResult::Cancelled,
@@ -193,10 +233,18 @@ impl InventoryActionMode {
                         ModeControl::Pop(InventoryActionModeResult::AppQuit.into()),
                         ModeUpdate::Immediate,
                     ),
-                    TargetModeResult::Cancelled => (ModeControl::Stay, ModeUpdate::WaitForEvent),
+                    TargetModeResult::Cancelled => {
+                        self.throwing = false;
+                        (ModeControl::Stay, ModeUpdate::WaitForEvent)
+                    }
                     TargetModeResult::Target { x, y } => (
                         ModeControl::Pop(
-                            InventoryActionModeResult::UseItem(self.item_id, Some((*x, *y))).into(),
+                            if self.throwing {
+                                InventoryActionModeResult::ThrowItem(self.item_id, (*x, *y))
+                            } else {
+                                InventoryActionModeResult::UseItem(self.item_id, Some((*x, *y)))
+                            }
+                            .into(),
                         ),
                         ModeUpdate::Immediate,
                     ),
@@ -213,7 +261,7 @@ impl InventoryActionMode {
                 ModeUpdate::Immediate,
             );
         } else if let Some(InputEvent::Press(keycode)) = inputs.get_input() {
-            match gamekey::from_keycode(keycode, inputs.get_mods(KeyMods::SHIFT)) {
+            match gamekey::from_keycode(world, keycode, inputs.get_mods(KeyMods::SHIFT)) {
                 GameKey::Down => match self.subsection {
                     SubSection::Actions => {
                         if self.selection < self.actions.len() as i32 - 1 {
@@ -251,7 +299,10 @@ impl InventoryActionMode {
                     )
                 }
                 GameKey::Confirm => return self.confirm_action(world, inputs),
-                key @ GameKey::EquipItem | key @ GameKey::UseItem | key @ GameKey::DropItem => {
+                key @ GameKey::EquipItem
+                | key @ GameKey::UseItem
+                | key @ GameKey::ThrowItem
+                | key @ GameKey::DropItem => {
                     if let Some(inv_action) = InventoryAction::from_key(key) {
                         if let Some(action_pos) = self.actions.iter().position(|a| *a == inv_action)
                         {
@@ -15,7 +15,7 @@ use ruggrogue::{
 };
 
 use super::{
-    inventory_action::InventoryAction,
+    inventory_action::{self, InventoryAction},
     target::{TargetMode, TargetModeResult},
     ModeControl, ModeResult, ModeUpdate,
 };
@@ -27,6 +27,7 @@ pub enum InventoryShortcutModeResult {
     Cancelled,
     EquipItem(EntityId),
     UseItem(EntityId, Option<(i32, i32)>),
+    ThrowItem(EntityId, (i32, i32)),
     DropItem(EntityId),
 }
 
@@ -72,6 +73,7 @@ impl InventoryShortcutMode {
         let selection = match action {
             InventoryAction::EquipItem => menu_memory[MenuMemory::INVENTORY_SHORTCUT_EQUIP],
             InventoryAction::UseItem => menu_memory[MenuMemory::INVENTORY_SHORTCUT_USE],
+            InventoryAction::ThrowItem => menu_memory[MenuMemory::INVENTORY_SHORTCUT_THROW],
             InventoryAction::DropItem => menu_memory[MenuMemory::INVENTORY_SHORTCUT_DROP],
         };
         let selection = selection.min(items.len().saturating_sub(1) as i32);
@@ -128,7 +130,7 @@ impl InventoryShortcutMode {
             SubSection::Items => match self.action {
                 InventoryAction::EquipItem => InventoryShortcutModeResult::EquipItem(item_id),
                 InventoryAction::UseItem => {
-                    if let Some(Ranged { range }) =
+                    if let Some(Ranged { range, metric }) =
                         &world.borrow::<View<Ranged>>().try_get(item_id).ok()
                     {
                         let item_name = world.borrow::<View<Name>>().get(item_id).0.clone();
@@ -140,7 +142,8 @@ impl InventoryShortcutMode {
                         inputs.clear_input();
                         return (
                             ModeControl::Push(
-                                TargetMode::new(world, item_name, *range, radius, true).into(),
+                                TargetMode::new(world, item_name, *range, radius, *metric, true)
+                                    .into(),
                             ),
                             ModeUpdate::Immediate,
                         );
@@ -148,6 +151,15 @@ impl InventoryShortcutMode {
                         InventoryShortcutModeResult::UseItem(item_id, None)
                     }
                 }
+                InventoryAction::ThrowItem => {
+                    inputs.clear_input();
+                    return (
+                        ModeControl::Push(
+                            inventory_action::throw_target_mode(world, item_id).into(),
+                        ),
+                        ModeUpdate::Immediate,
+                    );
+                }
                 InventoryAction::DropItem => InventoryShortcutModeResult::DropItem(item_id),
             },
             SubSection::Cancel => InventoryShortcutModeResult::Cancelled,
@@ -181,16 +193,21 @@ impl InventoryShortcutMode {
                         ModeUpdate::Immediate,
                     ),
                     TargetModeResult::Cancelled => (ModeControl::Stay, ModeUpdate::WaitForEvent),
-                    TargetModeResult::Target { x, y } => (
-                        ModeControl::Pop(
-                            InventoryShortcutModeResult::UseItem(
-                                self.items[self.selection as usize],
-                                Some((*x, *y)),
-                            )
-                            .into(),
-                        ),
-                        ModeUpdate::Immediate,
-                    ),
+                    TargetModeResult::Target { x, y } => {
+                        let item_id = self.items[self.selection as usize];
+
+                        (
+                            ModeControl::Pop(
+                                if matches!(self.action, InventoryAction::ThrowItem) {
+                                    InventoryShortcutModeResult::ThrowItem(item_id, (*x, *y))
+                                } else {
+                                    InventoryShortcutModeResult::UseItem(item_id, Some((*x, *y)))
+                                }
+                                .into(),
+                            ),
+                            ModeUpdate::Immediate,
+                        )
+                    }
                 },
                 _ => unreachable!(),
             }
@@ -203,7 +220,7 @@ impl InventoryShortcutMode {
                     ModeUpdate::Immediate,
                 );
             } else if let Some(InputEvent::Press(keycode)) = inputs.get_input() {
-                match gamekey::from_keycode(keycode, inputs.get_mods(KeyMods::SHIFT)) {
+                match gamekey::from_keycode(world, keycode, inputs.get_mods(KeyMods::SHIFT)) {
                     GameKey::Up => match self.subsection {
                         SubSection::Items => {
                             if self.selection > 0 {
@@ -232,7 +249,7 @@ impl InventoryShortcutMode {
                     },
                     GameKey::PageUp => {
                         if matches!(self.subsection, SubSection::Items) {
-                            if let Some(grid) = grids.get(0) {
+                            if let Some(grid) = grids.first() {
                                 self.selection = self
                                     .selection
                                     .saturating_sub(Self::item_list_height(grid))
@@ -242,7 +259,7 @@ impl InventoryShortcutMode {
                     }
                     GameKey::PageDown => {
                         if matches!(self.subsection, SubSection::Items) {
-                            if let Some(grid) = grids.get(0) {
+                            if let Some(grid) = grids.first() {
                                 let max_selection = (self.items.len() as i32 - 1).max(0);
 
                                 self.selection = self
@@ -289,6 +306,9 @@ impl InventoryShortcutMode {
                         InventoryAction::UseItem => {
                             &mut menu_memory[MenuMemory::INVENTORY_SHORTCUT_USE]
                         }
+                        InventoryAction::ThrowItem => {
+                            &mut menu_memory[MenuMemory::INVENTORY_SHORTCUT_THROW]
+                        }
                         InventoryAction::DropItem => {
                             &mut menu_memory[MenuMemory::INVENTORY_SHORTCUT_DROP]
                         }
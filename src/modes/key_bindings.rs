@@ -0,0 +1,228 @@
+use sdl2::keyboard::Keycode;
+use shipyard::{UniqueView, UniqueViewMut, World};
+
+use crate::{
+    gamekey::{self, GameKey, Key, KeyBindings, REBINDABLE_KEYS},
+    gamesym::GameSym,
+    ui::{self, Options},
+};
+use ruggrogue::{
+    util::{Color, Size},
+    InputBuffer, InputEvent, KeyMods, TileGrid, Tileset,
+};
+
+use super::{ModeControl, ModeResult, ModeUpdate};
+
+const TITLE: &str = "< Key bindings >";
+const HINT: &str = "[enter] Add key  [backspace] Clear  [del] Default  [esc] Back";
+const REBIND_HINT: &str = "Press a key to add, or [esc] to cancel.";
+const KEY_TAKEN: &str = "That key is bound to something else; clear it first.";
+const NAME_WIDTH: usize = 13;
+const KEY_NAMES_WIDTH: usize = 30;
+const UNBOUND: &str = "-";
+
+pub enum KeyBindingsModeResult {
+    AppQuit,
+    Done,
+}
+
+pub struct KeyBindingsMode {
+    selection: usize,
+    rebinding: bool,
+    key_taken: bool,
+}
+
+/// Number of key binding rows that fit in the grid at once.
+fn page_height(grid: &TileGrid<GameSym>) -> usize {
+    grid.height().saturating_sub(6).max(1) as usize
+}
+
+/// Keys that only modify other keys, which can't be bound by themselves.
+fn is_modifier(keycode: Keycode) -> bool {
+    matches!(
+        keycode,
+        Keycode::LShift
+            | Keycode::RShift
+            | Keycode::LCtrl
+            | Keycode::RCtrl
+            | Keycode::LAlt
+            | Keycode::RAlt
+            | Keycode::LGui
+            | Keycode::RGui
+    )
+}
+
+/// A menu for choosing the keys bound to each game key.
+impl KeyBindingsMode {
+    pub fn new() -> Self {
+        Self {
+            selection: 0,
+            rebinding: false,
+            key_taken: false,
+        }
+    }
+
+    pub fn prepare_grids(
+        &self,
+        world: &World,
+        grids: &mut Vec<TileGrid<GameSym>>,
+        tilesets: &[Tileset<GameSym>],
+        window_size: Size,
+    ) {
+        let Options {
+            font, text_zoom, ..
+        } = *world.borrow::<UniqueView<Options>>();
+        let tileset = &tilesets.get(font as usize).unwrap_or(&tilesets[0]);
+        let max_height = window_size.h / (tileset.tile_height() * text_zoom);
+        let new_grid_size = Size {
+            w: 4 + HINT
+                .len()
+                .max(REBIND_HINT.len())
+                .max(KEY_TAKEN.len())
+                .max(NAME_WIDTH + KEY_NAMES_WIDTH) as u32,
+            h: (6 + REBINDABLE_KEYS.len() as u32).min(max_height).max(8),
+        };
+
+        if !grids.is_empty() {
+            grids[0].resize(new_grid_size);
+        } else {
+            grids.push(TileGrid::new(new_grid_size, tilesets, font as usize));
+            grids[0].view.clear_color = None;
+        }
+
+        grids[0].set_tileset(tilesets, font as usize);
+        grids[0].view_centered(tilesets, text_zoom, (0, 0).into(), window_size);
+        grids[0].view.zoom = text_zoom;
+    }
+
+    pub fn update(
+        &mut self,
+        world: &World,
+        inputs: &mut InputBuffer,
+        _grids: &[TileGrid<GameSym>],
+        _pop_result: &Option<ModeResult>,
+    ) -> (ModeControl, ModeUpdate) {
+        inputs.prepare_input();
+
+        if let Some(InputEvent::AppQuit) = inputs.get_input() {
+            return (
+                ModeControl::Pop(KeyBindingsModeResult::AppQuit.into()),
+                ModeUpdate::Immediate,
+            );
+        } else if let Some(InputEvent::Press(keycode)) = inputs.get_input() {
+            let gkey = REBINDABLE_KEYS[self.selection].0;
+
+            self.key_taken = false;
+
+            let shift = inputs.get_mods(KeyMods::SHIFT);
+
+            // Navigation keys in this menu also work by their usual names, so that it can't be
+            // made unusable by unbinding the game keys that would otherwise control it.
+            let gkey_pressed = match keycode {
+                Keycode::Up => GameKey::Up,
+                Keycode::Down => GameKey::Down,
+                Keycode::Return => GameKey::Confirm,
+                Keycode::Escape => GameKey::Cancel,
+                _ => gamekey::from_keycode(world, keycode, shift),
+            };
+
+            if self.rebinding {
+                if is_modifier(keycode) {
+                    return (ModeControl::Stay, ModeUpdate::WaitForEvent);
+                }
+                if keycode != Keycode::Escape {
+                    self.key_taken = !world.borrow::<UniqueViewMut<KeyBindings>>().bind(
+                        gkey,
+                        Key {
+                            code: keycode,
+                            shift,
+                        },
+                    );
+                }
+                self.rebinding = false;
+            } else if keycode == Keycode::Backspace {
+                world.borrow::<UniqueViewMut<KeyBindings>>().unbind(gkey);
+            } else if keycode == Keycode::Delete {
+                world.borrow::<UniqueViewMut<KeyBindings>>().reset(gkey);
+            } else {
+                match gkey_pressed {
+                    GameKey::Up => {
+                        self.selection = if self.selection > 0 {
+                            self.selection - 1
+                        } else {
+                            REBINDABLE_KEYS.len() - 1
+                        };
+                    }
+                    GameKey::Down => {
+                        self.selection = (self.selection + 1) % REBINDABLE_KEYS.len();
+                    }
+                    GameKey::Home => self.selection = 0,
+                    GameKey::End => self.selection = REBINDABLE_KEYS.len() - 1,
+                    GameKey::Confirm => self.rebinding = true,
+                    GameKey::Cancel => {
+                        inputs.clear_input();
+                        return (
+                            ModeControl::Pop(KeyBindingsModeResult::Done.into()),
+                            ModeUpdate::Immediate,
+                        );
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        (ModeControl::Stay, ModeUpdate::WaitForEvent)
+    }
+
+    pub fn draw(&self, world: &World, grids: &mut [TileGrid<GameSym>], active: bool) {
+        let grid = &mut grids[0];
+        let fg = Color::WHITE;
+        let bg = Color::BLACK;
+        let selected_bg = ui::SELECTED_BG;
+        let key_bindings = world.borrow::<UniqueView<KeyBindings>>();
+        let page = page_height(grid);
+        let top = (self.selection + 1).saturating_sub(page);
+
+        grid.view.color_mod = if active { Color::WHITE } else { Color::GRAY };
+
+        grid.draw_box((0, 0), (grid.width(), grid.height()), fg, bg);
+        grid.print_color((2, 0), TITLE, true, Color::YELLOW, bg);
+
+        for (i, (gkey, name)) in REBINDABLE_KEYS.iter().enumerate().skip(top).take(page) {
+            let y = 2 + (i - top) as i32;
+            let keys = key_bindings.keys(*gkey);
+            let mut key_names = if keys.is_empty() {
+                UNBOUND.to_string()
+            } else {
+                keys.iter().map(Key::name).collect::<Vec<_>>().join(", ")
+            };
+
+            if self.rebinding && i == self.selection {
+                key_names.push_str(", ...");
+            }
+            if key_names.len() > KEY_NAMES_WIDTH {
+                key_names.truncate(KEY_NAMES_WIDTH - 3);
+                key_names.push_str("...");
+            }
+
+            grid.print((2, y), name);
+            grid.print_color(
+                (2 + NAME_WIDTH as i32, y),
+                &key_names,
+                true,
+                fg,
+                if i == self.selection { selected_bg } else { bg },
+            );
+        }
+
+        let hint_y = grid.height() as i32 - 3;
+
+        if self.key_taken {
+            grid.print_color((2, hint_y), KEY_TAKEN, true, Color::RED, bg);
+        } else if self.rebinding {
+            grid.print_color((2, hint_y), REBIND_HINT, true, Color::GRAY, bg);
+        } else {
+            grid.print_color((2, hint_y), HINT, true, Color::GRAY, bg);
+        }
+    }
+}
@@ -59,7 +59,7 @@ impl MessageBoxMode {
 
     pub fn update(
         &mut self,
-        _world: &World,
+        world: &World,
         inputs: &mut InputBuffer,
         _grids: &[TileGrid<GameSym>],
         _pop_result: &Option<ModeResult>,
@@ -72,7 +72,7 @@ impl MessageBoxMode {
                 ModeUpdate::Immediate,
             );
         } else if let Some(InputEvent::Press(keycode)) = inputs.get_input() {
-            let key = gamekey::from_keycode(keycode, inputs.get_mods(KeyMods::SHIFT));
+            let key = gamekey::from_keycode(world, keycode, inputs.get_mods(KeyMods::SHIFT));
             if matches!(key, GameKey::Confirm | GameKey::Cancel) {
                 inputs.clear_input();
                 return (
@@ -0,0 +1,280 @@
+use shipyard::{UniqueView, World};
+
+use crate::{
+    gamekey::{self, GameKey},
+    gamesym::GameSym,
+    message::Messages,
+    ui::Options,
+};
+use ruggrogue::{
+    util::{Color, Size},
+    InputBuffer, InputEvent, KeyMods, TileGrid, Tileset,
+};
+
+use super::{ModeControl, ModeResult, ModeUpdate};
+
+const TITLE: &str = "< Messages >";
+const HINT: &str = "[PgUp/PgDn/Home/End] Scroll  [esc] Close";
+
+pub enum MessageLogModeResult {
+    AppQuit,
+    Done,
+}
+
+pub struct MessageLogMode {
+    /// Index of the wrapped line shown at the top of the log; clamped when drawn so that it can be
+    /// left past the end to stick to the most recent messages.
+    top: usize,
+}
+
+/// Lines of the message history word-wrapped to the given width, oldest first, each with the color
+/// it's drawn in.  The first line of each message is marked with a '>' like the message pane, and
+/// prefixed with the turn that it occurred on.
+fn log_lines(world: &World, width: usize) -> Vec<(String, Color)> {
+    let messages = world.borrow::<UniqueView<Messages>>();
+    let mut history = messages.rev_iter().collect::<Vec<_>>();
+    let mut lines = Vec::new();
+    let turn_width = history
+        .iter()
+        .filter_map(|(_, _, _, turn)| *turn)
+        .max()
+        .map_or(0, |turn| turn.to_string().len() + 1);
+    let width = width.saturating_sub(turn_width).max(1);
+
+    history.reverse();
+
+    for (message, highlighted, color, turn) in history {
+        let fg = if highlighted {
+            color.unwrap_or(Color::WHITE)
+        } else {
+            Color::GRAY
+        };
+
+        if message.is_empty() {
+            lines.push((String::new(), fg));
+            continue;
+        }
+
+        let turn = turn.map_or_else(String::new, |turn| turn.to_string());
+
+        for (i, line) in ruggrogue::word_wrap(message, width).enumerate() {
+            let prefix = if i == 0 {
+                format!("{:>w$}> ", turn, w = turn_width)
+            } else {
+                " ".repeat(turn_width + 2)
+            };
+            lines.push((format!("{}{}", prefix, line), fg));
+        }
+    }
+
+    lines
+}
+
+/// Width available to the text of each message, not counting the '>' prefix.
+fn text_width(grid: &TileGrid<GameSym>) -> usize {
+    grid.width().saturating_sub(6).max(1) as usize
+}
+
+/// Number of log lines that fit in the grid at once.
+fn page_height(grid: &TileGrid<GameSym>) -> usize {
+    grid.height().saturating_sub(4).max(1) as usize
+}
+
+/// Clamp the index of the top line so that the last page of the log is filled instead of showing
+/// lines past the end.
+fn clamp_top(top: usize, num_lines: usize, page: usize) -> usize {
+    top.min(num_lines.saturating_sub(page))
+}
+
+/// Index of the top line after scrolling a log of `num_lines` lines with a game key.
+fn scroll_top(top: usize, num_lines: usize, page: usize, gkey: GameKey) -> usize {
+    let max_top = clamp_top(usize::MAX, num_lines, page);
+    let top = clamp_top(top, num_lines, page);
+
+    match gkey {
+        GameKey::Up => top.saturating_sub(1),
+        GameKey::Down => (top + 1).min(max_top),
+        GameKey::PageUp => top.saturating_sub(page),
+        GameKey::PageDown => (top + page).min(max_top),
+        GameKey::Home => 0,
+        GameKey::End => max_top,
+        _ => top,
+    }
+}
+
+/// A full-screen view of the message history that can be scrolled through.
+impl MessageLogMode {
+    pub fn new() -> Self {
+        Self { top: usize::MAX }
+    }
+
+    pub fn prepare_grids(
+        &self,
+        world: &World,
+        grids: &mut Vec<TileGrid<GameSym>>,
+        tilesets: &[Tileset<GameSym>],
+        window_size: Size,
+    ) {
+        let Options {
+            font, text_zoom, ..
+        } = *world.borrow::<UniqueView<Options>>();
+        let tileset = &tilesets.get(font as usize).unwrap_or(&tilesets[0]);
+        let new_grid_size = Size {
+            w: (window_size.w / (tileset.tile_width() * text_zoom)).max(HINT.len() as u32 + 4),
+            h: (window_size.h / (tileset.tile_height() * text_zoom)).max(8),
+        };
+
+        if !grids.is_empty() {
+            grids[0].resize(new_grid_size);
+        } else {
+            grids.push(TileGrid::new(new_grid_size, tilesets, font as usize));
+            grids[0].view.clear_color = Some(Color::BLACK);
+        }
+
+        grids[0].set_tileset(tilesets, font as usize);
+        grids[0].view_centered(tilesets, text_zoom, (0, 0).into(), window_size);
+        grids[0].view.zoom = text_zoom;
+    }
+
+    pub fn update(
+        &mut self,
+        world: &World,
+        inputs: &mut InputBuffer,
+        grids: &[TileGrid<GameSym>],
+        _pop_result: &Option<ModeResult>,
+    ) -> (ModeControl, ModeUpdate) {
+        inputs.prepare_input();
+
+        if let Some(InputEvent::AppQuit) = inputs.get_input() {
+            return (
+                ModeControl::Pop(MessageLogModeResult::AppQuit.into()),
+                ModeUpdate::Immediate,
+            );
+        } else if let Some(InputEvent::Press(keycode)) = inputs.get_input() {
+            if let Some(grid) = grids.first() {
+                match gamekey::from_keycode(world, keycode, inputs.get_mods(KeyMods::SHIFT)) {
+                    GameKey::Cancel | GameKey::Confirm | GameKey::MessageLog => {
+                        inputs.clear_input();
+                        return (
+                            ModeControl::Pop(MessageLogModeResult::Done.into()),
+                            ModeUpdate::Immediate,
+                        );
+                    }
+                    gkey => {
+                        let num_lines = log_lines(world, text_width(grid)).len();
+
+                        self.top = scroll_top(self.top, num_lines, page_height(grid), gkey);
+                    }
+                }
+            }
+        }
+
+        (ModeControl::Stay, ModeUpdate::WaitForEvent)
+    }
+
+    pub fn draw(&self, world: &World, grids: &mut [TileGrid<GameSym>], active: bool) {
+        let grid = &mut grids[0];
+        let fg = Color::WHITE;
+        let bg = Color::BLACK;
+        let page = page_height(grid);
+        let lines = log_lines(world, text_width(grid));
+        let top = clamp_top(self.top, lines.len(), page);
+
+        grid.view.color_mod = if active { Color::WHITE } else { Color::GRAY };
+
+        grid.clear();
+        grid.draw_box((0, 0), (grid.width(), grid.height()), fg, bg);
+        grid.print_color((2, 0), TITLE, true, Color::YELLOW, bg);
+
+        for (y, (line, line_fg)) in lines.iter().skip(top).take(page).enumerate() {
+            grid.print_color((2, 2 + y as i32), line, true, *line_fg, bg);
+        }
+
+        if lines.len() > page {
+            let position = format!(" {}-{}/{} ", top + 1, top + page, lines.len());
+
+            grid.print_color(
+                (grid.width() as i32 - 2 - position.len() as i32, 0),
+                &position,
+                true,
+                Color::GRAY,
+                bg,
+            );
+        }
+
+        grid.print_color((2, grid.height() as i32 - 1), HINT, true, Color::GRAY, bg);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scrolling_clamps_at_both_ends() {
+        let world = World::new();
+        let mut messages = Messages::new(100, 10);
+
+        for i in 0..50 {
+            messages.add(format!("Message {}.", i));
+        }
+        world.add_unique(messages);
+
+        let num_lines = log_lines(&world, 40).len();
+        let page = 20;
+        let max_top = num_lines - page;
+
+        assert_eq!(num_lines, 50);
+
+        // A new log sticks to the most recent messages.
+        let top = MessageLogMode::new().top;
+        assert_eq!(clamp_top(top, num_lines, page), max_top);
+        assert_eq!(scroll_top(top, num_lines, page, GameKey::Down), max_top);
+        assert_eq!(scroll_top(top, num_lines, page, GameKey::PageDown), max_top);
+        assert_eq!(scroll_top(top, num_lines, page, GameKey::Up), max_top - 1);
+
+        // Scrolling up stops at the oldest message.
+        assert_eq!(scroll_top(max_top, num_lines, page, GameKey::Home), 0);
+        assert_eq!(scroll_top(0, num_lines, page, GameKey::Up), 0);
+        assert_eq!(scroll_top(5, num_lines, page, GameKey::PageUp), 0);
+        assert_eq!(scroll_top(0, num_lines, page, GameKey::End), max_top);
+
+        // A log shorter than a page never scrolls.
+        assert_eq!(scroll_top(0, 5, page, GameKey::Down), 0);
+        assert_eq!(scroll_top(0, 5, page, GameKey::End), 0);
+    }
+
+    #[test]
+    fn log_lines_start_with_their_turn() {
+        let world = World::new();
+        let mut messages = Messages::new(100, 10);
+
+        messages.set_turn(7);
+        messages.add("You hit the goblin.".into());
+        messages.separator();
+        messages.set_turn(12);
+        messages.add("The goblin misses you, and then runs away in a panic.".into());
+        world.add_unique(messages);
+
+        let turns = world
+            .borrow::<UniqueView<Messages>>()
+            .rev_iter()
+            .map(|(_, _, _, turn)| turn)
+            .collect::<Vec<_>>();
+        assert_eq!(turns, [Some(12), None, Some(7)]);
+
+        let lines = log_lines(&world, 30)
+            .into_iter()
+            .map(|(line, _)| line)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            lines,
+            [
+                "  7> You hit the goblin.",
+                "",
+                " 12> The goblin misses you, and",
+                "     then runs away in a panic.",
+            ]
+        );
+    }
+}
@@ -31,9 +31,13 @@ pub mod game_over;
 pub mod inventory;
 pub mod inventory_action;
 pub mod inventory_shortcut;
+pub mod key_bindings;
 pub mod message_box;
+pub mod message_log;
+pub mod note_entry;
 pub mod options_menu;
 pub mod pick_up_menu;
+pub mod seed_entry;
 pub mod target;
 pub mod title;
 pub mod view_map;
@@ -52,9 +56,13 @@ use game_over::{GameOverMode, GameOverModeResult};
 use inventory::{InventoryMode, InventoryModeResult};
 use inventory_action::{InventoryActionMode, InventoryActionModeResult};
 use inventory_shortcut::{InventoryShortcutMode, InventoryShortcutModeResult};
+use key_bindings::{KeyBindingsMode, KeyBindingsModeResult};
 use message_box::{MessageBoxMode, MessageBoxModeResult};
+use message_log::{MessageLogMode, MessageLogModeResult};
+use note_entry::{NoteEntryMode, NoteEntryModeResult};
 use options_menu::{OptionsMenuMode, OptionsMenuModeResult};
 use pick_up_menu::{PickUpMenuMode, PickUpMenuModeResult};
+use seed_entry::{SeedEntryMode, SeedEntryModeResult};
 use target::{TargetMode, TargetModeResult};
 use title::{TitleMode, TitleModeResult};
 use view_map::{ViewMapMode, ViewMapModeResult};
@@ -84,9 +92,13 @@ pub enum Mode {
     InventoryMode(InventoryMode),
     InventoryActionMode(InventoryActionMode),
     InventoryShortcutMode(InventoryShortcutMode),
+    KeyBindingsMode(KeyBindingsMode),
     MessageBoxMode(MessageBoxMode),
+    MessageLogMode(MessageLogMode),
+    NoteEntryMode(NoteEntryMode),
     OptionsMenuMode(OptionsMenuMode),
     PickUpMenuMode(PickUpMenuMode),
+    SeedEntryMode(SeedEntryMode),
     TargetMode(TargetMode),
     TitleMode(TitleMode),
     ViewMapMode(ViewMapMode),
@@ -101,9 +113,13 @@ impl_from!(Mode, GameOverMode);
 impl_from!(Mode, InventoryMode);
 impl_from!(Mode, InventoryActionMode);
 impl_from!(Mode, InventoryShortcutMode);
+impl_from!(Mode, KeyBindingsMode);
 impl_from!(Mode, MessageBoxMode);
+impl_from!(Mode, MessageLogMode);
+impl_from!(Mode, NoteEntryMode);
 impl_from!(Mode, OptionsMenuMode);
 impl_from!(Mode, PickUpMenuMode);
+impl_from!(Mode, SeedEntryMode);
 impl_from!(Mode, TargetMode);
 impl_from!(Mode, TitleMode);
 impl_from!(Mode, ViewMapMode);
@@ -123,9 +139,13 @@ pub enum ModeResult {
     InventoryModeResult(InventoryModeResult),
     InventoryActionModeResult(InventoryActionModeResult),
     InventoryShortcutModeResult(InventoryShortcutModeResult),
+    KeyBindingsModeResult(KeyBindingsModeResult),
     MessageBoxModeResult(MessageBoxModeResult),
+    MessageLogModeResult(MessageLogModeResult),
+    NoteEntryModeResult(NoteEntryModeResult),
     OptionsMenuModeResult(OptionsMenuModeResult),
     PickUpMenuModeResult(PickUpMenuModeResult),
+    SeedEntryModeResult(SeedEntryModeResult),
     TargetModeResult(TargetModeResult),
     TitleModeResult(TitleModeResult),
     ViewMapModeResult(ViewMapModeResult),
@@ -140,9 +160,13 @@ impl_from!(ModeResult, GameOverModeResult);
 impl_from!(ModeResult, InventoryModeResult);
 impl_from!(ModeResult, InventoryActionModeResult);
 impl_from!(ModeResult, InventoryShortcutModeResult);
+impl_from!(ModeResult, KeyBindingsModeResult);
 impl_from!(ModeResult, MessageBoxModeResult);
+impl_from!(ModeResult, MessageLogModeResult);
+impl_from!(ModeResult, NoteEntryModeResult);
 impl_from!(ModeResult, OptionsMenuModeResult);
 impl_from!(ModeResult, PickUpMenuModeResult);
+impl_from!(ModeResult, SeedEntryModeResult);
 impl_from!(ModeResult, TargetModeResult);
 impl_from!(ModeResult, TitleModeResult);
 impl_from!(ModeResult, ViewMapModeResult);
@@ -195,9 +219,13 @@ impl Mode {
             Mode::InventoryMode(x) => x.prepare_grids(world, grids, tilesets, window_size),
             Mode::InventoryActionMode(x) => x.prepare_grids(world, grids, tilesets, window_size),
             Mode::InventoryShortcutMode(x) => x.prepare_grids(world, grids, tilesets, window_size),
+            Mode::KeyBindingsMode(x) => x.prepare_grids(world, grids, tilesets, window_size),
             Mode::MessageBoxMode(x) => x.prepare_grids(world, grids, tilesets, window_size),
+            Mode::MessageLogMode(x) => x.prepare_grids(world, grids, tilesets, window_size),
+            Mode::NoteEntryMode(x) => x.prepare_grids(world, grids, tilesets, window_size),
             Mode::OptionsMenuMode(x) => x.prepare_grids(world, grids, tilesets, window_size),
             Mode::PickUpMenuMode(x) => x.prepare_grids(world, grids, tilesets, window_size),
+            Mode::SeedEntryMode(x) => x.prepare_grids(world, grids, tilesets, window_size),
             Mode::TargetMode(x) => x.prepare_grids(world, grids, tilesets, window_size),
             Mode::TitleMode(x) => x.prepare_grids(world, grids, tilesets, window_size),
             Mode::ViewMapMode(x) => x.prepare_grids(world, grids, tilesets, window_size),
@@ -221,9 +249,13 @@ impl Mode {
             Mode::InventoryMode(x) => x.update(world, inputs, grids, pop_result),
             Mode::InventoryActionMode(x) => x.update(world, inputs, grids, pop_result),
             Mode::InventoryShortcutMode(x) => x.update(world, inputs, grids, pop_result),
+            Mode::KeyBindingsMode(x) => x.update(world, inputs, grids, pop_result),
             Mode::MessageBoxMode(x) => x.update(world, inputs, grids, pop_result),
+            Mode::MessageLogMode(x) => x.update(world, inputs, grids, pop_result),
+            Mode::NoteEntryMode(x) => x.update(world, inputs, grids, pop_result),
             Mode::OptionsMenuMode(x) => x.update(world, inputs, grids, pop_result),
             Mode::PickUpMenuMode(x) => x.update(world, inputs, grids, pop_result),
+            Mode::SeedEntryMode(x) => x.update(world, inputs, grids, pop_result),
             Mode::TargetMode(x) => x.update(world, inputs, grids, pop_result),
             Mode::TitleMode(x) => x.update(world, inputs, grids, pop_result),
             Mode::ViewMapMode(x) => x.update(world, inputs, grids, pop_result),
@@ -241,9 +273,13 @@ impl Mode {
             Mode::InventoryMode(x) => x.draw(world, grids, active),
             Mode::InventoryActionMode(x) => x.draw(world, grids, active),
             Mode::InventoryShortcutMode(x) => x.draw(world, grids, active),
+            Mode::KeyBindingsMode(x) => x.draw(world, grids, active),
             Mode::MessageBoxMode(x) => x.draw(world, grids, active),
+            Mode::MessageLogMode(x) => x.draw(world, grids, active),
+            Mode::NoteEntryMode(x) => x.draw(world, grids, active),
             Mode::OptionsMenuMode(x) => x.draw(world, grids, active),
             Mode::PickUpMenuMode(x) => x.draw(world, grids, active),
+            Mode::SeedEntryMode(x) => x.draw(world, grids, active),
             Mode::TargetMode(x) => x.draw(world, grids, active),
             Mode::TitleMode(x) => x.draw(world, grids, active),
             Mode::ViewMapMode(x) => x.draw(world, grids, active),
@@ -262,9 +298,13 @@ impl Mode {
             Mode::InventoryMode(_) => true,
             Mode::InventoryActionMode(_) => true,
             Mode::InventoryShortcutMode(_) => true,
+            Mode::KeyBindingsMode(_) => true,
             Mode::MessageBoxMode(_) => true,
+            Mode::MessageLogMode(_) => false,
+            Mode::NoteEntryMode(_) => true,
             Mode::OptionsMenuMode(_) => true,
             Mode::PickUpMenuMode(_) => true,
+            Mode::SeedEntryMode(_) => true,
             Mode::TargetMode(_) => false,
             Mode::TitleMode(_) => false,
             Mode::ViewMapMode(_) => false,
@@ -1,9 +1,14 @@
 use shipyard::{UniqueView, UniqueViewMut, World};
 
 use crate::{
+    config,
     gamekey::{self, GameKey},
     gamesym::GameSym,
-    ui::{self, Options},
+    ui::{
+        self, MapGenerator, Options, RevealStyle, VisionShape, WallStyle, MAX_FOV_RANGE,
+        MAX_MONSTERS_LIMIT, MAX_MONSTERS_STEP, MAX_MONSTER_ACTION_DELAY, MIN_FOV_RANGE,
+        REST_UNTIL_STEP,
+    },
 };
 use ruggrogue::{
     util::{Color, Size},
@@ -11,21 +16,73 @@ use ruggrogue::{
 };
 
 use super::{
+    key_bindings::{KeyBindingsMode, KeyBindingsModeResult},
     yes_no_dialog::{YesNoDialogMode, YesNoDialogModeResult},
     ModeControl, ModeResult, ModeUpdate,
 };
 
 const TILESET_LABEL: &str = "  Tileset:";
 const FONT_LABEL: &str = "     Font:";
-const NUM_FONTS: u32 = 2;
-const TILESET_NAMES: [&str; 3] = ["GohuFont", "Terminal", "Urizen"];
+const TILESET_NAMES: [&str; ui::NUM_TILESETS as usize] = ["GohuFont", "Terminal", "Urizen"];
 const UNKNOWN_TILESET_NAME: &str = "???";
 const MAP_ZOOM_LABEL: &str = " Map zoom:";
 const TEXT_ZOOM_LABEL: &str = "Text zoom:";
-const ZOOM_1X_ON: &str = "[1x]";
-const ZOOM_1X_OFF: &str = " 1x ";
-const ZOOM_2X_ON: &str = "[2x]";
-const ZOOM_2X_OFF: &str = " 2x ";
+const ZOOM_CHOICE_WIDTH: usize = 4; // e.g. "[1x]" or " 1x "
+const PLAYER_COLOR_LABEL: &str = "   Player:";
+const PLAYER_COLORS: [(&str, Color); 6] = [
+    ("Yellow", Color::YELLOW),
+    ("White", Color::WHITE),
+    ("Cyan", Color::CYAN),
+    ("Green", Color::GREEN),
+    ("Pink", Color::PINK),
+    ("Orange", Color::ORANGE),
+];
+const WANDERING_MONSTERS_LABEL: &str = "Wanderers:";
+const SMOOTH_SCROLL_LABEL: &str = "   Smooth:";
+const LUNGE_ANIMATIONS_LABEL: &str = "   Lunges:";
+const SCREEN_EFFECTS_LABEL: &str = "  Effects:";
+const ALLOW_UNDO_LABEL: &str = "     Undo:";
+const REST_UNTIL_LABEL: &str = "  Rest to:";
+const REST_UNTIL_CHOICE_WIDTH: usize = 4; // e.g. "100%"
+const MONSTER_ACTION_DELAY_LABEL: &str = "    Delay:";
+const SHOW_EXACT_HP_LABEL: &str = " Exact HP:";
+const ASSIST_RETREAT_LABEL: &str = "  Retreat:";
+const DAMAGE_PREVIEW_LABEL: &str = "  Preview:";
+const WALL_STYLE_LABEL: &str = "    Walls:";
+const WALL_STYLES: [(&str, WallStyle); 3] = [
+    ("Lines", WallStyle::Lines),
+    ("Hash", WallStyle::Hash),
+    ("Block", WallStyle::Block),
+];
+const VISION_SHAPE_LABEL: &str = "    Sight:";
+const VISION_SHAPES: [(&str, VisionShape); 3] = [
+    ("Circle", VisionShape::Circle),
+    ("Square", VisionShape::Square),
+    ("Diamond", VisionShape::Diamond),
+];
+const QUICKSAVE_LABEL: &str = "Quicksave:";
+const MAX_MONSTERS_LABEL: &str = " Monsters:";
+const MAX_MONSTERS_CHOICE_WIDTH: usize = 3; // e.g. "100"
+const STRICT_DIAGONALS_LABEL: &str = "Diagonals:";
+const MAP_GENERATOR_LABEL: &str = "Generator:";
+const MAP_GENERATORS: [(&str, MapGenerator); 3] = [
+    ("Rooms", MapGenerator::Rooms),
+    ("BSP", MapGenerator::Bsp),
+    ("Caves", MapGenerator::Caves),
+];
+const FOV_RANGE_LABEL: &str = "    Range:";
+const FOV_RANGE_CHOICE_WIDTH: usize = 2; // e.g. "12"
+const REVEAL_STYLE_LABEL: &str = "   Memory:";
+const REVEAL_STYLES: [(&str, RevealStyle); 3] = [
+    ("Dim", RevealStyle::Dim),
+    ("Monochrome", RevealStyle::Monochrome),
+    ("Hidden", RevealStyle::Hidden),
+];
+const OFF_ON: &str = "[Off]";
+const OFF_OFF: &str = " Off ";
+const ON_ON: &str = "[On]";
+const ON_OFF: &str = " On ";
+const KEY_BINDINGS: &str = "[ Key bindings ]";
 const QUIT: &str = "[ Save and exit ]";
 const BACK: &str = "[ Back ]";
 
@@ -40,9 +97,90 @@ enum Selection {
     Font,
     MapZoom,
     TextZoom,
+    PlayerColor,
+    WanderingMonsters,
+    SmoothScroll,
+    LungeAnimations,
+    ScreenEffects,
+    AllowUndo,
+    RestUntil,
+    MonsterActionDelay,
+    ShowExactHp,
+    AssistRetreat,
+    DamagePreview,
+    WallStyle,
+    VisionShape,
+    Quicksave,
+    MaxMonsters,
+    StrictDiagonals,
+    MapGenerator,
+    FovRange,
+    RevealStyle,
+    KeyBindings,
     Quit,
 }
 
+/// Step an index forwards or backwards through `count` choices, wrapping around at either end.
+fn cycle_index(index: u32, count: u32, forward: bool) -> u32 {
+    if forward {
+        (index + 1) % count
+    } else {
+        (index + count - 1) % count
+    }
+}
+
+/// Index into [PLAYER_COLORS] of the color the player is currently drawn with.
+fn player_color_index(options: &Options) -> usize {
+    options
+        .color_overrides
+        .get(&GameSym::Player)
+        .and_then(|color| PLAYER_COLORS.iter().position(|(_, c)| c == color))
+        .unwrap_or(0)
+}
+
+/// Draw the player with the color at the given index of [PLAYER_COLORS].  The first color is the
+/// default, so it's drawn without an override.
+fn set_player_color(options: &mut Options, index: usize) {
+    if index == 0 {
+        options.color_overrides.remove(&GameSym::Player);
+    } else {
+        options
+            .color_overrides
+            .insert(GameSym::Player, PLAYER_COLORS[index].1);
+    }
+}
+
+/// Index into [WALL_STYLES] of the current wall style.
+fn wall_style_index(options: &Options) -> usize {
+    WALL_STYLES
+        .iter()
+        .position(|&(_, style)| style == options.wall_style)
+        .unwrap_or(0)
+}
+
+/// Index into [VISION_SHAPES] of the current shape of the player's field of view.
+fn map_generator_index(options: &Options) -> usize {
+    MAP_GENERATORS
+        .iter()
+        .position(|&(_, generator)| generator == options.map_generator)
+        .unwrap_or(0)
+}
+
+/// Index into [REVEAL_STYLES] of how remembered tiles are currently drawn.
+fn reveal_style_index(options: &Options) -> usize {
+    REVEAL_STYLES
+        .iter()
+        .position(|&(_, style)| style == options.reveal_style)
+        .unwrap_or(0)
+}
+
+fn vision_shape_index(options: &Options) -> usize {
+    VISION_SHAPES
+        .iter()
+        .position(|&(_, shape)| shape == options.vision_shape)
+        .unwrap_or(0)
+}
+
 pub struct OptionsMenuMode {
     prompt_to_save: bool,
     selection: Selection,
@@ -75,19 +213,85 @@ impl OptionsMenuMode {
             + FONT_LABEL.len()
             + TILESET_NAMES
                 .iter()
-                .take(NUM_FONTS as usize)
+                .take(ui::NUM_FONTS as usize)
                 .map(|n| n.len())
                 .max()
                 .unwrap_or_else(|| UNKNOWN_TILESET_NAME.len());
-        let map_zoom_width = 2 + MAP_ZOOM_LABEL.len() + ZOOM_1X_ON.len() + ZOOM_2X_ON.len();
-        let text_zoom_width = 2 + TEXT_ZOOM_LABEL.len() + ZOOM_1X_ON.len() + ZOOM_2X_ON.len();
+        let map_zoom_width = 2 + MAP_ZOOM_LABEL.len() + ZOOM_CHOICE_WIDTH * ui::MAX_ZOOM as usize;
+        let text_zoom_width = 2 + TEXT_ZOOM_LABEL.len() + ZOOM_CHOICE_WIDTH * ui::MAX_ZOOM as usize;
+        let player_color_width = 7
+            + PLAYER_COLOR_LABEL.len()
+            + PLAYER_COLORS
+                .iter()
+                .map(|(n, _)| n.len())
+                .max()
+                .unwrap_or(0);
+        let wandering_monsters_width =
+            2 + WANDERING_MONSTERS_LABEL.len() + OFF_ON.len() + ON_ON.len();
+        let smooth_scroll_width = 2 + SMOOTH_SCROLL_LABEL.len() + OFF_ON.len() + ON_ON.len();
+        let lunge_animations_width = 2 + LUNGE_ANIMATIONS_LABEL.len() + OFF_ON.len() + ON_ON.len();
+        let screen_effects_width = 2 + SCREEN_EFFECTS_LABEL.len() + OFF_ON.len() + ON_ON.len();
+        let allow_undo_width = 2 + ALLOW_UNDO_LABEL.len() + OFF_ON.len() + ON_ON.len();
+        let rest_until_width = 7 + REST_UNTIL_LABEL.len() + REST_UNTIL_CHOICE_WIDTH;
+        let monster_action_delay_width = 8 + MONSTER_ACTION_DELAY_LABEL.len();
+        let show_exact_hp_width = 2 + SHOW_EXACT_HP_LABEL.len() + OFF_ON.len() + ON_ON.len();
+        let assist_retreat_width = 2 + ASSIST_RETREAT_LABEL.len() + OFF_ON.len() + ON_ON.len();
+        let damage_preview_width = 2 + DAMAGE_PREVIEW_LABEL.len() + OFF_ON.len() + ON_ON.len();
+        let wall_style_width = 7
+            + WALL_STYLE_LABEL.len()
+            + WALL_STYLES.iter().map(|(n, _)| n.len()).max().unwrap_or(0);
+        let vision_shape_width = 7
+            + VISION_SHAPE_LABEL.len()
+            + VISION_SHAPES
+                .iter()
+                .map(|(n, _)| n.len())
+                .max()
+                .unwrap_or(0);
+        let quicksave_width = 2 + QUICKSAVE_LABEL.len() + OFF_ON.len() + ON_ON.len();
+        let max_monsters_width = 7 + MAX_MONSTERS_LABEL.len() + MAX_MONSTERS_CHOICE_WIDTH;
+        let strict_diagonals_width = 2 + STRICT_DIAGONALS_LABEL.len() + OFF_ON.len() + ON_ON.len();
+        let map_generator_width = 7
+            + MAP_GENERATOR_LABEL.len()
+            + MAP_GENERATORS
+                .iter()
+                .map(|(n, _)| n.len())
+                .max()
+                .unwrap_or(0);
+        let fov_range_width = 7 + FOV_RANGE_LABEL.len() + FOV_RANGE_CHOICE_WIDTH;
+        let reveal_style_width = 7
+            + REVEAL_STYLE_LABEL.len()
+            + REVEAL_STYLES
+                .iter()
+                .map(|(n, _)| n.len())
+                .max()
+                .unwrap_or(0);
         let new_grid_size = Size {
             w: 4 + tileset_width
                 .max(font_width)
                 .max(map_zoom_width)
                 .max(text_zoom_width)
+                .max(player_color_width)
+                .max(wandering_monsters_width)
+                .max(smooth_scroll_width)
+                .max(lunge_animations_width)
+                .max(screen_effects_width)
+                .max(allow_undo_width)
+                .max(rest_until_width)
+                .max(monster_action_delay_width)
+                .max(show_exact_hp_width)
+                .max(assist_retreat_width)
+                .max(damage_preview_width)
+                .max(wall_style_width)
+                .max(vision_shape_width)
+                .max(quicksave_width)
+                .max(max_monsters_width)
+                .max(strict_diagonals_width)
+                .max(map_generator_width)
+                .max(fov_range_width)
+                .max(reveal_style_width)
+                .max(KEY_BINDINGS.len())
                 .max(QUIT.len()) as u32,
-            h: 10,
+            h: 30,
         };
         let Options {
             font, text_zoom, ..
@@ -106,6 +310,23 @@ impl OptionsMenuMode {
     }
 
     pub fn update(
+        &mut self,
+        world: &World,
+        inputs: &mut InputBuffer,
+        grids: &[TileGrid<GameSym>],
+        pop_result: &Option<ModeResult>,
+    ) -> (ModeControl, ModeUpdate) {
+        let (control, update) = self.update_options(world, inputs, grids, pop_result);
+
+        // Remember the options for next time once the menu is closed.
+        if let ModeControl::Pop(_) = control {
+            config::save_options(&world.borrow::<UniqueView<Options>>());
+        }
+
+        (control, update)
+    }
+
+    fn update_options(
         &mut self,
         world: &World,
         inputs: &mut InputBuffer,
@@ -125,6 +346,13 @@ impl OptionsMenuMode {
                     ),
                     YesNoDialogModeResult::No => (ModeControl::Stay, ModeUpdate::WaitForEvent),
                 },
+                ModeResult::KeyBindingsModeResult(result) => match result {
+                    KeyBindingsModeResult::AppQuit => (
+                        ModeControl::Pop(OptionsMenuModeResult::AppQuit.into()),
+                        ModeUpdate::Immediate,
+                    ),
+                    KeyBindingsModeResult::Done => (ModeControl::Stay, ModeUpdate::WaitForEvent),
+                },
                 _ => unreachable!(),
             };
         }
@@ -138,70 +366,411 @@ impl OptionsMenuMode {
             );
         } else if let Some(InputEvent::Press(keycode)) = inputs.get_input() {
             let mut options = world.borrow::<UniqueViewMut<Options>>();
-            let gkey = gamekey::from_keycode(keycode, inputs.get_mods(KeyMods::SHIFT));
+            let gkey = gamekey::from_keycode(world, keycode, inputs.get_mods(KeyMods::SHIFT));
 
             match (&self.selection, gkey) {
                 (Selection::Tileset, GameKey::Up) => self.selection = Selection::Quit,
                 (Selection::Tileset, GameKey::Down) => self.selection = Selection::Font,
                 (Selection::Tileset, GameKey::Left) => {
-                    if options.tileset > 0 {
-                        options.tileset -= 1;
+                    options.tileset = cycle_index(options.tileset, ui::NUM_TILESETS, false);
+                    inputs.clear_input();
+                    return (ModeControl::Stay, ModeUpdate::Immediate);
+                }
+                (Selection::Tileset, GameKey::Right) => {
+                    options.tileset = cycle_index(options.tileset, ui::NUM_TILESETS, true);
+                    inputs.clear_input();
+                    return (ModeControl::Stay, ModeUpdate::Immediate);
+                }
+
+                (Selection::Font, GameKey::Up) => self.selection = Selection::Tileset,
+                (Selection::Font, GameKey::Down) => self.selection = Selection::MapZoom,
+                (Selection::Font, GameKey::Left) => {
+                    options.font = cycle_index(options.font, ui::NUM_FONTS, false);
+                    inputs.clear_input();
+                    return (ModeControl::Stay, ModeUpdate::Immediate);
+                }
+                (Selection::Font, GameKey::Right) => {
+                    options.font = cycle_index(options.font, ui::NUM_FONTS, true);
+                    inputs.clear_input();
+                    return (ModeControl::Stay, ModeUpdate::Immediate);
+                }
+
+                (Selection::MapZoom, GameKey::Up) => self.selection = Selection::Font,
+                (Selection::MapZoom, GameKey::Down) => self.selection = Selection::TextZoom,
+                (Selection::MapZoom, GameKey::Left) => {
+                    if options.map_zoom > 1 {
+                        options.map_zoom -= 1;
                         inputs.clear_input();
                         return (ModeControl::Stay, ModeUpdate::Immediate);
                     }
                 }
-                (Selection::Tileset, GameKey::Right) => {
-                    if options.tileset as usize + 1 < TILESET_NAMES.len() {
-                        options.tileset += 1;
+                (Selection::MapZoom, GameKey::Right) => {
+                    if options.map_zoom < ui::MAX_ZOOM {
+                        options.map_zoom += 1;
                         inputs.clear_input();
                         return (ModeControl::Stay, ModeUpdate::Immediate);
                     }
                 }
 
-                (Selection::Font, GameKey::Up) => self.selection = Selection::Tileset,
-                (Selection::Font, GameKey::Down) => self.selection = Selection::MapZoom,
-                (Selection::Font, GameKey::Left) => {
-                    if options.font > 0 {
-                        options.font -= 1;
+                (Selection::TextZoom, GameKey::Up) => self.selection = Selection::MapZoom,
+                (Selection::TextZoom, GameKey::Down) => self.selection = Selection::PlayerColor,
+                (Selection::TextZoom, GameKey::Left) => {
+                    if options.text_zoom > 1 {
+                        options.text_zoom -= 1;
                         inputs.clear_input();
                         return (ModeControl::Stay, ModeUpdate::Immediate);
                     }
                 }
-                (Selection::Font, GameKey::Right) => {
-                    if options.font + 1 < NUM_FONTS {
-                        options.font += 1;
+                (Selection::TextZoom, GameKey::Right) => {
+                    if options.text_zoom < ui::MAX_ZOOM {
+                        options.text_zoom += 1;
                         inputs.clear_input();
                         return (ModeControl::Stay, ModeUpdate::Immediate);
                     }
                 }
 
-                (Selection::MapZoom, GameKey::Up) => self.selection = Selection::Font,
-                (Selection::MapZoom, GameKey::Down) => self.selection = Selection::TextZoom,
-                (Selection::MapZoom, GameKey::Left) => {
-                    options.map_zoom = 1;
+                (Selection::PlayerColor, GameKey::Up) => self.selection = Selection::TextZoom,
+                (Selection::PlayerColor, GameKey::Down) => {
+                    self.selection = Selection::WanderingMonsters
+                }
+                (Selection::PlayerColor, GameKey::Left) => {
+                    let index = player_color_index(&options);
+                    if index > 0 {
+                        set_player_color(&mut options, index - 1);
+                        inputs.clear_input();
+                        return (ModeControl::Stay, ModeUpdate::Immediate);
+                    }
+                }
+                (Selection::PlayerColor, GameKey::Right) => {
+                    let index = player_color_index(&options);
+                    if index + 1 < PLAYER_COLORS.len() {
+                        set_player_color(&mut options, index + 1);
+                        inputs.clear_input();
+                        return (ModeControl::Stay, ModeUpdate::Immediate);
+                    }
+                }
+
+                (Selection::WanderingMonsters, GameKey::Up) => {
+                    self.selection = Selection::PlayerColor
+                }
+                (Selection::WanderingMonsters, GameKey::Down) => {
+                    self.selection = Selection::SmoothScroll
+                }
+                (Selection::WanderingMonsters, GameKey::Left) => {
+                    options.wandering_monsters = false;
                     inputs.clear_input();
                     return (ModeControl::Stay, ModeUpdate::Immediate);
                 }
-                (Selection::MapZoom, GameKey::Right) => {
-                    options.map_zoom = 2;
+                (Selection::WanderingMonsters, GameKey::Right) => {
+                    options.wandering_monsters = true;
                     inputs.clear_input();
                     return (ModeControl::Stay, ModeUpdate::Immediate);
                 }
 
-                (Selection::TextZoom, GameKey::Up) => self.selection = Selection::MapZoom,
-                (Selection::TextZoom, GameKey::Down) => self.selection = Selection::Quit,
-                (Selection::TextZoom, GameKey::Left) => {
-                    options.text_zoom = 1;
+                (Selection::SmoothScroll, GameKey::Up) => {
+                    self.selection = Selection::WanderingMonsters
+                }
+                (Selection::SmoothScroll, GameKey::Down) => {
+                    self.selection = Selection::LungeAnimations
+                }
+                (Selection::SmoothScroll, GameKey::Left) => {
+                    options.smooth_scroll = false;
                     inputs.clear_input();
                     return (ModeControl::Stay, ModeUpdate::Immediate);
                 }
-                (Selection::TextZoom, GameKey::Right) => {
-                    options.text_zoom = 2;
+                (Selection::SmoothScroll, GameKey::Right) => {
+                    options.smooth_scroll = true;
+                    inputs.clear_input();
+                    return (ModeControl::Stay, ModeUpdate::Immediate);
+                }
+
+                (Selection::LungeAnimations, GameKey::Up) => {
+                    self.selection = Selection::SmoothScroll
+                }
+                (Selection::LungeAnimations, GameKey::Down) => {
+                    self.selection = Selection::ScreenEffects
+                }
+                (Selection::LungeAnimations, GameKey::Left) => {
+                    options.lunge_animations = false;
+                    inputs.clear_input();
+                    return (ModeControl::Stay, ModeUpdate::Immediate);
+                }
+                (Selection::LungeAnimations, GameKey::Right) => {
+                    options.lunge_animations = true;
+                    inputs.clear_input();
+                    return (ModeControl::Stay, ModeUpdate::Immediate);
+                }
+
+                (Selection::ScreenEffects, GameKey::Up) => {
+                    self.selection = Selection::LungeAnimations
+                }
+                (Selection::ScreenEffects, GameKey::Down) => self.selection = Selection::AllowUndo,
+                (Selection::ScreenEffects, GameKey::Left) => {
+                    options.screen_effects = false;
+                    inputs.clear_input();
+                    return (ModeControl::Stay, ModeUpdate::Immediate);
+                }
+                (Selection::ScreenEffects, GameKey::Right) => {
+                    options.screen_effects = true;
+                    inputs.clear_input();
+                    return (ModeControl::Stay, ModeUpdate::Immediate);
+                }
+
+                (Selection::AllowUndo, GameKey::Up) => self.selection = Selection::ScreenEffects,
+                (Selection::AllowUndo, GameKey::Down) => self.selection = Selection::RestUntil,
+                (Selection::AllowUndo, GameKey::Left) => {
+                    options.allow_undo = false;
+                    inputs.clear_input();
+                    return (ModeControl::Stay, ModeUpdate::Immediate);
+                }
+                (Selection::AllowUndo, GameKey::Right) => {
+                    options.allow_undo = true;
+                    inputs.clear_input();
+                    return (ModeControl::Stay, ModeUpdate::Immediate);
+                }
+
+                (Selection::RestUntil, GameKey::Up) => self.selection = Selection::AllowUndo,
+                (Selection::RestUntil, GameKey::Down) => {
+                    self.selection = Selection::MonsterActionDelay
+                }
+                (Selection::RestUntil, GameKey::Left) => {
+                    if options.rest_until_percent > REST_UNTIL_STEP {
+                        options.rest_until_percent -= REST_UNTIL_STEP;
+                        inputs.clear_input();
+                        return (ModeControl::Stay, ModeUpdate::Immediate);
+                    }
+                }
+                (Selection::RestUntil, GameKey::Right) => {
+                    if options.rest_until_percent < 100 {
+                        options.rest_until_percent += REST_UNTIL_STEP;
+                        inputs.clear_input();
+                        return (ModeControl::Stay, ModeUpdate::Immediate);
+                    }
+                }
+
+                (Selection::MonsterActionDelay, GameKey::Up) => {
+                    self.selection = Selection::RestUntil
+                }
+                (Selection::MonsterActionDelay, GameKey::Down) => {
+                    self.selection = Selection::ShowExactHp
+                }
+                (Selection::MonsterActionDelay, GameKey::Left) => {
+                    if options.monster_action_delay > 0 {
+                        options.monster_action_delay -= 1;
+                        inputs.clear_input();
+                        return (ModeControl::Stay, ModeUpdate::Immediate);
+                    }
+                }
+                (Selection::MonsterActionDelay, GameKey::Right) => {
+                    if options.monster_action_delay < MAX_MONSTER_ACTION_DELAY {
+                        options.monster_action_delay += 1;
+                        inputs.clear_input();
+                        return (ModeControl::Stay, ModeUpdate::Immediate);
+                    }
+                }
+
+                (Selection::ShowExactHp, GameKey::Up) => {
+                    self.selection = Selection::MonsterActionDelay
+                }
+                (Selection::ShowExactHp, GameKey::Down) => {
+                    self.selection = Selection::AssistRetreat
+                }
+                (Selection::ShowExactHp, GameKey::Left) => {
+                    options.show_exact_hp = false;
+                    inputs.clear_input();
+                    return (ModeControl::Stay, ModeUpdate::Immediate);
+                }
+                (Selection::ShowExactHp, GameKey::Right) => {
+                    options.show_exact_hp = true;
+                    inputs.clear_input();
+                    return (ModeControl::Stay, ModeUpdate::Immediate);
+                }
+
+                (Selection::AssistRetreat, GameKey::Up) => self.selection = Selection::ShowExactHp,
+                (Selection::AssistRetreat, GameKey::Down) => {
+                    self.selection = Selection::DamagePreview
+                }
+                (Selection::AssistRetreat, GameKey::Left) => {
+                    options.assist_retreat = false;
+                    inputs.clear_input();
+                    return (ModeControl::Stay, ModeUpdate::Immediate);
+                }
+                (Selection::AssistRetreat, GameKey::Right) => {
+                    options.assist_retreat = true;
+                    inputs.clear_input();
+                    return (ModeControl::Stay, ModeUpdate::Immediate);
+                }
+
+                (Selection::DamagePreview, GameKey::Up) => {
+                    self.selection = Selection::AssistRetreat
+                }
+                (Selection::DamagePreview, GameKey::Down) => self.selection = Selection::WallStyle,
+                (Selection::DamagePreview, GameKey::Left) => {
+                    options.show_damage_preview = false;
+                    inputs.clear_input();
+                    return (ModeControl::Stay, ModeUpdate::Immediate);
+                }
+                (Selection::DamagePreview, GameKey::Right) => {
+                    options.show_damage_preview = true;
+                    inputs.clear_input();
+                    return (ModeControl::Stay, ModeUpdate::Immediate);
+                }
+
+                (Selection::WallStyle, GameKey::Up) => self.selection = Selection::DamagePreview,
+                (Selection::WallStyle, GameKey::Down) => self.selection = Selection::VisionShape,
+                (Selection::WallStyle, GameKey::Left) => {
+                    let index = wall_style_index(&options);
+                    if index > 0 {
+                        options.wall_style = WALL_STYLES[index - 1].1;
+                        inputs.clear_input();
+                        return (ModeControl::Stay, ModeUpdate::Immediate);
+                    }
+                }
+                (Selection::WallStyle, GameKey::Right) => {
+                    let index = wall_style_index(&options);
+                    if index + 1 < WALL_STYLES.len() {
+                        options.wall_style = WALL_STYLES[index + 1].1;
+                        inputs.clear_input();
+                        return (ModeControl::Stay, ModeUpdate::Immediate);
+                    }
+                }
+
+                (Selection::VisionShape, GameKey::Up) => self.selection = Selection::WallStyle,
+                (Selection::VisionShape, GameKey::Down) => self.selection = Selection::Quicksave,
+                (Selection::VisionShape, GameKey::Left) => {
+                    let index = vision_shape_index(&options);
+                    if index > 0 {
+                        options.vision_shape = VISION_SHAPES[index - 1].1;
+                        inputs.clear_input();
+                        return (ModeControl::Stay, ModeUpdate::Immediate);
+                    }
+                }
+                (Selection::VisionShape, GameKey::Right) => {
+                    let index = vision_shape_index(&options);
+                    if index + 1 < VISION_SHAPES.len() {
+                        options.vision_shape = VISION_SHAPES[index + 1].1;
+                        inputs.clear_input();
+                        return (ModeControl::Stay, ModeUpdate::Immediate);
+                    }
+                }
+
+                (Selection::Quicksave, GameKey::Up) => self.selection = Selection::VisionShape,
+                (Selection::Quicksave, GameKey::Down) => self.selection = Selection::MaxMonsters,
+                (Selection::Quicksave, GameKey::Left) => {
+                    options.quicksave_on_exit = false;
+                    inputs.clear_input();
+                    return (ModeControl::Stay, ModeUpdate::Immediate);
+                }
+                (Selection::Quicksave, GameKey::Right) => {
+                    options.quicksave_on_exit = true;
+                    inputs.clear_input();
+                    return (ModeControl::Stay, ModeUpdate::Immediate);
+                }
+
+                (Selection::MaxMonsters, GameKey::Up) => self.selection = Selection::Quicksave,
+                (Selection::MaxMonsters, GameKey::Down) => {
+                    self.selection = Selection::StrictDiagonals
+                }
+                (Selection::MaxMonsters, GameKey::Left) => {
+                    if options.max_monsters_per_level > MAX_MONSTERS_STEP {
+                        options.max_monsters_per_level -= MAX_MONSTERS_STEP;
+                        inputs.clear_input();
+                        return (ModeControl::Stay, ModeUpdate::Immediate);
+                    }
+                }
+                (Selection::MaxMonsters, GameKey::Right) => {
+                    if options.max_monsters_per_level < MAX_MONSTERS_LIMIT {
+                        options.max_monsters_per_level += MAX_MONSTERS_STEP;
+                        inputs.clear_input();
+                        return (ModeControl::Stay, ModeUpdate::Immediate);
+                    }
+                }
+
+                (Selection::StrictDiagonals, GameKey::Up) => {
+                    self.selection = Selection::MaxMonsters
+                }
+                (Selection::StrictDiagonals, GameKey::Down) => {
+                    self.selection = Selection::MapGenerator
+                }
+                (Selection::StrictDiagonals, GameKey::Left) => {
+                    options.strict_diagonals = false;
+                    inputs.clear_input();
+                    return (ModeControl::Stay, ModeUpdate::Immediate);
+                }
+                (Selection::StrictDiagonals, GameKey::Right) => {
+                    options.strict_diagonals = true;
                     inputs.clear_input();
                     return (ModeControl::Stay, ModeUpdate::Immediate);
                 }
 
-                (Selection::Quit, GameKey::Up) => self.selection = Selection::TextZoom,
+                (Selection::MapGenerator, GameKey::Up) => {
+                    self.selection = Selection::StrictDiagonals
+                }
+                (Selection::MapGenerator, GameKey::Down) => self.selection = Selection::FovRange,
+                (Selection::MapGenerator, GameKey::Left) => {
+                    let index = map_generator_index(&options);
+                    if index > 0 {
+                        options.map_generator = MAP_GENERATORS[index - 1].1;
+                        inputs.clear_input();
+                        return (ModeControl::Stay, ModeUpdate::Immediate);
+                    }
+                }
+                (Selection::MapGenerator, GameKey::Right) => {
+                    let index = map_generator_index(&options);
+                    if index + 1 < MAP_GENERATORS.len() {
+                        options.map_generator = MAP_GENERATORS[index + 1].1;
+                        inputs.clear_input();
+                        return (ModeControl::Stay, ModeUpdate::Immediate);
+                    }
+                }
+
+                (Selection::FovRange, GameKey::Up) => self.selection = Selection::MapGenerator,
+                (Selection::FovRange, GameKey::Down) => self.selection = Selection::RevealStyle,
+                (Selection::FovRange, GameKey::Left) => {
+                    if options.fov_range > MIN_FOV_RANGE {
+                        options.fov_range -= 1;
+                        inputs.clear_input();
+                        return (ModeControl::Stay, ModeUpdate::Immediate);
+                    }
+                }
+                (Selection::FovRange, GameKey::Right) => {
+                    if options.fov_range < MAX_FOV_RANGE {
+                        options.fov_range += 1;
+                        inputs.clear_input();
+                        return (ModeControl::Stay, ModeUpdate::Immediate);
+                    }
+                }
+
+                (Selection::RevealStyle, GameKey::Up) => self.selection = Selection::FovRange,
+                (Selection::RevealStyle, GameKey::Down) => self.selection = Selection::KeyBindings,
+                (Selection::RevealStyle, GameKey::Left) => {
+                    let index = reveal_style_index(&options);
+                    if index > 0 {
+                        options.reveal_style = REVEAL_STYLES[index - 1].1;
+                        inputs.clear_input();
+                        return (ModeControl::Stay, ModeUpdate::Immediate);
+                    }
+                }
+                (Selection::RevealStyle, GameKey::Right) => {
+                    let index = reveal_style_index(&options);
+                    if index + 1 < REVEAL_STYLES.len() {
+                        options.reveal_style = REVEAL_STYLES[index + 1].1;
+                        inputs.clear_input();
+                        return (ModeControl::Stay, ModeUpdate::Immediate);
+                    }
+                }
+
+                (Selection::KeyBindings, GameKey::Up) => self.selection = Selection::RevealStyle,
+                (Selection::KeyBindings, GameKey::Down) => self.selection = Selection::Quit,
+                (Selection::KeyBindings, GameKey::Confirm) => {
+                    inputs.clear_input();
+                    return (
+                        ModeControl::Push(KeyBindingsMode::new().into()),
+                        ModeUpdate::Immediate,
+                    );
+                }
+
+                (Selection::Quit, GameKey::Up) => self.selection = Selection::KeyBindings,
                 (Selection::Quit, GameKey::Down) => self.selection = Selection::Tileset,
                 (Selection::Quit, GameKey::Confirm) => {
                     inputs.clear_input();
@@ -255,9 +824,8 @@ impl OptionsMenuMode {
         let tileset = world.borrow::<UniqueView<Options>>().tileset;
 
         grid.print((2, tileset_y), TILESET_LABEL);
-        if tileset > 0 {
-            grid.print_color((tileset_left_x, tileset_y), "<<", true, fg, bg);
-        }
+        // Tilesets wrap around, so there's always another one in either direction.
+        grid.print_color((tileset_left_x, tileset_y), "<<", true, fg, bg);
         grid.print_color(
             (tileset_name_x, tileset_y),
             TILESET_NAMES
@@ -271,9 +839,7 @@ impl OptionsMenuMode {
                 bg
             },
         );
-        if tileset as usize + 1 < TILESET_NAMES.len() {
-            grid.print_color((tileset_right_x, tileset_y), ">>", true, fg, bg);
-        }
+        grid.print_color((tileset_right_x, tileset_y), ">>", true, fg, bg);
     }
 
     fn draw_font(
@@ -297,9 +863,7 @@ impl OptionsMenuMode {
         let font = world.borrow::<UniqueView<Options>>().font;
 
         grid.print((2, font_y), FONT_LABEL);
-        if font > 0 {
-            grid.print_color((font_left_x, font_y), "<<", true, fg, bg);
-        }
+        grid.print_color((font_left_x, font_y), "<<", true, fg, bg);
         grid.print_color(
             (font_name_x, font_y),
             TILESET_NAMES
@@ -313,8 +877,41 @@ impl OptionsMenuMode {
                 bg
             },
         );
-        if font + 1 < NUM_FONTS {
-            grid.print_color((font_right_x, font_y), ">>", true, fg, bg);
+        grid.print_color((font_right_x, font_y), ">>", true, fg, bg);
+    }
+
+    fn draw_zoom(
+        grid: &mut TileGrid<GameSym>,
+        y: i32,
+        label: &str,
+        zoom: u32,
+        selected: bool,
+        fg: Color,
+        bg: Color,
+        selected_bg: Color,
+    ) {
+        grid.print((2, y), label);
+
+        for choice in 1..=ui::MAX_ZOOM {
+            let choice_x =
+                (3 + label.len() + (choice as usize - 1) * (ZOOM_CHOICE_WIDTH + 1)) as i32;
+            let choice_text = if zoom == choice {
+                format!("[{}x]", choice)
+            } else {
+                format!(" {}x ", choice)
+            };
+
+            grid.print_color(
+                (choice_x, y),
+                &choice_text,
+                true,
+                fg,
+                if zoom == choice && selected {
+                    selected_bg
+                } else {
+                    bg
+                },
+            );
         }
     }
 
@@ -326,45 +923,414 @@ impl OptionsMenuMode {
         bg: Color,
         selected_bg: Color,
     ) {
-        let map_zoom_1x_x = 3 + MAP_ZOOM_LABEL.len() as i32;
-        let map_zoom_2x_x = 4 + (MAP_ZOOM_LABEL.len() + ZOOM_1X_OFF.len()) as i32;
-        let map_zoom_y = 4;
-        let map_zoom = world.borrow::<UniqueView<Options>>().map_zoom;
-
-        grid.print((2, map_zoom_y), MAP_ZOOM_LABEL);
-        grid.print_color(
-            (map_zoom_1x_x, map_zoom_y),
-            if map_zoom == 1 {
-                ZOOM_1X_ON
+        Self::draw_zoom(
+            grid,
+            4,
+            MAP_ZOOM_LABEL,
+            world.borrow::<UniqueView<Options>>().map_zoom,
+            matches!(self.selection, Selection::MapZoom),
+            fg,
+            bg,
+            selected_bg,
+        );
+    }
+
+    fn draw_text_zoom(
+        &self,
+        world: &World,
+        grid: &mut TileGrid<GameSym>,
+        fg: Color,
+        bg: Color,
+        selected_bg: Color,
+    ) {
+        Self::draw_zoom(
+            grid,
+            5,
+            TEXT_ZOOM_LABEL,
+            world.borrow::<UniqueView<Options>>().text_zoom,
+            matches!(self.selection, Selection::TextZoom),
+            fg,
+            bg,
+            selected_bg,
+        );
+    }
+
+    fn draw_player_color(
+        &self,
+        world: &World,
+        grid: &mut TileGrid<GameSym>,
+        fg: Color,
+        bg: Color,
+        selected_bg: Color,
+    ) {
+        let player_color_left_x = 3 + PLAYER_COLOR_LABEL.len() as i32;
+        let player_color_name_x = 3 + player_color_left_x;
+        let player_color_right_x = 1
+            + player_color_name_x
+            + PLAYER_COLORS
+                .iter()
+                .map(|(n, _)| n.len())
+                .max()
+                .unwrap_or(0) as i32;
+        let player_color_y = 6;
+        let index = player_color_index(&world.borrow::<UniqueView<Options>>());
+        let (color_name, color) = PLAYER_COLORS[index];
+
+        grid.print((2, player_color_y), PLAYER_COLOR_LABEL);
+        if index > 0 {
+            grid.print_color((player_color_left_x, player_color_y), "<<", true, fg, bg);
+        }
+        grid.print_color(
+            (player_color_name_x, player_color_y),
+            color_name,
+            true,
+            color,
+            if matches!(self.selection, Selection::PlayerColor) {
+                selected_bg
             } else {
-                ZOOM_1X_OFF
+                bg
             },
+        );
+        if index + 1 < PLAYER_COLORS.len() {
+            grid.print_color((player_color_right_x, player_color_y), ">>", true, fg, bg);
+        }
+    }
+
+    fn draw_toggle(
+        grid: &mut TileGrid<GameSym>,
+        y: i32,
+        label: &str,
+        value: bool,
+        selected: bool,
+        fg: Color,
+        bg: Color,
+        selected_bg: Color,
+    ) {
+        let off_x = 3 + label.len() as i32;
+        let on_x = 4 + (label.len() + OFF_OFF.len()) as i32;
+
+        grid.print((2, y), label);
+        grid.print_color(
+            (off_x, y),
+            if !value { OFF_ON } else { OFF_OFF },
             true,
             fg,
-            if map_zoom == 1 && matches!(self.selection, Selection::MapZoom) {
+            if !value && selected { selected_bg } else { bg },
+        );
+        grid.print_color(
+            (on_x, y),
+            if value { ON_ON } else { ON_OFF },
+            true,
+            fg,
+            if value && selected { selected_bg } else { bg },
+        );
+    }
+
+    fn draw_wandering_monsters(
+        &self,
+        world: &World,
+        grid: &mut TileGrid<GameSym>,
+        fg: Color,
+        bg: Color,
+        selected_bg: Color,
+    ) {
+        Self::draw_toggle(
+            grid,
+            7,
+            WANDERING_MONSTERS_LABEL,
+            world.borrow::<UniqueView<Options>>().wandering_monsters,
+            matches!(self.selection, Selection::WanderingMonsters),
+            fg,
+            bg,
+            selected_bg,
+        );
+    }
+
+    fn draw_smooth_scroll(
+        &self,
+        world: &World,
+        grid: &mut TileGrid<GameSym>,
+        fg: Color,
+        bg: Color,
+        selected_bg: Color,
+    ) {
+        Self::draw_toggle(
+            grid,
+            8,
+            SMOOTH_SCROLL_LABEL,
+            world.borrow::<UniqueView<Options>>().smooth_scroll,
+            matches!(self.selection, Selection::SmoothScroll),
+            fg,
+            bg,
+            selected_bg,
+        );
+    }
+
+    fn draw_lunge_animations(
+        &self,
+        world: &World,
+        grid: &mut TileGrid<GameSym>,
+        fg: Color,
+        bg: Color,
+        selected_bg: Color,
+    ) {
+        Self::draw_toggle(
+            grid,
+            9,
+            LUNGE_ANIMATIONS_LABEL,
+            world.borrow::<UniqueView<Options>>().lunge_animations,
+            matches!(self.selection, Selection::LungeAnimations),
+            fg,
+            bg,
+            selected_bg,
+        );
+    }
+
+    fn draw_screen_effects(
+        &self,
+        world: &World,
+        grid: &mut TileGrid<GameSym>,
+        fg: Color,
+        bg: Color,
+        selected_bg: Color,
+    ) {
+        Self::draw_toggle(
+            grid,
+            10,
+            SCREEN_EFFECTS_LABEL,
+            world.borrow::<UniqueView<Options>>().screen_effects,
+            matches!(self.selection, Selection::ScreenEffects),
+            fg,
+            bg,
+            selected_bg,
+        );
+    }
+
+    fn draw_allow_undo(
+        &self,
+        world: &World,
+        grid: &mut TileGrid<GameSym>,
+        fg: Color,
+        bg: Color,
+        selected_bg: Color,
+    ) {
+        Self::draw_toggle(
+            grid,
+            11,
+            ALLOW_UNDO_LABEL,
+            world.borrow::<UniqueView<Options>>().allow_undo,
+            matches!(self.selection, Selection::AllowUndo),
+            fg,
+            bg,
+            selected_bg,
+        );
+    }
+
+    fn draw_rest_until(
+        &self,
+        world: &World,
+        grid: &mut TileGrid<GameSym>,
+        fg: Color,
+        bg: Color,
+        selected_bg: Color,
+    ) {
+        let rest_until_left_x = 3 + REST_UNTIL_LABEL.len() as i32;
+        let rest_until_value_x = 3 + rest_until_left_x;
+        let rest_until_right_x = 1 + rest_until_value_x + REST_UNTIL_CHOICE_WIDTH as i32;
+        let rest_until_y = 12;
+        let rest_until_percent = world.borrow::<UniqueView<Options>>().rest_until_percent;
+
+        grid.print((2, rest_until_y), REST_UNTIL_LABEL);
+        if rest_until_percent > REST_UNTIL_STEP {
+            grid.print_color((rest_until_left_x, rest_until_y), "<<", true, fg, bg);
+        }
+        grid.print_color(
+            (rest_until_value_x, rest_until_y),
+            &format!("{:>3}%", rest_until_percent),
+            true,
+            fg,
+            if matches!(self.selection, Selection::RestUntil) {
+                selected_bg
+            } else {
+                bg
+            },
+        );
+        if rest_until_percent < 100 {
+            grid.print_color((rest_until_right_x, rest_until_y), ">>", true, fg, bg);
+        }
+    }
+
+    fn draw_monster_action_delay(
+        &self,
+        world: &World,
+        grid: &mut TileGrid<GameSym>,
+        fg: Color,
+        bg: Color,
+        selected_bg: Color,
+    ) {
+        let delay_left_x = 3 + MONSTER_ACTION_DELAY_LABEL.len() as i32;
+        let delay_value_x = 3 + delay_left_x;
+        let delay_right_x = 2 + delay_value_x;
+        let delay_y = 13;
+        let delay = world.borrow::<UniqueView<Options>>().monster_action_delay;
+
+        grid.print((2, delay_y), MONSTER_ACTION_DELAY_LABEL);
+        if delay > 0 {
+            grid.print_color((delay_left_x, delay_y), "<<", true, fg, bg);
+        }
+        grid.print_color(
+            (delay_value_x, delay_y),
+            &delay.to_string(),
+            true,
+            fg,
+            if matches!(self.selection, Selection::MonsterActionDelay) {
                 selected_bg
             } else {
                 bg
             },
         );
+        if delay < MAX_MONSTER_ACTION_DELAY {
+            grid.print_color((delay_right_x, delay_y), ">>", true, fg, bg);
+        }
+    }
+
+    fn draw_show_exact_hp(
+        &self,
+        world: &World,
+        grid: &mut TileGrid<GameSym>,
+        fg: Color,
+        bg: Color,
+        selected_bg: Color,
+    ) {
+        Self::draw_toggle(
+            grid,
+            14,
+            SHOW_EXACT_HP_LABEL,
+            world.borrow::<UniqueView<Options>>().show_exact_hp,
+            matches!(self.selection, Selection::ShowExactHp),
+            fg,
+            bg,
+            selected_bg,
+        );
+    }
+
+    fn draw_assist_retreat(
+        &self,
+        world: &World,
+        grid: &mut TileGrid<GameSym>,
+        fg: Color,
+        bg: Color,
+        selected_bg: Color,
+    ) {
+        Self::draw_toggle(
+            grid,
+            15,
+            ASSIST_RETREAT_LABEL,
+            world.borrow::<UniqueView<Options>>().assist_retreat,
+            matches!(self.selection, Selection::AssistRetreat),
+            fg,
+            bg,
+            selected_bg,
+        );
+    }
+
+    fn draw_damage_preview(
+        &self,
+        world: &World,
+        grid: &mut TileGrid<GameSym>,
+        fg: Color,
+        bg: Color,
+        selected_bg: Color,
+    ) {
+        Self::draw_toggle(
+            grid,
+            16,
+            DAMAGE_PREVIEW_LABEL,
+            world.borrow::<UniqueView<Options>>().show_damage_preview,
+            matches!(self.selection, Selection::DamagePreview),
+            fg,
+            bg,
+            selected_bg,
+        );
+    }
+
+    fn draw_wall_style(
+        &self,
+        world: &World,
+        grid: &mut TileGrid<GameSym>,
+        fg: Color,
+        bg: Color,
+        selected_bg: Color,
+    ) {
+        let wall_style_left_x = 3 + WALL_STYLE_LABEL.len() as i32;
+        let wall_style_name_x = 3 + wall_style_left_x;
+        let wall_style_right_x = 1
+            + wall_style_name_x
+            + WALL_STYLES.iter().map(|(n, _)| n.len()).max().unwrap_or(0) as i32;
+        let wall_style_y = 17;
+        let index = wall_style_index(&world.borrow::<UniqueView<Options>>());
+
+        grid.print((2, wall_style_y), WALL_STYLE_LABEL);
+        if index > 0 {
+            grid.print_color((wall_style_left_x, wall_style_y), "<<", true, fg, bg);
+        }
         grid.print_color(
-            (map_zoom_2x_x, map_zoom_y),
-            if map_zoom == 2 {
-                ZOOM_2X_ON
+            (wall_style_name_x, wall_style_y),
+            WALL_STYLES[index].0,
+            true,
+            fg,
+            if matches!(self.selection, Selection::WallStyle) {
+                selected_bg
             } else {
-                ZOOM_2X_OFF
+                bg
             },
+        );
+        if index + 1 < WALL_STYLES.len() {
+            grid.print_color((wall_style_right_x, wall_style_y), ">>", true, fg, bg);
+        }
+    }
+
+    fn draw_vision_shape(
+        &self,
+        world: &World,
+        grid: &mut TileGrid<GameSym>,
+        fg: Color,
+        bg: Color,
+        selected_bg: Color,
+    ) {
+        let vision_shape_left_x = 3 + VISION_SHAPE_LABEL.len() as i32;
+        let vision_shape_name_x = 3 + vision_shape_left_x;
+        let vision_shape_right_x = 1
+            + vision_shape_name_x
+            + VISION_SHAPES
+                .iter()
+                .map(|(n, _)| n.len())
+                .max()
+                .unwrap_or(0) as i32;
+        let vision_shape_y = 18;
+        let index = vision_shape_index(&world.borrow::<UniqueView<Options>>());
+
+        grid.print((2, vision_shape_y), VISION_SHAPE_LABEL);
+        if index > 0 {
+            grid.print_color((vision_shape_left_x, vision_shape_y), "<<", true, fg, bg);
+        }
+        grid.print_color(
+            (vision_shape_name_x, vision_shape_y),
+            VISION_SHAPES[index].0,
             true,
             fg,
-            if map_zoom == 2 && matches!(self.selection, Selection::MapZoom) {
+            if matches!(self.selection, Selection::VisionShape) {
                 selected_bg
             } else {
                 bg
             },
         );
+        if index + 1 < VISION_SHAPES.len() {
+            grid.print_color((vision_shape_right_x, vision_shape_y), ">>", true, fg, bg);
+        }
     }
 
-    fn draw_text_zoom(
+    fn draw_quicksave(
         &self,
         world: &World,
         grid: &mut TileGrid<GameSym>,
@@ -372,42 +1338,184 @@ impl OptionsMenuMode {
         bg: Color,
         selected_bg: Color,
     ) {
-        let text_zoom_1x_x = 3 + TEXT_ZOOM_LABEL.len() as i32;
-        let text_zoom_2x_x = 4 + (TEXT_ZOOM_LABEL.len() + ZOOM_1X_OFF.len()) as i32;
-        let text_zoom_y = 5;
-        let text_zoom = world.borrow::<UniqueView<Options>>().text_zoom;
+        Self::draw_toggle(
+            grid,
+            19,
+            QUICKSAVE_LABEL,
+            world.borrow::<UniqueView<Options>>().quicksave_on_exit,
+            matches!(self.selection, Selection::Quicksave),
+            fg,
+            bg,
+            selected_bg,
+        );
+    }
+
+    fn draw_max_monsters(
+        &self,
+        world: &World,
+        grid: &mut TileGrid<GameSym>,
+        fg: Color,
+        bg: Color,
+        selected_bg: Color,
+    ) {
+        let max_monsters_left_x = 3 + MAX_MONSTERS_LABEL.len() as i32;
+        let max_monsters_value_x = 3 + max_monsters_left_x;
+        let max_monsters_right_x = 1 + max_monsters_value_x + MAX_MONSTERS_CHOICE_WIDTH as i32;
+        let max_monsters_y = 20;
+        let max_monsters = world.borrow::<UniqueView<Options>>().max_monsters_per_level;
 
-        grid.print((2, text_zoom_y), TEXT_ZOOM_LABEL);
+        grid.print((2, max_monsters_y), MAX_MONSTERS_LABEL);
+        if max_monsters > MAX_MONSTERS_STEP {
+            grid.print_color((max_monsters_left_x, max_monsters_y), "<<", true, fg, bg);
+        }
         grid.print_color(
-            (text_zoom_1x_x, text_zoom_y),
-            if text_zoom == 1 {
-                ZOOM_1X_ON
+            (max_monsters_value_x, max_monsters_y),
+            &format!("{:>3}", max_monsters),
+            true,
+            fg,
+            if matches!(self.selection, Selection::MaxMonsters) {
+                selected_bg
             } else {
-                ZOOM_1X_OFF
+                bg
             },
+        );
+        if max_monsters < MAX_MONSTERS_LIMIT {
+            grid.print_color((max_monsters_right_x, max_monsters_y), ">>", true, fg, bg);
+        }
+    }
+
+    fn draw_strict_diagonals(
+        &self,
+        world: &World,
+        grid: &mut TileGrid<GameSym>,
+        fg: Color,
+        bg: Color,
+        selected_bg: Color,
+    ) {
+        Self::draw_toggle(
+            grid,
+            21,
+            STRICT_DIAGONALS_LABEL,
+            world.borrow::<UniqueView<Options>>().strict_diagonals,
+            matches!(self.selection, Selection::StrictDiagonals),
+            fg,
+            bg,
+            selected_bg,
+        );
+    }
+
+    fn draw_map_generator(
+        &self,
+        world: &World,
+        grid: &mut TileGrid<GameSym>,
+        fg: Color,
+        bg: Color,
+        selected_bg: Color,
+    ) {
+        let map_generator_left_x = 3 + MAP_GENERATOR_LABEL.len() as i32;
+        let map_generator_name_x = 3 + map_generator_left_x;
+        let map_generator_right_x = 1
+            + map_generator_name_x
+            + MAP_GENERATORS
+                .iter()
+                .map(|(n, _)| n.len())
+                .max()
+                .unwrap_or(0) as i32;
+        let map_generator_y = 22;
+        let index = map_generator_index(&world.borrow::<UniqueView<Options>>());
+
+        grid.print((2, map_generator_y), MAP_GENERATOR_LABEL);
+        if index > 0 {
+            grid.print_color((map_generator_left_x, map_generator_y), "<<", true, fg, bg);
+        }
+        grid.print_color(
+            (map_generator_name_x, map_generator_y),
+            MAP_GENERATORS[index].0,
             true,
             fg,
-            if text_zoom == 1 && matches!(self.selection, Selection::TextZoom) {
+            if matches!(self.selection, Selection::MapGenerator) {
                 selected_bg
             } else {
                 bg
             },
         );
+        if index + 1 < MAP_GENERATORS.len() {
+            grid.print_color((map_generator_right_x, map_generator_y), ">>", true, fg, bg);
+        }
+    }
+
+    fn draw_fov_range(
+        &self,
+        world: &World,
+        grid: &mut TileGrid<GameSym>,
+        fg: Color,
+        bg: Color,
+        selected_bg: Color,
+    ) {
+        let fov_range_left_x = 3 + FOV_RANGE_LABEL.len() as i32;
+        let fov_range_value_x = 3 + fov_range_left_x;
+        let fov_range_right_x = 1 + fov_range_value_x + FOV_RANGE_CHOICE_WIDTH as i32;
+        let fov_range_y = 23;
+        let fov_range = world.borrow::<UniqueView<Options>>().fov_range;
+
+        grid.print((2, fov_range_y), FOV_RANGE_LABEL);
+        if fov_range > MIN_FOV_RANGE {
+            grid.print_color((fov_range_left_x, fov_range_y), "<<", true, fg, bg);
+        }
         grid.print_color(
-            (text_zoom_2x_x, text_zoom_y),
-            if text_zoom == 2 {
-                ZOOM_2X_ON
+            (fov_range_value_x, fov_range_y),
+            &format!("{:>2}", fov_range),
+            true,
+            fg,
+            if matches!(self.selection, Selection::FovRange) {
+                selected_bg
             } else {
-                ZOOM_2X_OFF
+                bg
             },
+        );
+        if fov_range < MAX_FOV_RANGE {
+            grid.print_color((fov_range_right_x, fov_range_y), ">>", true, fg, bg);
+        }
+    }
+
+    fn draw_reveal_style(
+        &self,
+        world: &World,
+        grid: &mut TileGrid<GameSym>,
+        fg: Color,
+        bg: Color,
+        selected_bg: Color,
+    ) {
+        let reveal_style_left_x = 3 + REVEAL_STYLE_LABEL.len() as i32;
+        let reveal_style_name_x = 3 + reveal_style_left_x;
+        let reveal_style_right_x = 1
+            + reveal_style_name_x
+            + REVEAL_STYLES
+                .iter()
+                .map(|(n, _)| n.len())
+                .max()
+                .unwrap_or(0) as i32;
+        let reveal_style_y = 24;
+        let index = reveal_style_index(&world.borrow::<UniqueView<Options>>());
+
+        grid.print((2, reveal_style_y), REVEAL_STYLE_LABEL);
+        if index > 0 {
+            grid.print_color((reveal_style_left_x, reveal_style_y), "<<", true, fg, bg);
+        }
+        grid.print_color(
+            (reveal_style_name_x, reveal_style_y),
+            REVEAL_STYLES[index].0,
             true,
             fg,
-            if text_zoom == 2 && matches!(self.selection, Selection::TextZoom) {
+            if matches!(self.selection, Selection::RevealStyle) {
                 selected_bg
             } else {
                 bg
             },
         );
+        if index + 1 < REVEAL_STYLES.len() {
+            grid.print_color((reveal_style_right_x, reveal_style_y), ">>", true, fg, bg);
+        }
     }
 
     pub fn draw(&self, world: &World, grids: &mut [TileGrid<GameSym>], active: bool) {
@@ -425,9 +1533,39 @@ impl OptionsMenuMode {
         self.draw_font(world, grid, fg, bg, selected_bg);
         self.draw_map_zoom(world, grid, fg, bg, selected_bg);
         self.draw_text_zoom(world, grid, fg, bg, selected_bg);
+        self.draw_player_color(world, grid, fg, bg, selected_bg);
+        self.draw_wandering_monsters(world, grid, fg, bg, selected_bg);
+        self.draw_smooth_scroll(world, grid, fg, bg, selected_bg);
+        self.draw_lunge_animations(world, grid, fg, bg, selected_bg);
+        self.draw_screen_effects(world, grid, fg, bg, selected_bg);
+        self.draw_allow_undo(world, grid, fg, bg, selected_bg);
+        self.draw_rest_until(world, grid, fg, bg, selected_bg);
+        self.draw_monster_action_delay(world, grid, fg, bg, selected_bg);
+        self.draw_show_exact_hp(world, grid, fg, bg, selected_bg);
+        self.draw_assist_retreat(world, grid, fg, bg, selected_bg);
+        self.draw_damage_preview(world, grid, fg, bg, selected_bg);
+        self.draw_wall_style(world, grid, fg, bg, selected_bg);
+        self.draw_vision_shape(world, grid, fg, bg, selected_bg);
+        self.draw_quicksave(world, grid, fg, bg, selected_bg);
+        self.draw_max_monsters(world, grid, fg, bg, selected_bg);
+        self.draw_strict_diagonals(world, grid, fg, bg, selected_bg);
+        self.draw_map_generator(world, grid, fg, bg, selected_bg);
+        self.draw_fov_range(world, grid, fg, bg, selected_bg);
+        self.draw_reveal_style(world, grid, fg, bg, selected_bg);
 
         grid.print_color(
-            (2, 7),
+            (2, 26),
+            KEY_BINDINGS,
+            true,
+            fg,
+            if matches!(self.selection, Selection::KeyBindings) {
+                selected_bg
+            } else {
+                bg
+            },
+        );
+        grid.print_color(
+            (2, 27),
             if self.prompt_to_save { QUIT } else { BACK },
             true,
             fg,
@@ -439,3 +1577,27 @@ impl OptionsMenuMode {
         );
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tilesets_and_fonts_wrap_around() {
+        let last_tileset = ui::NUM_TILESETS - 1;
+
+        assert_eq!(cycle_index(0, ui::NUM_TILESETS, true), 1);
+        assert_eq!(cycle_index(last_tileset, ui::NUM_TILESETS, true), 0);
+        assert_eq!(cycle_index(0, ui::NUM_TILESETS, false), last_tileset);
+        assert_eq!(cycle_index(1, ui::NUM_TILESETS, false), 0);
+        assert_eq!(cycle_index(ui::NUM_FONTS - 1, ui::NUM_FONTS, true), 0);
+        assert_eq!(cycle_index(0, ui::NUM_FONTS, false), ui::NUM_FONTS - 1);
+
+        // Going all the way around in either direction comes back to the start.
+        let mut tileset = 1;
+        for _ in 0..ui::NUM_TILESETS {
+            tileset = cycle_index(tileset, ui::NUM_TILESETS, false);
+        }
+        assert_eq!(tileset, 1);
+    }
+}
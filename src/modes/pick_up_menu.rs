@@ -130,6 +130,14 @@ impl PickUpMenuMode {
                 ModeControl::Pop(PickUpMenuModeResult::Cancelled.into()),
                 ModeUpdate::Immediate,
             )
+        } else if self.items.len() == 1 {
+            // There's nothing to choose between, so pick up the only item without asking.
+            world.borrow::<UniqueViewMut<PickUpHint>>().0 = false;
+
+            (
+                ModeControl::Pop(PickUpMenuModeResult::PickedItem(self.items[0]).into()),
+                ModeUpdate::Immediate,
+            )
         } else {
             inputs.prepare_input();
 
@@ -139,7 +147,7 @@ impl PickUpMenuMode {
                     ModeUpdate::Immediate,
                 );
             } else if let Some(InputEvent::Press(keycode)) = inputs.get_input() {
-                match gamekey::from_keycode(keycode, inputs.get_mods(KeyMods::SHIFT)) {
+                match gamekey::from_keycode(world, keycode, inputs.get_mods(KeyMods::SHIFT)) {
                     GameKey::Down => match self.subsection {
                         SubSection::Items => {
                             if self.selection < self.items.len() as i32 - 1 {
@@ -168,7 +176,7 @@ impl PickUpMenuMode {
                     },
                     GameKey::PageUp => {
                         if matches!(self.subsection, SubSection::Items) {
-                            if let Some(grid) = grids.get(0) {
+                            if let Some(grid) = grids.first() {
                                 self.selection = self
                                     .selection
                                     .saturating_sub(Self::item_list_height(grid))
@@ -178,7 +186,7 @@ impl PickUpMenuMode {
                     }
                     GameKey::PageDown => {
                         if matches!(self.subsection, SubSection::Items) {
-                            if let Some(grid) = grids.get(0) {
+                            if let Some(grid) = grids.first() {
                                 let max_selection = (self.items.len() as i32 - 1).max(0);
 
                                 self.selection = self
@@ -305,3 +313,44 @@ impl PickUpMenuMode {
         );
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spawn;
+
+    #[test]
+    fn menu_lists_every_item_on_a_crowded_tile() {
+        let world = spawn::test_world(20, 20, (5, 5));
+        let here = [
+            GameSym::HealthPotion,
+            GameSym::Ration,
+            GameSym::HealthPotion,
+        ]
+        .iter()
+        .map(|&sym| spawn::spawn_item_kind(&world, sym, (5, 5)).unwrap())
+        .collect::<Vec<_>>();
+        spawn::spawn_item_kind(&world, GameSym::Ration, (6, 5)).unwrap();
+
+        let menu = PickUpMenuMode::new(&world);
+
+        assert_eq!(menu.items.len(), here.len());
+        assert!(here.iter().all(|id| menu.items.contains(id)));
+    }
+
+    #[test]
+    fn a_lone_item_is_picked_up_without_asking() {
+        let world = spawn::test_world(20, 20, (5, 5));
+        let potion_id = spawn::spawn_item_kind(&world, GameSym::HealthPotion, (5, 5)).unwrap();
+        let mut menu = PickUpMenuMode::new(&world);
+
+        let (control, _) = menu.update(&world, &mut InputBuffer::new(), &[], &None);
+
+        assert!(matches!(
+            control,
+            ModeControl::Pop(ModeResult::PickUpMenuModeResult(
+                PickUpMenuModeResult::PickedItem(id)
+            )) if id == potion_id
+        ));
+    }
+}
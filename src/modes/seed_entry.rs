@@ -0,0 +1,159 @@
+use sdl2::keyboard::Keycode;
+use shipyard::{UniqueView, World};
+use std::hash::Hasher;
+use wyhash::WyHash;
+
+use crate::{
+    gamekey::{self, GameKey},
+    gamesym::GameSym,
+    magicnum,
+    ui::{self, Options},
+};
+use ruggrogue::{
+    util::{Color, Size},
+    InputBuffer, InputEvent, KeyMods, TileGrid, Tileset,
+};
+
+use super::{ModeControl, ModeResult, ModeUpdate};
+
+const PROMPT: &str = "Enter a seed (number or text):";
+const HINT: &str = "[enter] Start  [esc] Cancel";
+const MAX_SEED_TEXT_LEN: usize = 24;
+
+pub enum SeedEntryModeResult {
+    AppQuit,
+    Cancelled,
+    Seed(u64),
+}
+
+pub struct SeedEntryMode {
+    text: String,
+}
+
+/// Turn text typed by the player into a game seed.  Text that reads as a number is used as-is so
+/// that seeds shown by the game can be typed back in, while anything else is hashed.
+fn seed_from_text(text: &str) -> u64 {
+    text.parse().unwrap_or_else(|_| {
+        let mut hasher = WyHash::with_seed(magicnum::SEED_FROM_TEXT);
+        hasher.write(text.as_bytes());
+        hasher.finish()
+    })
+}
+
+/// The character that a key types into the seed, if any.
+fn keycode_char(keycode: Keycode, shift: bool) -> Option<char> {
+    let c = char::from_u32(keycode as i32 as u32)?;
+
+    if c.is_ascii_digit() || c == '-' {
+        Some(c)
+    } else if c.is_ascii_lowercase() {
+        Some(if shift { c.to_ascii_uppercase() } else { c })
+    } else {
+        None
+    }
+}
+
+/// A dialog box for typing in a custom seed to start a new game with.
+impl SeedEntryMode {
+    pub fn new() -> Self {
+        Self {
+            text: String::new(),
+        }
+    }
+
+    pub fn prepare_grids(
+        &self,
+        world: &World,
+        grids: &mut Vec<TileGrid<GameSym>>,
+        tilesets: &[Tileset<GameSym>],
+        window_size: Size,
+    ) {
+        let Options {
+            font, text_zoom, ..
+        } = *world.borrow::<UniqueView<Options>>();
+        let new_grid_size = Size {
+            w: 4 + PROMPT.len().max(MAX_SEED_TEXT_LEN + 1).max(HINT.len()) as u32,
+            h: 8,
+        };
+
+        if !grids.is_empty() {
+            grids[0].resize(new_grid_size);
+        } else {
+            grids.push(TileGrid::new(new_grid_size, tilesets, font as usize));
+            grids[0].view.clear_color = None;
+        }
+
+        grids[0].set_tileset(tilesets, font as usize);
+        grids[0].view_centered(tilesets, text_zoom, (0, 0).into(), window_size);
+        grids[0].view.zoom = text_zoom;
+    }
+
+    pub fn update(
+        &mut self,
+        world: &World,
+        inputs: &mut InputBuffer,
+        _grids: &[TileGrid<GameSym>],
+        _pop_result: &Option<ModeResult>,
+    ) -> (ModeControl, ModeUpdate) {
+        inputs.prepare_input();
+
+        if let Some(InputEvent::AppQuit) = inputs.get_input() {
+            return (
+                ModeControl::Pop(SeedEntryModeResult::AppQuit.into()),
+                ModeUpdate::Immediate,
+            );
+        } else if let Some(InputEvent::Press(keycode)) = inputs.get_input() {
+            let shift = inputs.get_mods(KeyMods::SHIFT);
+
+            if let Some(c) = keycode_char(keycode, shift) {
+                if self.text.len() < MAX_SEED_TEXT_LEN {
+                    self.text.push(c);
+                }
+            } else if keycode == Keycode::Backspace {
+                self.text.pop();
+            } else {
+                match gamekey::from_keycode(world, keycode, shift) {
+                    GameKey::Confirm if !self.text.is_empty() => {
+                        inputs.clear_input();
+                        return (
+                            ModeControl::Pop(
+                                SeedEntryModeResult::Seed(seed_from_text(&self.text)).into(),
+                            ),
+                            ModeUpdate::Immediate,
+                        );
+                    }
+                    GameKey::Cancel => {
+                        inputs.clear_input();
+                        return (
+                            ModeControl::Pop(SeedEntryModeResult::Cancelled.into()),
+                            ModeUpdate::Immediate,
+                        );
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        (ModeControl::Stay, ModeUpdate::WaitForEvent)
+    }
+
+    pub fn draw(&self, _world: &World, grids: &mut [TileGrid<GameSym>], active: bool) {
+        let grid = &mut grids[0];
+        let fg = Color::WHITE;
+        let bg = Color::BLACK;
+
+        grid.view.color_mod = if active { Color::WHITE } else { Color::GRAY };
+
+        grid.draw_box((0, 0), (grid.width(), grid.height()), fg, bg);
+        grid.print((2, 2), PROMPT);
+        grid.print_color((2, 3), &self.text, true, Color::YELLOW, bg);
+        grid.print_color(
+            (2 + self.text.len() as i32, 3),
+            "_",
+            true,
+            fg,
+            ui::SELECTED_BG,
+        );
+        grid.print_color((2, 5), HINT, true, Color::GRAY, bg);
+    }
+}
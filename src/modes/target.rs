@@ -1,18 +1,19 @@
 use shipyard::{Get, UniqueView, View, World};
-use std::collections::HashSet;
+use std::collections::BTreeSet;
 
 use crate::{
     chunked::ChunkedMapGrid,
-    components::{Coord, FieldOfView, Monster},
+    components::{Coord, Disguise, FieldOfView, Monster},
     gamekey::{self, GameKey},
     gamesym::GameSym,
     map::Map,
     player::PlayerId,
     render, ui,
+    vision::{self, DistanceMetric},
 };
 use ruggrogue::{
     util::{Color, Position, Size},
-    InputBuffer, InputEvent, KeyMods, TileGrid, Tileset,
+    InputBuffer, InputEvent, KeyMods, TileGrid, Tileset, ViewableField,
 };
 
 use super::{
@@ -34,7 +35,8 @@ pub struct TargetMode {
     center: (i32, i32), // x, y
     range: i32,
     radius: i32,
-    valid: HashSet<(i32, i32)>,
+    metric: DistanceMetric,
+    valid: BTreeSet<(i32, i32)>,
     cursor: (i32, i32), // x, y
     warn_self: bool,
 }
@@ -43,9 +45,39 @@ fn dist2((x1, y1): (i32, i32), (x2, y2): (i32, i32)) -> i32 {
     (x2 - x1).pow(2) + (y2 - y1).pow(2)
 }
 
+/// Valid target positions with visible monsters in them, closest to the center first.  Ties are
+/// broken by position so that the order is the same every time.
+fn monster_positions(
+    world: &World,
+    valid: &BTreeSet<(i32, i32)>,
+    center: (i32, i32),
+) -> Vec<(i32, i32)> {
+    let map = world.borrow::<UniqueView<Map>>();
+    let disguises = world.borrow::<View<Disguise>>();
+    let monsters = world.borrow::<View<Monster>>();
+    let mut positions = valid
+        .iter()
+        .filter(|(x, y)| {
+            map.iter_entities_at(*x, *y)
+                .any(|id| monsters.contains(id) && !disguises.contains(id))
+        })
+        .copied()
+        .collect::<Vec<_>>();
+
+    positions.sort_by_key(|&(x, y)| (dist2((x, y), center), y, x));
+    positions
+}
+
 /// Pick a target position within a certain range of the player.
 impl TargetMode {
-    pub fn new(world: &World, for_what: String, range: i32, radius: i32, warn_self: bool) -> Self {
+    pub fn new(
+        world: &World,
+        for_what: String,
+        range: i32,
+        radius: i32,
+        metric: DistanceMetric,
+        warn_self: bool,
+    ) -> Self {
         assert!(range >= 0);
         assert!(radius >= 0);
 
@@ -55,24 +87,15 @@ impl TargetMode {
             });
 
         let valid = world.run(|player_id: UniqueView<PlayerId>, fovs: View<FieldOfView>| {
-            // Add 0.5 to the range to prevent 'bumps' at the edge of the range circle.
-            let max_dist2 = range * (range + 1);
             fovs.get(player_id.0)
                 .iter()
-                .filter(|pos| dist2(*pos, player_pos) <= max_dist2)
-                .collect::<HashSet<_>>()
+                .filter(|pos| metric.within(*pos, player_pos, range))
+                .collect::<BTreeSet<_>>()
         });
 
         // Default to the closest monster position, or the player if no monsters are present.
-        let cursor = valid
-            .iter()
-            .filter(|(x, y)| {
-                world
-                    .borrow::<UniqueView<Map>>()
-                    .iter_entities_at(*x, *y)
-                    .any(|id| world.borrow::<View<Monster>>().contains(id))
-            })
-            .min_by_key(|pos| dist2(**pos, player_pos))
+        let cursor = monster_positions(world, &valid, player_pos)
+            .first()
             .copied()
             .unwrap_or(player_pos);
 
@@ -84,12 +107,31 @@ impl TargetMode {
             center: player_pos,
             range,
             radius,
+            metric,
             valid,
             cursor,
             warn_self,
         }
     }
 
+    /// Jump the cursor to the next or previous monster in order of distance, wrapping around at
+    /// either end.
+    fn cycle_target(&mut self, world: &World, forward: bool) {
+        let targets = monster_positions(world, &self.valid, self.center);
+
+        if !targets.is_empty() {
+            let last = targets.len() - 1;
+            let index = match targets.iter().position(|pos| *pos == self.cursor) {
+                Some(i) if forward => (i + 1) % targets.len(),
+                Some(i) => i.checked_sub(1).unwrap_or(last),
+                None if forward => 0,
+                None => last,
+            };
+
+            self.cursor = targets[index];
+        }
+    }
+
     pub fn prepare_grids(
         &mut self,
         world: &World,
@@ -114,7 +156,7 @@ impl TargetMode {
 
     pub fn update(
         &mut self,
-        _world: &World,
+        world: &World,
         inputs: &mut InputBuffer,
         _grids: &[TileGrid<GameSym>],
         pop_result: &Option<ModeResult>,
@@ -156,7 +198,7 @@ impl TargetMode {
             let max_y = self.center.1 + self.range;
             let old_cursor = self.cursor;
 
-            match gamekey::from_keycode(keycode, inputs.get_mods(KeyMods::SHIFT)) {
+            match gamekey::from_keycode(world, keycode, inputs.get_mods(KeyMods::SHIFT)) {
                 GameKey::Left => {
                     self.cursor.0 = std::cmp::max(min_x, self.cursor.0 - 1);
                 }
@@ -169,68 +211,67 @@ impl TargetMode {
                 GameKey::Right => {
                     self.cursor.0 = std::cmp::min(max_x, self.cursor.0 + 1);
                 }
-                GameKey::UpLeft => {
-                    if self.cursor.0 > min_x && self.cursor.1 > min_y {
-                        self.cursor.0 -= 1;
-                        self.cursor.1 -= 1;
-                    }
+                GameKey::UpLeft if self.cursor.0 > min_x && self.cursor.1 > min_y => {
+                    self.cursor.0 -= 1;
+                    self.cursor.1 -= 1;
                 }
-                GameKey::UpRight => {
-                    if self.cursor.0 < max_x && self.cursor.1 > min_y {
-                        self.cursor.0 += 1;
-                        self.cursor.1 -= 1;
-                    }
+                GameKey::UpRight if self.cursor.0 < max_x && self.cursor.1 > min_y => {
+                    self.cursor.0 += 1;
+                    self.cursor.1 -= 1;
                 }
-                GameKey::DownLeft => {
-                    if self.cursor.0 > min_x && self.cursor.1 < max_y {
-                        self.cursor.0 -= 1;
-                        self.cursor.1 += 1;
-                    }
+                GameKey::DownLeft if self.cursor.0 > min_x && self.cursor.1 < max_y => {
+                    self.cursor.0 -= 1;
+                    self.cursor.1 += 1;
                 }
-                GameKey::DownRight => {
-                    if self.cursor.0 < max_x && self.cursor.1 < max_y {
-                        self.cursor.0 += 1;
-                        self.cursor.1 += 1;
-                    }
+                GameKey::DownRight if self.cursor.0 < max_x && self.cursor.1 < max_y => {
+                    self.cursor.0 += 1;
+                    self.cursor.1 += 1;
                 }
+                GameKey::NextTarget => self.cycle_target(world, true),
+                GameKey::PrevTarget => self.cycle_target(world, false),
                 GameKey::Cancel => {
                     return (
                         ModeControl::Pop(TargetModeResult::Cancelled.into()),
                         ModeUpdate::Immediate,
                     )
                 }
-                GameKey::Confirm | GameKey::UseItem => {
-                    if self.valid.contains(&self.cursor) {
-                        let result = if self.warn_self
-                            && dist2(self.cursor, self.center) <= self.radius * (self.radius + 1)
-                        {
-                            inputs.clear_input();
-                            ModeControl::Push(
-                                YesNoDialogMode::new(
-                                    format!(
-                                        "Really {} yourself?",
-                                        if self.cursor == self.center {
-                                            "target"
-                                        } else {
-                                            "include"
-                                        },
-                                    ),
-                                    false,
-                                )
-                                .into(),
-                            )
-                        } else {
-                            ModeControl::Pop(
-                                TargetModeResult::Target {
-                                    x: self.cursor.0,
-                                    y: self.cursor.1,
-                                }
-                                .into(),
+                GameKey::Confirm | GameKey::UseItem
+                    if self.valid.contains(&self.cursor)
+                        && vision::clear_line(
+                            &world.borrow::<UniqueView<Map>>(),
+                            self.center,
+                            self.cursor,
+                        ) =>
+                {
+                    let result = if self.warn_self
+                        && self.metric.within(self.cursor, self.center, self.radius)
+                    {
+                        inputs.clear_input();
+                        ModeControl::Push(
+                            YesNoDialogMode::new(
+                                format!(
+                                    "Really {} yourself?",
+                                    if self.cursor == self.center {
+                                        "target"
+                                    } else {
+                                        "include"
+                                    },
+                                ),
+                                false,
                             )
-                        };
+                            .into(),
+                        )
+                    } else {
+                        ModeControl::Pop(
+                            TargetModeResult::Target {
+                                x: self.cursor.0,
+                                y: self.cursor.1,
+                            }
+                            .into(),
+                        )
+                    };
 
-                        return (result, ModeUpdate::Immediate);
-                    }
+                    return (result, ModeUpdate::Immediate);
                 }
                 _ => {}
             }
@@ -277,8 +318,6 @@ impl TargetMode {
         self.chunked_map_grid.draw(world, map_grid);
         render::draw_renderables(&self.chunked_map_grid, world, map_grid);
 
-        let radius2 = self.radius * (self.radius + 1);
-
         // Highlight targetable spaces.
         for y in (self.center.1 - self.range)..=(self.center.1 + self.range) {
             for x in (self.center.0 - self.range)..=(self.center.0 + self.range) {
@@ -296,7 +335,7 @@ impl TargetMode {
         // Highlight area of effect.
         for y in (self.cursor.1 - self.radius)..=(self.cursor.1 + self.radius) {
             for x in (self.cursor.0 - self.radius)..=(self.cursor.0 + self.radius) {
-                if dist2((x, y), self.cursor) <= radius2 {
+                if self.metric.within((x, y), self.cursor, self.radius) {
                     if let Some(pos) = self
                         .chunked_map_grid
                         .map_to_grid_pos(world, Position { x, y })
@@ -307,6 +346,25 @@ impl TargetMode {
             }
         }
 
+        // Highlight the line of sight to the cursor, turning red from the first tile that blocks it.
+        let line_clear = {
+            let map = world.borrow::<UniqueView<Map>>();
+            let line = vision::line_between(self.center, self.cursor);
+            let mut blocked = false;
+
+            for &(x, y) in line.iter().skip(1) {
+                blocked = blocked || ((x, y) != self.cursor && map.is_opaque(x, y));
+                if let Some(pos) = self
+                    .chunked_map_grid
+                    .map_to_grid_pos(world, Position { x, y })
+                {
+                    map_grid.recolor_pos(pos, None, if blocked { Color::RED } else { Color::CYAN });
+                }
+            }
+
+            !blocked
+        };
+
         // Highlight cursor position.
         if let Some(pos) = self
             .chunked_map_grid
@@ -316,13 +374,15 @@ impl TargetMode {
         }
 
         // Describe the location that the cursor is positioned at.
-        let cursor_desc = if self.valid.contains(&self.cursor) {
+        let cursor_desc = if !self.valid.contains(&self.cursor) {
+            "Out of range".to_string()
+        } else if !line_clear {
+            "No line of sight.".to_string()
+        } else {
             world
                 .borrow::<UniqueView<Map>>()
                 .describe_pos(world, self.cursor.0, self.cursor.1, true, false, false)
                 .0
-        } else {
-            "Out of range".to_string()
         };
 
         if self.redraw_msg_frame_grid {
@@ -342,3 +402,129 @@ impl TargetMode {
         );
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use shipyard::{EntitiesViewMut, UniqueViewMut, ViewMut};
+
+    use super::super::inventory_action;
+    use super::*;
+    use crate::{
+        components::{Name, Player},
+        spawn,
+        ui::{Options, VisionShape},
+    };
+
+    /// A 20x20 map with the player at (10, 10) and goblins at the given positions, spawned in
+    /// order.
+    fn targeting_world(goblin_positions: &[(i32, i32)]) -> World {
+        let world = World::new();
+
+        world.add_unique(Map::new(20, 20));
+        world.add_unique(Options::default());
+
+        let player_id = world.run(
+            |mut entities: EntitiesViewMut,
+             mut coords: ViewMut<Coord>,
+             mut fovs: ViewMut<FieldOfView>,
+             mut players: ViewMut<Player>| {
+                entities.add_entity(
+                    (&mut coords, &mut fovs, &mut players),
+                    (
+                        Coord((10, 10).into()),
+                        FieldOfView::new(8),
+                        Player {
+                            auto_run: None,
+                            low_hp_warned: false,
+                            damage_previewed: None,
+                        },
+                    ),
+                )
+            },
+        );
+
+        world.add_unique(PlayerId(player_id));
+        for &pos in goblin_positions {
+            spawn::spawn_monster_kind(&world, GameSym::Goblin, pos);
+        }
+        world.run(vision::recalculate_fields_of_view);
+
+        world
+    }
+
+    #[test]
+    fn tab_visits_monsters_closest_first() {
+        let world = targeting_world(&[(13, 10), (10, 11), (8, 12)]);
+        let mut target_mode = TargetMode::new(
+            &world,
+            "testing".into(),
+            6,
+            0,
+            DistanceMetric::Euclidean,
+            false,
+        );
+        let mut visited = vec![target_mode.cursor];
+
+        for _ in 0..3 {
+            target_mode.cycle_target(&world, true);
+            visited.push(target_mode.cursor);
+        }
+        assert_eq!(visited, [(10, 11), (8, 12), (13, 10), (10, 11)]);
+
+        target_mode.cycle_target(&world, false);
+        assert_eq!(target_mode.cursor, (13, 10));
+    }
+
+    #[test]
+    fn equidistant_monsters_give_a_consistent_default_target() {
+        let around = [(12, 10), (8, 10), (10, 12), (10, 8)];
+        let reversed = around.iter().rev().copied().collect::<Vec<_>>();
+
+        for positions in [&around[..], &reversed[..]] {
+            let world = targeting_world(positions);
+            let target_mode = TargetMode::new(
+                &world,
+                "testing".into(),
+                6,
+                0,
+                DistanceMetric::Euclidean,
+                false,
+            );
+
+            // Ties in distance are broken by row, then column.
+            assert_eq!(target_mode.cursor, (10, 8));
+        }
+    }
+
+    #[test]
+    fn valid_targets_follow_the_distance_metric() {
+        let world = targeting_world(&[]);
+        let valid = |metric| TargetMode::new(&world, "testing".into(), 3, 0, metric, false).valid;
+        let euclidean = valid(DistanceMetric::Euclidean);
+        let chebyshev = valid(DistanceMetric::Chebyshev);
+        let manhattan = valid(DistanceMetric::Manhattan);
+
+        assert_eq!(euclidean.len(), 37);
+        assert_eq!(chebyshev.len(), 7 * 7);
+        assert_eq!(manhattan.len(), 25);
+        assert!(manhattan.is_subset(&euclidean));
+        assert!(euclidean.is_subset(&chebyshev));
+        assert!(euclidean.contains(&(13, 11)) && !manhattan.contains(&(13, 11)));
+        assert!(chebyshev.contains(&(13, 13)) && !euclidean.contains(&(13, 13)));
+    }
+
+    #[test]
+    fn throwing_range_ignores_the_vision_shape() {
+        let world = targeting_world(&[]);
+        let item_id = world.run(|mut entities: EntitiesViewMut, mut names: ViewMut<Name>| {
+            entities.add_entity(&mut names, Name("Dagger".into()))
+        });
+        let throw_valid = |world: &World| inventory_action::throw_target_mode(world, item_id).valid;
+        let circle_valid = throw_valid(&world);
+
+        world.borrow::<UniqueViewMut<Options>>().vision_shape = VisionShape::Square;
+        world.run(vision::recalculate_fields_of_view);
+
+        assert_eq!(throw_valid(&world), circle_valid);
+    }
+}
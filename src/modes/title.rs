@@ -5,6 +5,7 @@ use crate::{
     experience::{self, Difficulty},
     gamekey::{self, GameKey},
     gamesym::GameSym,
+    highscore,
     item::PickUpHint,
     map::{self, Map},
     menu_memory::MenuMemory,
@@ -23,6 +24,7 @@ use super::{
     dungeon::DungeonMode,
     message_box::{MessageBoxMode, MessageBoxModeResult},
     options_menu::{OptionsMenuMode, OptionsMenuModeResult},
+    seed_entry::{SeedEntryMode, SeedEntryModeResult},
     yes_no_dialog::{YesNoDialogMode, YesNoDialogModeResult},
     ModeControl, ModeResult, ModeUpdate,
 };
@@ -55,10 +57,13 @@ pub enum TitleModeResult {
     AppQuit,
 }
 
+#[derive(Clone, Copy)]
 pub enum TitleAction {
     NewGame,
+    CustomSeed,
     LoadGame,
     Options,
+    HighScores,
     #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
     Quit,
 }
@@ -67,17 +72,21 @@ impl TitleAction {
     fn label(&self) -> &'static str {
         match self {
             TitleAction::NewGame => "New Game",
+            TitleAction::CustomSeed => "Custom Seed",
             TitleAction::LoadGame => "Load Game",
             TitleAction::Options => "Options",
+            TitleAction::HighScores => "High Scores",
             TitleAction::Quit => "Quit",
         }
     }
 }
 
-const ALL_TITLE_ACTIONS: [TitleAction; 4] = [
+const ALL_TITLE_ACTIONS: [TitleAction; 6] = [
     TitleAction::NewGame,
+    TitleAction::CustomSeed,
     TitleAction::LoadGame,
     TitleAction::Options,
+    TitleAction::HighScores,
     TitleAction::Quit,
 ];
 
@@ -102,6 +111,9 @@ pub fn new_game_setup(world: &World, new_game_plus: bool) {
 
         // Increment turn count and depth.
         world.borrow::<UniqueViewMut<TurnCount>>().0 += 1;
+        world
+            .borrow::<UniqueViewMut<Messages>>()
+            .set_turn(world.borrow::<UniqueView<TurnCount>>().0);
         world.borrow::<UniqueViewMut<Map>>().depth += 1;
 
         let player_id = world.borrow::<UniqueView<PlayerId>>().0;
@@ -128,6 +140,7 @@ pub fn new_game_setup(world: &World, new_game_plus: bool) {
 
         // Reset turn count and depth.
         world.borrow::<UniqueViewMut<TurnCount>>().0 = 1;
+        world.borrow::<UniqueViewMut<Messages>>().set_turn(1);
         world.borrow::<UniqueViewMut<Map>>().depth = 1;
 
         // Replace the old player with a fresh one.
@@ -155,7 +168,7 @@ pub fn new_game_setup(world: &World, new_game_plus: bool) {
     }
 
     if let Some(victory_pos) = world.run(map::generate_rooms_and_corridors) {
-        spawn::spawn_present(world, victory_pos);
+        spawn::spawn_boss(world, victory_pos);
     }
     world.run(player::add_coords_to_players);
     world.run(map::place_player_in_first_room);
@@ -181,12 +194,14 @@ pub struct TitleMode {
     menu_width: u32,
     menu_height: u32,
     selection: usize,
+    /// Action that's waiting for the player to agree to delete their save data first.
+    confirming: Option<TitleAction>,
 }
 
 /// Show the title screen of the game with a menu that leads into the game proper.
 impl TitleMode {
     pub fn new() -> Self {
-        let mut actions = vec![TitleAction::NewGame];
+        let mut actions = vec![TitleAction::NewGame, TitleAction::CustomSeed];
 
         // There's no obvious way to get Emscripten to load the IndexedDB filesystem in time to
         // realize that a save file exists, so always include the Load Game option for it and just
@@ -196,6 +211,7 @@ impl TitleMode {
         }
 
         actions.push(TitleAction::Options);
+        actions.push(TitleAction::HighScores);
 
         #[cfg(not(target_arch = "wasm32"))]
         actions.push(TitleAction::Quit);
@@ -218,6 +234,7 @@ impl TitleMode {
                 .unwrap_or(0) as u32,
             menu_height: ALL_TITLE_ACTIONS.len() as u32,
             selection,
+            confirming: None,
         }
     }
 
@@ -342,12 +359,36 @@ impl TitleMode {
                     OptionsMenuModeResult::Closed => (ModeControl::Stay, ModeUpdate::WaitForEvent),
                     OptionsMenuModeResult::ReallyQuit => unreachable!(),
                 },
+                ModeResult::SeedEntryModeResult(result) => match result {
+                    SeedEntryModeResult::AppQuit => (
+                        ModeControl::Pop(TitleModeResult::AppQuit.into()),
+                        ModeUpdate::Immediate,
+                    ),
+                    SeedEntryModeResult::Cancelled => (ModeControl::Stay, ModeUpdate::WaitForEvent),
+                    SeedEntryModeResult::Seed(seed) => {
+                        world.borrow::<UniqueViewMut<GameSeed>>().0 = *seed;
+                        new_game_setup(world, false);
+
+                        // Show the seed so that it can be shared, especially if it was hashed.
+                        world
+                            .borrow::<UniqueViewMut<Messages>>()
+                            .add(format!("Game seed: {}", seed));
+
+                        inputs.clear_input();
+                        (
+                            ModeControl::Switch(DungeonMode::new().into()),
+                            ModeUpdate::Immediate,
+                        )
+                    }
+                },
                 ModeResult::YesNoDialogModeResult(result) => match result {
                     YesNoDialogModeResult::AppQuit => (
                         ModeControl::Pop(TitleModeResult::AppQuit.into()),
                         ModeUpdate::Immediate,
                     ),
                     YesNoDialogModeResult::Yes => {
+                        let confirmed = self.confirming.take();
+
                         saveload::delete_save_file();
 
                         // Remove the load game option.
@@ -355,11 +396,13 @@ impl TitleMode {
                             .retain(|a| !matches!(*a, TitleAction::LoadGame));
 
                         // Adjust selection if needed.
-                        if let Some(pos) = self
-                            .actions
-                            .iter()
-                            .position(|a| matches!(*a, TitleAction::NewGame))
-                        {
+                        if let Some(pos) = self.actions.iter().position(|a| {
+                            if matches!(confirmed, Some(TitleAction::CustomSeed)) {
+                                matches!(*a, TitleAction::CustomSeed)
+                            } else {
+                                matches!(*a, TitleAction::NewGame)
+                            }
+                        }) {
                             self.selection = pos;
                         } else {
                             self.selection =
@@ -367,9 +410,21 @@ impl TitleMode {
                         }
 
                         inputs.clear_input();
-                        (ModeControl::Stay, ModeUpdate::Immediate)
+
+                        // Carry on to entering a seed now that there's no save data in the way.
+                        if matches!(confirmed, Some(TitleAction::CustomSeed)) {
+                            (
+                                ModeControl::Push(SeedEntryMode::new().into()),
+                                ModeUpdate::Immediate,
+                            )
+                        } else {
+                            (ModeControl::Stay, ModeUpdate::Immediate)
+                        }
+                    }
+                    YesNoDialogModeResult::No => {
+                        self.confirming = None;
+                        (ModeControl::Stay, ModeUpdate::WaitForEvent)
                     }
-                    YesNoDialogModeResult::No => (ModeControl::Stay, ModeUpdate::WaitForEvent),
                 },
                 _ => unreachable!(),
             };
@@ -386,7 +441,7 @@ impl TitleMode {
             }
 
             Some(InputEvent::Press(keycode)) => {
-                match gamekey::from_keycode(keycode, inputs.get_mods(KeyMods::SHIFT)) {
+                match gamekey::from_keycode(world, keycode, inputs.get_mods(KeyMods::SHIFT)) {
                     GameKey::Up => {
                         if self.selection > 0 {
                             self.selection -= 1;
@@ -414,8 +469,9 @@ impl TitleMode {
                         assert!(self.selection < self.actions.len());
 
                         match self.actions[self.selection] {
-                            TitleAction::NewGame => {
+                            TitleAction::NewGame | TitleAction::CustomSeed => {
                                 if saveload::save_file_exists() {
+                                    self.confirming = Some(self.actions[self.selection]);
                                     inputs.clear_input();
                                     return (
                                         ModeControl::Push(
@@ -427,6 +483,15 @@ impl TitleMode {
                                         ),
                                         ModeUpdate::Immediate,
                                     );
+                                } else if matches!(
+                                    self.actions[self.selection],
+                                    TitleAction::CustomSeed
+                                ) {
+                                    inputs.clear_input();
+                                    return (
+                                        ModeControl::Push(SeedEntryMode::new().into()),
+                                        ModeUpdate::Immediate,
+                                    );
                                 } else {
                                     new_game_setup(world, false);
                                     inputs.clear_input();
@@ -489,6 +554,18 @@ impl TitleMode {
                                     ModeUpdate::Immediate,
                                 );
                             }
+                            TitleAction::HighScores => {
+                                inputs.clear_input();
+                                return (
+                                    ModeControl::Push(
+                                        MessageBoxMode::new(
+                                            highscore::load_high_scores().describe(),
+                                        )
+                                        .into(),
+                                    ),
+                                    ModeUpdate::Immediate,
+                                );
+                            }
                             TitleAction::Quit => {
                                 return (
                                     ModeControl::Pop(TitleModeResult::AppQuit.into()),
@@ -1,10 +1,11 @@
-use shipyard::{Get, UniqueView, UniqueViewMut, View, World};
+use shipyard::{EntityId, Get, UniqueView, UniqueViewMut, View, World};
 
 use crate::{
     chunked::{Camera, ChunkedMapGrid},
-    components::Coord,
+    components::{Coord, FieldOfView, Item},
     gamekey::{self, GameKey},
     gamesym::GameSym,
+    item,
     map::Map,
     player::PlayerId,
     render, ui,
@@ -14,7 +15,11 @@ use ruggrogue::{
     InputBuffer, InputEvent, KeyMods, TileGrid, Tileset,
 };
 
-use super::{ModeControl, ModeResult, ModeUpdate};
+use super::{
+    message_box::{MessageBoxMode, MessageBoxModeResult},
+    note_entry::{NoteEntryMode, NoteEntryModeResult},
+    ModeControl, ModeResult, ModeUpdate,
+};
 
 const SHIFT_STEP: i32 = 5;
 
@@ -39,6 +44,24 @@ fn reset_camera(
     camera.0 = coords.get(player_id.0).0;
 }
 
+/// Find an item that the player can currently see at the camera position.
+fn visible_item_at_camera(world: &World) -> Option<EntityId> {
+    let (map, camera, player_id, fovs, items) = world.borrow::<(
+        UniqueView<Map>,
+        UniqueView<Camera>,
+        UniqueView<PlayerId>,
+        View<FieldOfView>,
+        View<Item>,
+    )>();
+
+    if fovs.get(player_id.0).get(camera.0.into()) {
+        map.iter_entities_at(camera.0.x, camera.0.y)
+            .find(|id| items.contains(*id))
+    } else {
+        None
+    }
+}
+
 /// Show a movable cursor that describes seen and recalled map tiles and any occupying entities.
 impl ViewMapMode {
     pub fn new(world: &World) -> Self {
@@ -78,8 +101,46 @@ impl ViewMapMode {
         world: &World,
         inputs: &mut InputBuffer,
         _grids: &[TileGrid<GameSym>],
-        _pop_result: &Option<ModeResult>,
+        pop_result: &Option<ModeResult>,
     ) -> (ModeControl, ModeUpdate) {
+        if let Some(result) = pop_result {
+            return match result {
+                ModeResult::MessageBoxModeResult(result) => match result {
+                    MessageBoxModeResult::AppQuit => {
+                        world.run(reset_camera);
+                        (
+                            ModeControl::Pop(ViewMapModeResult::AppQuit.into()),
+                            ModeUpdate::Immediate,
+                        )
+                    }
+                    MessageBoxModeResult::Done => (ModeControl::Stay, ModeUpdate::WaitForEvent),
+                },
+                ModeResult::NoteEntryModeResult(result) => match result {
+                    NoteEntryModeResult::AppQuit => {
+                        world.run(reset_camera);
+                        (
+                            ModeControl::Pop(ViewMapModeResult::AppQuit.into()),
+                            ModeUpdate::Immediate,
+                        )
+                    }
+                    NoteEntryModeResult::Cancelled => (ModeControl::Stay, ModeUpdate::WaitForEvent),
+                    NoteEntryModeResult::Note(text) => {
+                        let camera = world.borrow::<UniqueView<Camera>>();
+
+                        world.borrow::<UniqueViewMut<Map>>().set_note(
+                            camera.0.x,
+                            camera.0.y,
+                            text.clone(),
+                        );
+                        self.chunked_map_grid
+                            .mark_dirty(camera.0, Size { w: 1, h: 1 });
+                        (ModeControl::Stay, ModeUpdate::WaitForEvent)
+                    }
+                },
+                _ => unreachable!(),
+            };
+        }
+
         inputs.prepare_input();
 
         if let Some(InputEvent::AppQuit) = inputs.get_input() {
@@ -95,7 +156,7 @@ impl ViewMapMode {
             let mut move_x = 0;
             let mut move_y = 0;
 
-            match gamekey::from_keycode(keycode, shift) {
+            match gamekey::from_keycode(world, keycode, shift) {
                 GameKey::Up => move_y = -move_amount,
                 GameKey::Down => move_y = move_amount,
                 GameKey::Left => move_x = -move_amount,
@@ -127,6 +188,36 @@ impl ViewMapMode {
                     move_x = player_pos.x - camera.0.x;
                     move_y = player_pos.y - camera.0.y;
                 }
+                GameKey::Examine => {
+                    // Examine an item lying on the floor without picking it up.
+                    if let Some(item_id) = visible_item_at_camera(world) {
+                        inputs.clear_input();
+                        return (
+                            ModeControl::Push(
+                                MessageBoxMode::new(item::describe_item(world, item_id)).into(),
+                            ),
+                            ModeUpdate::Immediate,
+                        );
+                    }
+                }
+                GameKey::Mark => {
+                    // Leave a note on a seen tile, e.g. to plan a route through a large level.
+                    let camera = world.borrow::<UniqueView<Camera>>();
+                    let map = world.borrow::<UniqueView<Map>>();
+
+                    if map.seen.get_bit(camera.0.x, camera.0.y) {
+                        let text = map
+                            .note_at(camera.0.x, camera.0.y)
+                            .unwrap_or("")
+                            .to_string();
+
+                        inputs.clear_input();
+                        return (
+                            ModeControl::Push(NoteEntryMode::new(text).into()),
+                            ModeUpdate::Immediate,
+                        );
+                    }
+                }
                 GameKey::Confirm | GameKey::Cancel | GameKey::ViewMap => {
                     world.run(reset_camera);
                     return (
@@ -239,3 +330,28 @@ impl ViewMapMode {
         );
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{spawn, vision};
+
+    #[test]
+    fn examining_a_floor_item_describes_it() {
+        let world = spawn::test_world(20, 20, (5, 5));
+        let potion_id = spawn::spawn_item_kind(&world, GameSym::HealthPotion, (7, 5)).unwrap();
+
+        world.run(vision::recalculate_fields_of_view);
+        world.borrow::<UniqueViewMut<Camera>>().0 = (7, 5).into();
+
+        assert_eq!(visible_item_at_camera(&world), Some(potion_id));
+
+        let lines = item::describe_item(&world, potion_id);
+        assert_eq!(lines[0], "Health Potion");
+        assert!(lines.iter().any(|line| line.starts_with("Heals: ")));
+
+        // Nothing is examined on an empty tile.
+        world.borrow::<UniqueViewMut<Camera>>().0 = (8, 5).into();
+        assert_eq!(visible_item_at_camera(&world), None);
+    }
+}
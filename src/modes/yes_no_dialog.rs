@@ -74,7 +74,7 @@ impl YesNoDialogMode {
 
     pub fn update(
         &mut self,
-        _world: &World,
+        world: &World,
         inputs: &mut InputBuffer,
         _grids: &[TileGrid<GameSym>],
         _pop_result: &Option<ModeResult>,
@@ -87,7 +87,7 @@ impl YesNoDialogMode {
                 ModeUpdate::Immediate,
             );
         } else if let Some(InputEvent::Press(keycode)) = inputs.get_input() {
-            match gamekey::from_keycode(keycode, inputs.get_mods(KeyMods::SHIFT)) {
+            match gamekey::from_keycode(world, keycode, inputs.get_mods(KeyMods::SHIFT)) {
                 GameKey::Left => self.yes_selected = true,
                 GameKey::Right => self.yes_selected = false,
                 GameKey::Confirm => {
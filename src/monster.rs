@@ -1,15 +1,46 @@
+use rand::{seq::SliceRandom, SeedableRng};
+use rand_xoshiro::Xoshiro128PlusPlus as GameRng;
 use shipyard::{
     EntitiesView, EntityId, Get, IntoIter, Shiperator, UniqueView, UniqueViewMut, View, ViewMut,
     World,
 };
-use std::{cmp::Reverse, collections::BinaryHeap};
+use std::{cmp::Reverse, collections::BinaryHeap, hash::Hasher};
+use wyhash::WyHash;
 
 use crate::{
-    components::{BlocksTile, Coord, FieldOfView, Monster},
-    damage, item,
-    map::Map,
+    animation::{Animation, Animations},
+    components::{
+        AggroRange, BlocksTile, CombatStats, Coord, Disguise, Fearful, FieldOfView, HurtBy,
+        Monster, Name, ProperNoun, Renderable,
+    },
+    damage::{self, DamageQueue},
+    item, magicnum,
+    map::{Map, StepMap},
+    message::{self, Messages},
     player::{self, PlayerId},
+    ui::Options,
+    GameSeed, TurnCount,
 };
+use ruggrogue::PathableMap;
+
+/// Number of turns a monster will pursue the last seen position of the player after losing sight
+/// of them.
+const PURSUIT_TURNS: i32 = 5;
+
+/// Percentage of maximum hit points below which fearful monsters flee from the player.
+const FEAR_PERCENT: i32 = 25;
+
+/// Directions that a confused monster may stumble in.
+const STUMBLE_DIRECTIONS: [(i32, i32); 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
 
 pub struct MonsterTurns(BinaryHeap<(Reverse<i32>, EntityId)>);
 
@@ -41,43 +72,500 @@ pub fn enqueue_monster_turns(
     }
 }
 
+/// Drop a monster's disguise so that it appears as it truly is.
+pub fn reveal_disguise(world: &World, monster: EntityId) {
+    let (mut msgs, mut disguises, mut names, proper_nouns, mut renderables) = world.borrow::<(
+        UniqueViewMut<Messages>,
+        ViewMut<Disguise>,
+        ViewMut<Name>,
+        View<ProperNoun>,
+        ViewMut<Renderable>,
+    )>();
+
+    if let Some(disguise) = disguises.remove(monster) {
+        let proper = proper_nouns.contains(monster);
+        let name = &mut (&mut names).get(monster).0;
+        let old_name = message::the_name(name, proper);
+
+        *name = disguise.name;
+        if let Ok(render) = (&mut renderables).try_get(monster) {
+            render.sym = disguise.sym;
+            render.fg = disguise.fg;
+        }
+
+        msgs.add(format!(
+            "{} is really {}!",
+            message::capitalize(&old_name),
+            message::a_name(&names.get(monster).0, proper)
+        ));
+    }
+}
+
+/// Returns true if a disguised monster is still lying in wait, i.e. it hasn't been hurt and the
+/// player hasn't come right next to it.
+fn disguise_holds(world: &World, monster: EntityId) -> bool {
+    let (player_id, coords, hurt_bys) =
+        world.borrow::<(UniqueView<PlayerId>, View<Coord>, View<HurtBy>)>();
+
+    !hurt_bys.contains(monster) && coords.get(monster).dist(coords.get(player_id.0)) > 1
+}
+
+/// Update whether a fearful monster is fleeing based on how hurt it is, announcing when it starts
+/// to flee.  Returns true if the monster is fleeing.
+fn update_fear(world: &World, monster: EntityId) -> bool {
+    let mut fearfuls = world.borrow::<ViewMut<Fearful>>();
+
+    if let Ok(fearful) = (&mut fearfuls).try_get(monster) {
+        let afraid = {
+            let combat_stats = world.borrow::<View<CombatStats>>();
+            let stats = combat_stats.get(monster);
+            stats.hp * 100 < stats.max_hp * FEAR_PERCENT
+        };
+
+        if afraid && !fearful.fleeing {
+            let name = message::the_name_of(
+                &world.borrow::<View<Name>>(),
+                &world.borrow::<View<ProperNoun>>(),
+                monster,
+            );
+
+            world
+                .borrow::<UniqueViewMut<Messages>>()
+                .add(format!("{} flees in terror!", message::capitalize(&name)));
+        }
+        fearful.fleeing = afraid;
+
+        afraid
+    } else {
+        false
+    }
+}
+
+/// Move a monster a single step, animating it if smooth scrolling is enabled.
+fn move_monster(
+    world: &World,
+    map: &mut Map,
+    monster: EntityId,
+    pos: (i32, i32),
+    step: (i32, i32),
+) {
+    let blocks = world.borrow::<View<BlocksTile>>();
+    let mut coords = world.borrow::<ViewMut<Coord>>();
+    let mut fovs = world.borrow::<ViewMut<FieldOfView>>();
+
+    map.move_entity(monster, pos, step, blocks.contains(monster));
+    (&mut coords).get(monster).0 = step.into();
+    (&mut fovs).get(monster).dirty = true;
+
+    if world.borrow::<UniqueView<Options>>().smooth_scroll {
+        world
+            .borrow::<UniqueViewMut<Animations>>()
+            .0
+            .push(Animation::Move {
+                id: monster,
+                prev_pos: pos.into(),
+                pos: step.into(),
+            });
+    }
+}
+
+/// Move a confused monster a step in a random direction, attacking the player if it stumbles into
+/// them.
+fn stumble_randomly(world: &World, monster: EntityId) {
+    let mut map = world.borrow::<UniqueViewMut<Map>>();
+    let player_id = world.borrow::<UniqueView<PlayerId>>();
+    let (player_pos, pos): ((i32, i32), (i32, i32)) = {
+        let coords = world.borrow::<View<Coord>>();
+        (
+            coords.get(player_id.0).0.into(),
+            coords.get(monster).0.into(),
+        )
+    };
+    let mut rng = {
+        let mut hasher = WyHash::with_seed(magicnum::STUMBLE_RANDOMLY);
+        hasher.write_u64(world.borrow::<UniqueView<GameSeed>>().0);
+        hasher.write_u64(world.borrow::<UniqueView<TurnCount>>().0);
+        hasher.write_i32(pos.0);
+        hasher.write_i32(pos.1);
+        GameRng::seed_from_u64(hasher.finish())
+    };
+
+    if let Some(&(dx, dy)) = STUMBLE_DIRECTIONS.choose(&mut rng) {
+        let step = (pos.0 + dx, pos.1 + dy);
+        let step_map = StepMap {
+            map: &map,
+            strict_diagonals: world.borrow::<UniqueView<Options>>().strict_diagonals,
+        };
+
+        if step_map.is_diagonal_blocked(pos.0, pos.1, dx, dy) {
+            return;
+        }
+
+        if step == player_pos {
+            damage::melee_attack(world, monster, player_id.0);
+        } else if !map.wall_or_oob(step.0, step.1) && !map.is_blocked(step.0, step.1) {
+            move_monster(world, &mut map, monster, pos, step);
+        }
+    }
+}
+
 fn do_turn_for_one_monster(world: &World, monster: EntityId) {
+    // Disguised monsters wait for the player to come close before revealing themselves.
+    if world.borrow::<View<Disguise>>().contains(monster) {
+        if disguise_holds(world, monster) {
+            return;
+        }
+        reveal_disguise(world, monster);
+    }
+
     if item::is_asleep(world, monster) {
         item::handle_sleep_turn(world, monster);
-    } else if player::can_see_player(world, monster) {
-        let mut map = world.borrow::<UniqueViewMut<Map>>();
-        let player_id = world.borrow::<UniqueView<PlayerId>>();
-        let (player_pos, pos): ((i32, i32), (i32, i32)) = {
-            let coords = world.borrow::<View<Coord>>();
-            (
-                coords.get(player_id.0).0.into(),
-                coords.get(monster).0.into(),
-            )
+        return;
+    }
+
+    // Confused monsters stumble around instead of chasing the player.
+    if item::is_confused(world, monster) {
+        stumble_randomly(world, monster);
+        return;
+    }
+
+    let mut map = world.borrow::<UniqueViewMut<Map>>();
+    let player_id = world.borrow::<UniqueView<PlayerId>>();
+    let (player_pos, pos): ((i32, i32), (i32, i32)) = {
+        let coords = world.borrow::<View<Coord>>();
+        (
+            coords.get(player_id.0).0.into(),
+            coords.get(monster).0.into(),
+        )
+    };
+
+    // Chase the player if they're in sight, otherwise pursue where they were last seen.
+    let (sees_player, target_pos) = {
+        let mut monsters = world.borrow::<ViewMut<Monster>>();
+        let monster_data = (&mut monsters).get(monster);
+
+        // Monsters notice the player within their aggro range, but keep chasing them beyond it.
+        let noticed = monster_data.last_seen_player.is_some()
+            || world
+                .borrow::<View<AggroRange>>()
+                .try_get(monster)
+                .map_or(true, |aggro| {
+                    Coord(pos.into()).dist(&Coord(player_pos.into())) <= aggro.0
+                });
+
+        let sees_player = noticed && player::can_see_player(world, monster);
+
+        if sees_player {
+            monster_data.last_seen_player = Some(player_pos);
+            monster_data.pursuit_turns = PURSUIT_TURNS;
+        } else if monster_data.pursuit_turns > 0 {
+            monster_data.pursuit_turns -= 1;
+        } else {
+            monster_data.last_seen_player = None;
+        }
+
+        if monster_data.last_seen_player == Some(pos) {
+            // Arrived at the last seen position, so give up.
+            monster_data.last_seen_player = None;
+            monster_data.pursuit_turns = 0;
+        }
+
+        (sees_player, monster_data.last_seen_player)
+    };
+
+    let strict_diagonals = world.borrow::<UniqueView<Options>>().strict_diagonals;
+
+    // Badly hurt fearful monsters run from a player that they can see, only fighting if cornered.
+    if sees_player && update_fear(world, monster) {
+        let flee_step = {
+            let step_map = StepMap {
+                map: &map,
+                strict_diagonals,
+            };
+            let mut values = vec![i32::MAX; (map.width * map.height) as usize];
+
+            values[(player_pos.1 * map.width + player_pos.0) as usize] = 0;
+            player::flee_step(&step_map, values, pos)
+        };
+
+        if let Some((dx, dy)) = flee_step {
+            move_monster(world, &mut map, monster, pos, (pos.0 + dx, pos.1 + dy));
+            return;
+        }
+    }
+
+    // Fleeing monsters that have lost sight of the player keep away instead of pursuing them.
+    if !sees_player
+        && world
+            .borrow::<View<Fearful>>()
+            .try_get(monster)
+            .is_ok_and(|fearful| fearful.fleeing)
+    {
+        return;
+    }
+
+    if let Some(target_pos) = target_pos {
+        let step_map = StepMap {
+            map: &map,
+            strict_diagonals,
         };
 
-        if let Some(step) = ruggrogue::find_path(&*map, pos, player_pos, 4, true).nth(1) {
+        if let Some(step) = ruggrogue::find_path(&step_map, pos, target_pos, 4, true).nth(1) {
             if step == player_pos {
                 damage::melee_attack(world, monster, player_id.0);
-            } else {
-                let blocks = world.borrow::<View<BlocksTile>>();
-                let mut coords = world.borrow::<ViewMut<Coord>>();
-                let mut fovs = world.borrow::<ViewMut<FieldOfView>>();
-
-                map.move_entity(monster, pos, step, blocks.contains(monster));
-                (&mut coords).get(monster).0 = step.into();
-                (&mut fovs).get(monster).dirty = true;
+            } else if !map.is_blocked(step.0, step.1) {
+                move_monster(world, &mut map, monster, pos, step);
             }
         }
     }
 }
 
+fn player_hp_depleted(world: &World) -> bool {
+    let (damage_queue, player_id, combat_stats) = world.borrow::<(
+        UniqueView<DamageQueue>,
+        UniqueView<PlayerId>,
+        View<CombatStats>,
+    )>();
+
+    combat_stats.get(player_id.0).hp - damage_queue.pending_for(player_id.0) <= 0
+}
+
 pub fn do_monster_turns(world: &World) {
     let (entities, mut monster_turns) =
         world.borrow::<(EntitiesView, UniqueViewMut<MonsterTurns>)>();
 
     while let Some((_, monster)) = monster_turns.0.pop() {
+        // Monsters stop acting once the player has been killed.
+        if player_hp_depleted(world) {
+            monster_turns.0.clear();
+            break;
+        }
+
         if entities.is_alive(monster) {
             do_turn_for_one_monster(world, monster);
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use shipyard::{EntitiesViewMut, ViewMut};
+
+    use super::*;
+    use crate::{
+        components::{Confusion, Player},
+        gamesym::GameSym,
+        map::Tile,
+        spawn, status, vision,
+    };
+
+    /// Build a world with an open map, the player at `player_pos` and a goblin at `goblin_pos`,
+    /// returning the world and the goblin's ID.
+    fn goblin_world(player_pos: (i32, i32), goblin_pos: (i32, i32)) -> (World, EntityId) {
+        let world = World::new();
+
+        world.add_unique(Map::new(20, 20));
+        world.add_unique(Options::default());
+        world.add_unique(Messages::new(10, 5));
+        world.add_unique(Animations::new());
+        world.add_unique(DamageQueue::new());
+        world.add_unique(GameSeed(1));
+        world.add_unique(TurnCount(1));
+
+        let player_id = world.run(
+            |mut entities: EntitiesViewMut,
+             mut combat_stats: ViewMut<CombatStats>,
+             mut coords: ViewMut<Coord>,
+             mut fovs: ViewMut<FieldOfView>,
+             mut players: ViewMut<Player>| {
+                entities.add_entity(
+                    (&mut combat_stats, &mut coords, &mut fovs, &mut players),
+                    (
+                        CombatStats {
+                            max_hp: 100,
+                            hp: 100,
+                            attack: 1.0,
+                            defense: 1.0,
+                        },
+                        Coord(player_pos.into()),
+                        FieldOfView::new(8),
+                        Player {
+                            auto_run: None,
+                            low_hp_warned: false,
+                            damage_previewed: None,
+                        },
+                    ),
+                )
+            },
+        );
+
+        world.add_unique(PlayerId(player_id));
+        world
+            .borrow::<UniqueViewMut<Map>>()
+            .place_entity(player_id, player_pos, true);
+
+        let goblin_id = spawn::spawn_monster_kind(&world, GameSym::Goblin, goblin_pos).unwrap();
+
+        (world, goblin_id)
+    }
+
+    fn dist_to_player(world: &World, monster: EntityId) -> i32 {
+        let (player_id, coords) = world.borrow::<(UniqueView<PlayerId>, View<Coord>)>();
+
+        coords.get(monster).dist(coords.get(player_id.0))
+    }
+
+    #[test]
+    fn hurt_goblin_flees_from_player() {
+        let (world, goblin_id) = goblin_world((5, 10), (8, 10));
+
+        world.run(vision::recalculate_fields_of_view);
+        {
+            let mut combat_stats = world.borrow::<ViewMut<CombatStats>>();
+            let stats = (&mut combat_stats).get(goblin_id);
+            stats.hp = stats.max_hp * FEAR_PERCENT / 100 - 1;
+        }
+
+        let before = dist_to_player(&world, goblin_id);
+        do_turn_for_one_monster(&world, goblin_id);
+
+        assert!(dist_to_player(&world, goblin_id) > before);
+        assert!(world.borrow::<View<Fearful>>().get(goblin_id).fleeing);
+        assert!(world
+            .borrow::<UniqueView<Messages>>()
+            .rev_iter()
+            .any(|(msg, ..)| msg == "The goblin flees in terror!"));
+    }
+
+    #[test]
+    fn fleeing_goblin_does_not_pursue_out_of_sight() {
+        let (world, goblin_id) = goblin_world((5, 5), (15, 5));
+
+        // A wall between the two with a gap at the bottom to path around.
+        {
+            let mut map = world.borrow::<UniqueViewMut<Map>>();
+            for y in 0..16 {
+                map.set_tile(10, y, Tile::Wall);
+            }
+        }
+        world.run(vision::recalculate_fields_of_view);
+        {
+            let mut monsters = world.borrow::<ViewMut<Monster>>();
+            let monster_data = (&mut monsters).get(goblin_id);
+            monster_data.last_seen_player = Some((5, 5));
+            monster_data.pursuit_turns = PURSUIT_TURNS;
+        }
+        (&mut world.borrow::<ViewMut<Fearful>>())
+            .get(goblin_id)
+            .fleeing = true;
+
+        assert!(!player::can_see_player(&world, goblin_id));
+
+        do_turn_for_one_monster(&world, goblin_id);
+
+        let pos: (i32, i32) = world.borrow::<View<Coord>>().get(goblin_id).0.into();
+        assert_eq!(pos, (15, 5));
+    }
+
+    #[test]
+    fn confused_goblin_stumbles_until_confusion_wears_off() {
+        let (world, goblin_id) = goblin_world((5, 10), (10, 10));
+
+        world.borrow::<EntitiesView>().add_component(
+            &mut world.borrow::<ViewMut<Confusion>>(),
+            Confusion { turns: 8 },
+            goblin_id,
+        );
+
+        let mut approached_every_turn = true;
+        for turn in 1..=8 {
+            world.borrow::<UniqueViewMut<TurnCount>>().0 = turn;
+            world.run(vision::recalculate_fields_of_view);
+
+            let before = dist_to_player(&world, goblin_id);
+            do_turn_for_one_monster(&world, goblin_id);
+            // A goblin chasing the player would close in on them over the first few turns.
+            if turn <= 4 {
+                approached_every_turn &= dist_to_player(&world, goblin_id) < before;
+            }
+
+            status::tick_status_effects(&world);
+        }
+
+        assert!(!approached_every_turn);
+        assert!(!item::is_confused(&world, goblin_id));
+    }
+
+    #[test]
+    fn goblin_seeing_the_player_beyond_aggro_range_stays_idle() {
+        let (world, goblin_id) = goblin_world((5, 10), (13, 10));
+
+        world.run(vision::recalculate_fields_of_view);
+        assert!(player::can_see_player(&world, goblin_id));
+        assert!(
+            dist_to_player(&world, goblin_id) > world.borrow::<View<AggroRange>>().get(goblin_id).0
+        );
+
+        do_turn_for_one_monster(&world, goblin_id);
+
+        let pos: (i32, i32) = world.borrow::<View<Coord>>().get(goblin_id).0.into();
+        assert_eq!(pos, (13, 10));
+        assert!(world
+            .borrow::<View<Monster>>()
+            .get(goblin_id)
+            .last_seen_player
+            .is_none());
+    }
+
+    #[test]
+    fn goblin_within_aggro_range_gives_chase() {
+        let (world, goblin_id) = goblin_world((5, 10), (10, 10));
+
+        world.run(vision::recalculate_fields_of_view);
+
+        let before = dist_to_player(&world, goblin_id);
+        do_turn_for_one_monster(&world, goblin_id);
+
+        assert!(dist_to_player(&world, goblin_id) < before);
+        assert_eq!(
+            world
+                .borrow::<View<Monster>>()
+                .get(goblin_id)
+                .last_seen_player,
+            Some((5, 10))
+        );
+    }
+
+    #[test]
+    fn goblin_in_sight_closes_in_until_adjacent() {
+        // A full player, since the goblin gets to attack them.
+        let world = spawn::test_world(20, 20, (5, 10));
+        let goblin_id = spawn::spawn_monster_kind(&world, GameSym::Goblin, (10, 12)).unwrap();
+        let goblin_pos = || -> (i32, i32) { world.borrow::<View<Coord>>().get(goblin_id).0.into() };
+        let steps_to_player = |(x, y): (i32, i32)| (x - 5).abs().max((y - 10).abs());
+
+        for _ in 0..4 {
+            world.run(vision::recalculate_fields_of_view);
+
+            let before = steps_to_player(goblin_pos());
+            do_turn_for_one_monster(&world, goblin_id);
+
+            assert_eq!(steps_to_player(goblin_pos()), before - 1);
+        }
+
+        assert_eq!(steps_to_player(goblin_pos()), 1);
+
+        // Once adjacent it holds its ground to attack rather than trying to step onto the player.
+        let adjacent_pos = goblin_pos();
+
+        world.run(vision::recalculate_fields_of_view);
+        do_turn_for_one_monster(&world, goblin_id);
+
+        assert!(goblin_pos() == adjacent_pos);
+        world.run(damage::apply_damage_queue);
+        assert!(world
+            .borrow::<UniqueView<Messages>>()
+            .rev_iter()
+            .any(|(msg, ..)| msg.starts_with("The goblin")));
+    }
+}
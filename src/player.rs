@@ -4,16 +4,21 @@ use shipyard::{
     EntitiesView, EntityId, Get, IntoIter, Shiperator, UniqueView, UniqueViewMut, View, ViewMut,
     World,
 };
+use std::{cmp::Reverse, collections::BinaryHeap};
 
 use crate::{
-    components::{CombatStats, Coord, FieldOfView, Item, Monster, Name, Player},
+    components::{
+        CombatStats, Coord, Disguise, FieldOfView, Item, Monster, Name, Player, ProperNoun,
+    },
     damage, experience,
     gamekey::{self, GameKey},
     hunger::{self, CanRegenResult},
     item::{self, PickUpHint},
-    map::{self, Map, Tile},
-    message::Messages,
-    spawn, vision,
+    map::{self, Map, StepMap, Tile},
+    message::{self, Messages},
+    monster, spawn,
+    ui::{self, Options},
+    vision,
 };
 use ruggrogue::{util::Position, InputBuffer, InputEvent, KeyMods, PathableMap};
 
@@ -34,13 +39,24 @@ enum AutoRunWallSide {
 enum AutoRunType {
     RestInPlace,
     Corridor,
-    Straight { expect_wall: AutoRunWallSide },
+    Straight {
+        expect_wall: AutoRunWallSide,
+    },
+    /// Head for the nearest unexplored part of the level, stopping if the player is hurt, i.e.
+    /// their hit points drop below `hp`.
+    Explore {
+        hp: i32,
+    },
+    /// Follow the path to a tile that the player picked.
+    Travel,
 }
 
 pub struct AutoRun {
     limit: i32,
     dir: (i32, i32),
     run_type: AutoRunType,
+    /// Positions left to step through when travelling, last step first.
+    path: Vec<(i32, i32)>,
 }
 
 pub enum PlayerInputResult {
@@ -50,10 +66,40 @@ pub enum PlayerInputResult {
     TryDescend,
     ShowOptionsMenu,
     ViewMap,
+    ShowMessageLog,
     ShowPickUpMenu,
     ShowInventory,
     ShowInventoryShortcut(GameKey),
     ShowEquipmentShortcut(GameKey),
+    Undo,
+    /// The player clicked at a pixel position in the window.
+    Click(i32, i32),
+}
+
+/// Something the player wants to do, independent of the key that was pressed to do it.
+pub enum PlayerAction {
+    /// Move or attack in a direction, optionally starting an auto run.
+    Move {
+        dx: i32,
+        dy: i32,
+        run: bool,
+    },
+    /// Wait a turn, optionally resting in place until interrupted.
+    Wait {
+        rest: bool,
+    },
+    Retreat,
+    /// Walk towards the nearest unexplored part of the level until interrupted.
+    Explore,
+    ShowOptionsMenu,
+    ViewMap,
+    ShowMessageLog,
+    Descend,
+    PickUp,
+    OpenInventory,
+    InventoryShortcut(GameKey),
+    EquipmentShortcut(GameKey),
+    Undo,
 }
 
 pub fn player_is_auto_running(player_id: UniqueView<PlayerId>, players: View<Player>) -> bool {
@@ -67,12 +113,14 @@ pub fn player_stop_auto_run(player_id: UniqueView<PlayerId>, mut players: ViewMu
 pub fn player_sees_foes(
     map: UniqueView<Map>,
     player_id: UniqueView<PlayerId>,
+    disguises: View<Disguise>,
     fovs: View<FieldOfView>,
     monsters: View<Monster>,
 ) -> bool {
-    fovs.get(player_id.0)
-        .iter()
-        .any(|(x, y)| map.iter_entities_at(x, y).any(|id| monsters.contains(id)))
+    fovs.get(player_id.0).iter().any(|(x, y)| {
+        map.iter_entities_at(x, y)
+            .any(|id| monsters.contains(id) && !disguises.contains(id))
+    })
 }
 
 pub fn can_see_player(world: &World, who: EntityId) -> bool {
@@ -125,6 +173,10 @@ fn player_check_frontier(
         // Interrupting resting is handled elsewhere.
         return false;
     }
+    if matches!(run_type, AutoRunType::Travel) {
+        // The player already picked where to stop.
+        return false;
+    }
 
     let player_coord = coords.get(player_id.0);
     let (real_x_from_x, real_x_from_y, real_y_from_x, real_y_from_y) =
@@ -487,11 +539,14 @@ fn auto_run_next_step(world: &World) -> Option<(i32, i32)> {
             AutoRunType::RestInPlace => {
                 let player_id = world.borrow::<UniqueView<PlayerId>>();
 
-                // Rest while player can regenerate hit points.
+                // Rest while player can regenerate hit points, up to the limit set in the options,
+                // unless something warned them of danger.
                 if matches!(
                     hunger::can_regen(world, player_id.0),
                     CanRegenResult::CanRegen
-                ) {
+                ) && !world.run(player_rested_enough)
+                    && !world.borrow::<UniqueViewMut<Messages>>().take_warning()
+                {
                     Some((0, 0))
                 } else {
                     None
@@ -525,6 +580,50 @@ fn auto_run_next_step(world: &World) -> Option<(i32, i32)> {
                     None
                 }
             }
+            AutoRunType::Explore { hp } => {
+                let player_hp = world.run(
+                    |player_id: UniqueView<PlayerId>, combat_stats: View<CombatStats>| {
+                        combat_stats.get(player_id.0).hp
+                    },
+                );
+
+                if player_hp < hp {
+                    None
+                } else if let Some(Some(new_dir)) = explore_step(world) {
+                    // Remember the latest facing and hit points for the next step.
+                    world.run(
+                        |player_id: UniqueView<PlayerId>, mut players: ViewMut<Player>| {
+                            let player = (&mut players).get(player_id.0);
+                            if let Some(ar) = &mut player.auto_run {
+                                ar.dir = new_dir;
+                                ar.run_type = AutoRunType::Explore { hp: player_hp };
+                            }
+                        },
+                    );
+                    Some(new_dir)
+                } else {
+                    None
+                }
+            }
+            AutoRunType::Travel => world.run(
+                |player_id: UniqueView<PlayerId>,
+                 map: UniqueView<Map>,
+                 coords: View<Coord>,
+                 mut players: ViewMut<Player>| {
+                    let player_pos = coords.get(player_id.0).0;
+                    let ar = (&mut players).get(player_id.0).auto_run.as_mut()?;
+                    let (x, y) = ar.path.pop()?;
+                    let new_dir = (x - player_pos.x, y - player_pos.y);
+
+                    // Stop if the player has strayed from the path or it's been blocked.
+                    if new_dir.0.abs() > 1 || new_dir.1.abs() > 1 || map.is_blocked(x, y) {
+                        None
+                    } else {
+                        ar.dir = new_dir;
+                        Some(new_dir)
+                    }
+                },
+            ),
         }
     } else {
         None
@@ -542,6 +641,7 @@ pub fn try_move_player(world: &World, dx: i32, dy: i32, start_run: bool) -> Play
     let mut melee_queue = Vec::new();
     let (took_time, moved) = world.run(
         |mut map: UniqueViewMut<Map>,
+         options: UniqueView<Options>,
          combat_stats: View<CombatStats>,
          mut coords: ViewMut<Coord>,
          mut fovs: ViewMut<FieldOfView>,
@@ -553,7 +653,13 @@ pub fn try_move_player(world: &World, dx: i32, dy: i32, start_run: bool) -> Play
                 let new_x = coord.0.x + dx;
                 let new_y = coord.0.y + dy;
 
-                if new_x >= 0 && new_y >= 0 && new_x < map.width && new_y < map.height {
+                if new_x >= 0
+                    && new_y >= 0
+                    && new_x < map.width
+                    && new_y < map.height
+                    && !(options.strict_diagonals
+                        && map.squeezes_between_walls(coord.0.x, coord.0.y, dx, dy))
+                {
                     let melee_target = map
                         .iter_entities_at(new_x, new_y)
                         .find(|e| combat_stats.contains(*e));
@@ -575,6 +681,34 @@ pub fn try_move_player(world: &World, dx: i32, dy: i32, start_run: bool) -> Play
         },
     );
 
+    if let Some(&(attacker, defender)) = melee_queue.first() {
+        // Bumping into a disguised monster gives it away before any damage preview names it.
+        monster::reveal_disguise(world, defender);
+
+        if world.run(
+            |options: UniqueView<Options>, mut players: ViewMut<Player>| {
+                let player = (&mut players).get(attacker);
+                let first_bump = player.damage_previewed != Some(defender);
+
+                player.damage_previewed = Some(defender);
+                options.show_damage_preview && first_bump
+            },
+        ) {
+            let (min_damage, max_damage) = damage::melee_damage_range(world, attacker, defender);
+            let def_name = message::the_name_of(
+                &world.borrow::<View<Name>>(),
+                &world.borrow::<View<ProperNoun>>(),
+                defender,
+            );
+
+            world.borrow::<UniqueViewMut<Messages>>().add(format!(
+                "Attacking {} would deal {}-{} damage.  Bump again to attack.",
+                def_name, min_damage, max_damage
+            ));
+            return PlayerInputResult::NoResult;
+        }
+    }
+
     for (attacker, defender) in melee_queue {
         damage::melee_attack(world, attacker, defender);
     }
@@ -588,6 +722,7 @@ pub fn try_move_player(world: &World, dx: i32, dy: i32, start_run: bool) -> Play
                         limit: 200,
                         dir: (dx, dy),
                         run_type: AutoRunType::Corridor,
+                        path: Vec::new(),
                     });
                 },
             );
@@ -599,6 +734,7 @@ pub fn try_move_player(world: &World, dx: i32, dy: i32, start_run: bool) -> Play
                         limit: 200,
                         dir: (dx, dy),
                         run_type: AutoRunType::Straight { expect_wall },
+                        path: Vec::new(),
                     });
                 },
             );
@@ -612,7 +748,359 @@ pub fn try_move_player(world: &World, dx: i32, dy: i32, start_run: bool) -> Play
     }
 }
 
+/// Returns true if the player has rested up to the hit point percentage set in the options.
+fn player_rested_enough(
+    options: UniqueView<Options>,
+    player_id: UniqueView<PlayerId>,
+    combat_stats: View<CombatStats>,
+) -> bool {
+    let stats = combat_stats.get(player_id.0);
+
+    stats.hp * 100 >= stats.max_hp * options.rest_until_percent as i32
+}
+
+/// Offsets to every tile surrounding a position.
+const DIRECTIONS: [(i32, i32); 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+
+/// Cost of a single step in the Dijkstra maps used to retreat, leaving room for fractions.
+const DIJKSTRA_STEP_COST: i32 = 10;
+
+/// Multiplier in tenths applied to distances from monsters to turn them into a map for fleeing.
+/// Going past -1 makes fleeing into open space better than fleeing into a nearby dead end.
+const FLEE_MULTIPLIER_TENTHS: i32 = -12;
+
+/// Fill out a Dijkstra map of the whole level, where every tile ends up with the lowest of its
+/// starting value and the value of any neighbor plus a step.  Walls are left at `i32::MAX`.
+fn dijkstra_map(step_map: &StepMap, values: Vec<i32>) -> Vec<i32> {
+    dijkstra_map_within(step_map, values, |x, y| !step_map.map.wall_or_oob(x, y))
+}
+
+/// Like [dijkstra_map], but only spreading across tiles that the `passable` check allows.
+fn dijkstra_map_within<F>(step_map: &StepMap, mut values: Vec<i32>, passable: F) -> Vec<i32>
+where
+    F: Fn(i32, i32) -> bool,
+{
+    let map = step_map.map;
+    let mut frontier = values
+        .iter()
+        .enumerate()
+        .filter(|(_, v)| **v < i32::MAX)
+        .map(|(i, v)| Reverse((*v, i)))
+        .collect::<BinaryHeap<_>>();
+
+    while let Some(Reverse((value, i))) = frontier.pop() {
+        if value > values[i] {
+            continue;
+        }
+
+        let x = i as i32 % map.width;
+        let y = i as i32 / map.width;
+
+        for (dx, dy) in DIRECTIONS.iter() {
+            let (nx, ny) = (x + dx, y + dy);
+
+            if passable(nx, ny) && !step_map.is_diagonal_blocked(x, y, *dx, *dy) {
+                let ni = (ny * map.width + nx) as usize;
+
+                if value + DIJKSTRA_STEP_COST < values[ni] {
+                    values[ni] = value + DIJKSTRA_STEP_COST;
+                    frontier.push(Reverse((values[ni], ni)));
+                }
+            }
+        }
+    }
+
+    values
+}
+
+/// Find the safest step from `pos` away from every tile given a value of zero in `values`,
+/// returning `None` if there's no step that's any safer.
+pub fn flee_step(step_map: &StepMap, values: Vec<i32>, pos: (i32, i32)) -> Option<(i32, i32)> {
+    let map = step_map.map;
+    let index = |x: i32, y: i32| (y * map.width + x) as usize;
+
+    // Distances from the threats, inverted and settled again into a map for fleeing.
+    let flee_values = dijkstra_map(step_map, values)
+        .into_iter()
+        .map(|v| {
+            if v < i32::MAX {
+                v * FLEE_MULTIPLIER_TENTHS / 10
+            } else {
+                v
+            }
+        })
+        .collect();
+    let flee_values = dijkstra_map(step_map, flee_values);
+
+    DIRECTIONS
+        .iter()
+        .map(|(dx, dy)| (*dx, *dy, pos.0 + dx, pos.1 + dy))
+        .filter(|(dx, dy, x, y)| {
+            !map.wall_or_oob(*x, *y)
+                && !map.is_blocked(*x, *y)
+                && !step_map.is_diagonal_blocked(pos.0, pos.1, *dx, *dy)
+        })
+        .map(|(dx, dy, x, y)| (flee_values[index(x, y)], dx, dy))
+        .filter(|(v, _, _)| *v < flee_values[index(pos.0, pos.1)])
+        .min()
+        .map(|(_, dx, dy)| (dx, dy))
+}
+
+/// Step the player away from every monster in sight along the safest path, as an assist.
+fn retreat_player(world: &World) -> PlayerInputResult {
+    if !world.borrow::<UniqueView<Options>>().assist_retreat {
+        world
+            .borrow::<UniqueViewMut<Messages>>()
+            .add("Retreating is disabled in the options.".into());
+        return PlayerInputResult::NoResult;
+    }
+
+    let step = {
+        let (map, options, player_id, coords, disguises, fovs, monsters) = world.borrow::<(
+            UniqueView<Map>,
+            UniqueView<Options>,
+            UniqueView<PlayerId>,
+            View<Coord>,
+            View<Disguise>,
+            View<FieldOfView>,
+            View<Monster>,
+        )>();
+        let player_pos = coords.get(player_id.0).0;
+        let index = |x: i32, y: i32| (y * map.width + x) as usize;
+        let mut values = vec![i32::MAX; (map.width * map.height) as usize];
+        let mut foes_seen = false;
+
+        for (x, y) in fovs.get(player_id.0).iter() {
+            if map
+                .iter_entities_at(x, y)
+                .any(|id| monsters.contains(id) && !disguises.contains(id))
+            {
+                values[index(x, y)] = 0;
+                foes_seen = true;
+            }
+        }
+
+        if !foes_seen {
+            None
+        } else {
+            let step_map = StepMap {
+                map: &map,
+                strict_diagonals: options.strict_diagonals,
+            };
+
+            Some(flee_step(&step_map, values, player_pos.into()))
+        }
+    };
+
+    match step {
+        None => {
+            world
+                .borrow::<UniqueViewMut<Messages>>()
+                .add("There is nothing in sight to retreat from.".into());
+            PlayerInputResult::NoResult
+        }
+        Some(None) => {
+            world
+                .borrow::<UniqueViewMut<Messages>>()
+                .add("There is nowhere safer to retreat to.".into());
+            PlayerInputResult::NoResult
+        }
+        Some(Some((dx, dy))) => try_move_player(world, dx, dy, false),
+    }
+}
+
+/// Find the first step along the shortest path to the nearest explored tile next to an unexplored
+/// one, only walking across tiles that the player has seen.
+///
+/// Returns `None` if there's no such tile that can be reached, `Some(None)` if something is
+/// standing in the way, or `Some(Some((dx, dy)))` with the step to take.
+fn explore_step(world: &World) -> Option<Option<(i32, i32)>> {
+    let (map, options, player_id, coords) = world.borrow::<(
+        UniqueView<Map>,
+        UniqueView<Options>,
+        UniqueView<PlayerId>,
+        View<Coord>,
+    )>();
+    let player_pos = coords.get(player_id.0).0;
+    let step_map = StepMap {
+        map: &map,
+        strict_diagonals: options.strict_diagonals,
+    };
+    let index = |x: i32, y: i32| (y * map.width + x) as usize;
+    let known_floor = |x: i32, y: i32| !map.wall_or_oob(x, y) && map.seen.get_bit(x, y);
+    let mut values = vec![i32::MAX; (map.width * map.height) as usize];
+
+    for y in 0..map.height {
+        for x in 0..map.width {
+            if known_floor(x, y)
+                && DIRECTIONS.iter().any(|(dx, dy)| {
+                    let (nx, ny) = (x + dx, y + dy);
+                    nx >= 0
+                        && ny >= 0
+                        && nx < map.width
+                        && ny < map.height
+                        && !map.seen.get_bit(nx, ny)
+                })
+            {
+                values[index(x, y)] = 0;
+            }
+        }
+    }
+
+    let values = dijkstra_map_within(&step_map, values, known_floor);
+    let player_value = values[index(player_pos.x, player_pos.y)];
+
+    if player_value == 0 || player_value == i32::MAX {
+        None
+    } else {
+        Some(
+            DIRECTIONS
+                .iter()
+                .map(|(dx, dy)| (*dx, *dy, player_pos.x + dx, player_pos.y + dy))
+                .filter(|(dx, dy, x, y)| {
+                    known_floor(*x, *y)
+                        && !map.is_blocked(*x, *y)
+                        && !step_map.is_diagonal_blocked(player_pos.x, player_pos.y, *dx, *dy)
+                })
+                .map(|(dx, dy, x, y)| (values[index(x, y)], dx, dy))
+                .filter(|(v, _, _)| *v < player_value)
+                .min()
+                .map(|(_, dx, dy)| (dx, dy)),
+        )
+    }
+}
+
+/// A map that only lets paths through tiles that the player has seen.
+struct SeenMap<'a>(StepMap<'a>);
+
+impl ruggrogue::BoundedMap for SeenMap<'_> {
+    fn bounds(&self) -> (i32, i32, i32, i32) {
+        self.0.bounds()
+    }
+}
+
+impl PathableMap for SeenMap<'_> {
+    fn is_blocked(&self, x: i32, y: i32) -> bool {
+        !self.0.map.seen.get_bit(x, y) || self.0.is_blocked(x, y)
+    }
+
+    fn is_diagonal_blocked(&self, x: i32, y: i32, dx: i32, dy: i32) -> bool {
+        self.0.is_diagonal_blocked(x, y, dx, dy)
+    }
+}
+
+/// Start travelling to a tile that the player has seen, one step at a time.
+pub fn travel_player(world: &World, dest: Position) -> PlayerInputResult {
+    if world.run(player_sees_foes) {
+        world
+            .borrow::<UniqueViewMut<Messages>>()
+            .add("You cannot travel while foes are near.".into());
+        return PlayerInputResult::NoResult;
+    }
+
+    let (mut path, player_pos) = {
+        let (map, options, player_id, coords) = world.borrow::<(
+            UniqueView<Map>,
+            UniqueView<Options>,
+            UniqueView<PlayerId>,
+            View<Coord>,
+        )>();
+        let player_pos = coords.get(player_id.0).0;
+
+        if dest == player_pos
+            || map.wall_or_oob(dest.x, dest.y)
+            || !map.seen.get_bit(dest.x, dest.y)
+        {
+            return PlayerInputResult::NoResult;
+        }
+
+        let seen_map = SeenMap(StepMap {
+            map: &map,
+            strict_diagonals: options.strict_diagonals,
+        });
+        let mut path = ruggrogue::find_path(&seen_map, player_pos.into(), dest.into(), 0, false)
+            .skip(1)
+            .collect::<Vec<_>>();
+
+        path.reverse();
+        (path, player_pos)
+    };
+
+    if let Some((x, y)) = path.pop() {
+        let (dx, dy) = (x - player_pos.x, y - player_pos.y);
+
+        world.run(
+            |player_id: UniqueView<PlayerId>, mut players: ViewMut<Player>| {
+                (&mut players).get(player_id.0).auto_run = Some(AutoRun {
+                    limit: path.len() as i32 + 1,
+                    dir: (dx, dy),
+                    run_type: AutoRunType::Travel,
+                    path,
+                });
+            },
+        );
+        try_move_player(world, dx, dy, false)
+    } else {
+        world
+            .borrow::<UniqueViewMut<Messages>>()
+            .add("You don't know a way there.".into());
+        PlayerInputResult::NoResult
+    }
+}
+
+/// Start walking towards the nearest unexplored part of the level.
+fn explore_player(world: &World) -> PlayerInputResult {
+    if world.run(player_sees_foes) {
+        world
+            .borrow::<UniqueViewMut<Messages>>()
+            .add("You cannot explore while foes are near.".into());
+        return PlayerInputResult::NoResult;
+    }
+
+    match explore_step(world) {
+        None => {
+            world
+                .borrow::<UniqueViewMut<Messages>>()
+                .add("There is nothing left to explore here.".into());
+            PlayerInputResult::NoResult
+        }
+        Some(None) => {
+            world
+                .borrow::<UniqueViewMut<Messages>>()
+                .add("Something is in the way.".into());
+            PlayerInputResult::NoResult
+        }
+        Some(Some((dx, dy))) => {
+            world.run(
+                |player_id: UniqueView<PlayerId>,
+                 combat_stats: View<CombatStats>,
+                 mut players: ViewMut<Player>| {
+                    (&mut players).get(player_id.0).auto_run = Some(AutoRun {
+                        limit: 400,
+                        dir: (dx, dy),
+                        run_type: AutoRunType::Explore {
+                            hp: combat_stats.get(player_id.0).hp,
+                        },
+                        path: Vec::new(),
+                    });
+                },
+            );
+            try_move_player(world, dx, dy, false)
+        }
+    }
+}
+
 fn wait_player(world: &World, rest_in_place: bool) -> PlayerInputResult {
+    let rested_enough = world.run(player_rested_enough);
     let foes_seen = world.run(player_sees_foes);
     let (player_id, mut players) = world.borrow::<(UniqueView<PlayerId>, ViewMut<Player>)>();
     let player_can_regen = hunger::can_regen(world, player_id.0);
@@ -630,15 +1118,21 @@ fn wait_player(world: &World, rest_in_place: bool) -> PlayerInputResult {
                 CanRegenResult::TooHungry => msgs.add("You are too hungry to rest.".into()),
             }
             return PlayerInputResult::NoResult;
+        } else if rested_enough {
+            msgs.add("You are already rested enough.".into());
+            return PlayerInputResult::NoResult;
         }
 
         // Rest in place if requested.
         if matches!(player_can_regen, CanRegenResult::CanRegen) {
+            // Only warnings from here on interrupt resting.
+            msgs.take_warning();
             msgs.add("You tend to your wounds.".into());
             (&mut players).get(player_id.0).auto_run = Some(AutoRun {
                 limit: 400,
                 dir: (0, 0),
                 run_type: AutoRunType::RestInPlace,
+                path: Vec::new(),
             });
         }
     }
@@ -695,7 +1189,7 @@ pub fn player_do_descend(world: &World) {
         map.depth += 1;
     });
     if let Some(victory_pos) = world.run(map::generate_rooms_and_corridors) {
-        spawn::spawn_present(world, victory_pos);
+        spawn::spawn_boss(world, victory_pos);
     }
     world.run(map::place_player_in_first_room);
 
@@ -730,13 +1224,15 @@ pub fn player_pick_up_item(world: &World, item_id: EntityId) {
 
     item::remove_item_from_map(world, item_id);
     item::add_item_to_inventory(world, player_id, item_id);
-    world.run(|mut msgs: UniqueViewMut<Messages>, names: View<Name>| {
-        msgs.add(format!(
-            "{} picks up {}.",
-            names.get(player_id).0,
-            names.get(item_id).0
-        ));
-    });
+    world.run(
+        |mut msgs: UniqueViewMut<Messages>, names: View<Name>, proper_nouns: View<ProperNoun>| {
+            msgs.add(format!(
+                "{} picks up {}.",
+                message::capitalize(&message::the_name_of(&names, &proper_nouns, player_id)),
+                message::a_name_of(&names, &proper_nouns, item_id)
+            ));
+        },
+    );
 }
 
 pub fn player_drop_item(world: &World, item_id: EntityId) {
@@ -745,13 +1241,15 @@ pub fn player_drop_item(world: &World, item_id: EntityId) {
 
     item::remove_item_from_inventory(world, player_id, item_id);
     item::add_item_to_map(world, item_id, player_pos);
-    world.run(|mut msgs: UniqueViewMut<Messages>, names: View<Name>| {
-        msgs.add(format!(
-            "{} drops {}.",
-            names.get(player_id).0,
-            names.get(item_id).0
-        ));
-    });
+    world.run(
+        |mut msgs: UniqueViewMut<Messages>, names: View<Name>, proper_nouns: View<ProperNoun>| {
+            msgs.add(format!(
+                "{} drops {}.",
+                message::capitalize(&message::the_name_of(&names, &proper_nouns, player_id)),
+                message::the_name_of(&names, &proper_nouns, item_id)
+            ));
+        },
+    );
 }
 
 /// Describe contents of the tile the player is on.
@@ -797,7 +1295,7 @@ pub fn player_input(world: &World, inputs: &mut InputBuffer) -> PlayerInputResul
             PlayerInputResult::AppQuit
         } else if let Some(InputEvent::Press(keycode)) = inputs.get_input() {
             let shift = inputs.get_mods(KeyMods::SHIFT);
-            let key = gamekey::from_keycode(keycode, shift);
+            let key = gamekey::from_keycode(world, keycode, shift);
 
             if !matches!(key, GameKey::Unmapped) {
                 world.borrow::<UniqueViewMut<Messages>>().reset_highlight();
@@ -820,8 +1318,10 @@ pub fn player_input(world: &World, inputs: &mut InputBuffer) -> PlayerInputResul
         if matches!(inputs.get_input(), Some(InputEvent::AppQuit)) {
             world.run(player_stop_auto_run);
             PlayerInputResult::AppQuit
-        } else if matches!(inputs.get_input(), Some(InputEvent::Press(_)))
-            || world.run(player_check_frontier)
+        } else if matches!(
+            inputs.get_input(),
+            Some(InputEvent::Press(_)) | Some(InputEvent::Click(..))
+        ) || world.run(player_check_frontier)
             || world.run(player_sees_foes)
         {
             world.run(player_stop_auto_run);
@@ -845,9 +1345,9 @@ pub fn player_input(world: &World, inputs: &mut InputBuffer) -> PlayerInputResul
             } else if let Some((dx, dy)) = auto_run_next_step(world) {
                 // Do one step of auto running.
                 if dx == 0 && dy == 0 {
-                    wait_player(world, false)
+                    apply_action(world, PlayerAction::Wait { rest: false })
                 } else {
-                    try_move_player(world, dx, dy, false)
+                    apply_action(world, PlayerAction::Move { dx, dy, run: false })
                 }
             } else {
                 world.run(player_stop_auto_run);
@@ -856,40 +1356,103 @@ pub fn player_input(world: &World, inputs: &mut InputBuffer) -> PlayerInputResul
         }
     } else if let Some(InputEvent::AppQuit) = inputs.get_input() {
         PlayerInputResult::AppQuit
+    } else if let Some(InputEvent::Click(x, y)) = inputs.get_input() {
+        world.borrow::<UniqueViewMut<Messages>>().reset_highlight();
+        PlayerInputResult::Click(x, y)
     } else if let Some(InputEvent::Press(keycode)) = inputs.get_input() {
         let shift = inputs.get_mods(KeyMods::SHIFT);
-        let key = gamekey::from_keycode(keycode, shift);
+        let key = gamekey::from_keycode(world, keycode, shift);
 
         if !matches!(key, GameKey::Unmapped) {
             world.borrow::<UniqueViewMut<Messages>>().reset_highlight();
         }
 
-        match key {
-            GameKey::Left => try_move_player(world, -1, 0, shift),
-            GameKey::Down => try_move_player(world, 0, 1, shift),
-            GameKey::Up => try_move_player(world, 0, -1, shift),
-            GameKey::Right => try_move_player(world, 1, 0, shift),
-            GameKey::UpLeft => try_move_player(world, -1, -1, shift),
-            GameKey::UpRight => try_move_player(world, 1, -1, shift),
-            GameKey::DownLeft => try_move_player(world, -1, 1, shift),
-            GameKey::DownRight => try_move_player(world, 1, 1, shift),
-            GameKey::Wait => wait_player(world, shift),
-            GameKey::Cancel => PlayerInputResult::ShowOptionsMenu,
-            GameKey::ViewMap => PlayerInputResult::ViewMap,
-            GameKey::Descend | GameKey::Confirm => PlayerInputResult::TryDescend,
-            GameKey::PickUp => PlayerInputResult::ShowPickUpMenu,
-            GameKey::Inventory => PlayerInputResult::ShowInventory,
-            key @ GameKey::UseItem | key @ GameKey::EquipItem | key @ GameKey::DropItem => {
-                PlayerInputResult::ShowInventoryShortcut(key)
-            }
-            key @ GameKey::RemoveItem => PlayerInputResult::ShowEquipmentShortcut(key),
-            _ => PlayerInputResult::NoResult,
-        }
+        translate_input(key, shift).map_or(PlayerInputResult::NoResult, |action| {
+            apply_action(world, action)
+        })
     } else {
         PlayerInputResult::NoResult
     }
 }
 
+/// Translate a key pressed by the player into the action it represents, if any.
+pub fn translate_input(key: GameKey, shift: bool) -> Option<PlayerAction> {
+    let move_action = |dx, dy| Some(PlayerAction::Move { dx, dy, run: shift });
+
+    match key {
+        GameKey::Left => move_action(-1, 0),
+        GameKey::Down => move_action(0, 1),
+        GameKey::Up => move_action(0, -1),
+        GameKey::Right => move_action(1, 0),
+        GameKey::UpLeft => move_action(-1, -1),
+        GameKey::UpRight => move_action(1, -1),
+        GameKey::DownLeft => move_action(-1, 1),
+        GameKey::DownRight => move_action(1, 1),
+        GameKey::Wait => Some(PlayerAction::Wait { rest: shift }),
+        GameKey::Retreat => Some(PlayerAction::Retreat),
+        GameKey::AutoExplore => Some(PlayerAction::Explore),
+        GameKey::Cancel => Some(PlayerAction::ShowOptionsMenu),
+        GameKey::ViewMap => Some(PlayerAction::ViewMap),
+        GameKey::MessageLog => Some(PlayerAction::ShowMessageLog),
+        GameKey::Descend | GameKey::Confirm => Some(PlayerAction::Descend),
+        GameKey::PickUp => Some(PlayerAction::PickUp),
+        GameKey::Inventory => Some(PlayerAction::OpenInventory),
+        key @ GameKey::UseItem
+        | key @ GameKey::EquipItem
+        | key @ GameKey::ThrowItem
+        | key @ GameKey::DropItem => Some(PlayerAction::InventoryShortcut(key)),
+        key @ GameKey::RemoveItem => Some(PlayerAction::EquipmentShortcut(key)),
+        GameKey::Undo => Some(PlayerAction::Undo),
+        _ => None,
+    }
+}
+
+/// Carry out an action for the player.
+pub fn apply_action(world: &World, action: PlayerAction) -> PlayerInputResult {
+    match action {
+        PlayerAction::Move { dx, dy, run } => try_move_player(world, dx, dy, run),
+        PlayerAction::Wait { rest } => wait_player(world, rest),
+        PlayerAction::Retreat => retreat_player(world),
+        PlayerAction::Explore => explore_player(world),
+        PlayerAction::ShowOptionsMenu => PlayerInputResult::ShowOptionsMenu,
+        PlayerAction::ViewMap => PlayerInputResult::ViewMap,
+        PlayerAction::ShowMessageLog => PlayerInputResult::ShowMessageLog,
+        PlayerAction::Descend => PlayerInputResult::TryDescend,
+        PlayerAction::PickUp => PlayerInputResult::ShowPickUpMenu,
+        PlayerAction::OpenInventory => PlayerInputResult::ShowInventory,
+        PlayerAction::InventoryShortcut(key) => PlayerInputResult::ShowInventoryShortcut(key),
+        PlayerAction::EquipmentShortcut(key) => PlayerInputResult::ShowEquipmentShortcut(key),
+        PlayerAction::Undo => PlayerInputResult::Undo,
+    }
+}
+
+/// Warn the player once when their hit points drop to a critical level, returning true if a new
+/// warning was given.  Recovering past the critical level allows the warning to be given again.
+pub fn player_warn_low_hp(
+    mut msgs: UniqueViewMut<Messages>,
+    player_id: UniqueView<PlayerId>,
+    combat_stats: View<CombatStats>,
+    mut players: ViewMut<Player>,
+) -> bool {
+    let stats = combat_stats.get(player_id.0);
+    let player = (&mut players).get(player_id.0);
+    let critical = stats.hp * 100 <= stats.max_hp * ui::HP_CRITICAL_PERCENT;
+
+    if critical && !player.low_hp_warned {
+        player.low_hp_warned = true;
+        msgs.add_colored(
+            "*** Your health is critically low! ***".into(),
+            message::WARNING_COLOR,
+        );
+        true
+    } else {
+        if !critical {
+            player.low_hp_warned = false;
+        }
+        false
+    }
+}
+
 pub fn player_is_alive(player_alive: UniqueView<PlayerAlive>) -> bool {
     player_alive.0
 }
@@ -902,3 +1465,197 @@ pub fn player_is_dead_input(inputs: &mut InputBuffer) -> bool {
         Some(InputEvent::Press(Keycode::Space)) | Some(InputEvent::AppQuit)
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gamesym::GameSym;
+
+    fn player_pos(world: &World) -> (i32, i32) {
+        let player_id = world.borrow::<UniqueView<PlayerId>>().0;
+
+        world.borrow::<View<Coord>>().get(player_id).0.into()
+    }
+
+    /// Chebyshev distance, i.e. the number of steps between two positions.
+    fn steps_between((x1, y1): (i32, i32), (x2, y2): (i32, i32)) -> i32 {
+        (x2 - x1).abs().max((y2 - y1).abs())
+    }
+
+    #[test]
+    fn keys_translate_to_actions() {
+        assert!(matches!(
+            translate_input(GameKey::UpLeft, false),
+            Some(PlayerAction::Move {
+                dx: -1,
+                dy: -1,
+                run: false
+            })
+        ));
+        assert!(matches!(
+            translate_input(GameKey::Right, true),
+            Some(PlayerAction::Move {
+                dx: 1,
+                dy: 0,
+                run: true
+            })
+        ));
+        assert!(matches!(
+            translate_input(GameKey::Wait, false),
+            Some(PlayerAction::Wait { rest: false })
+        ));
+        assert!(matches!(
+            translate_input(GameKey::Wait, true),
+            Some(PlayerAction::Wait { rest: true })
+        ));
+        assert!(matches!(
+            translate_input(GameKey::Confirm, false),
+            Some(PlayerAction::Descend)
+        ));
+        assert!(matches!(
+            translate_input(GameKey::ThrowItem, false),
+            Some(PlayerAction::InventoryShortcut(GameKey::ThrowItem))
+        ));
+        assert!(matches!(
+            translate_input(GameKey::RemoveItem, false),
+            Some(PlayerAction::EquipmentShortcut(GameKey::RemoveItem))
+        ));
+        assert!(matches!(
+            translate_input(GameKey::Cancel, false),
+            Some(PlayerAction::ShowOptionsMenu)
+        ));
+        assert!(translate_input(GameKey::PageUp, false).is_none());
+        assert!(translate_input(GameKey::Unmapped, true).is_none());
+    }
+
+    /// Fill a map with walls, then carve out floor tiles at the given positions.
+    fn carve_map(world: &World, floors: impl Iterator<Item = (i32, i32)>) {
+        let mut map = world.borrow::<UniqueViewMut<Map>>();
+
+        for y in 0..map.height {
+            for x in 0..map.width {
+                map.set_tile(x, y, Tile::Wall);
+            }
+        }
+        for (x, y) in floors {
+            map.set_tile(x, y, Tile::Floor);
+        }
+    }
+
+    #[test]
+    fn exploring_follows_a_corridor_to_its_dead_end() {
+        let world = spawn::test_world(10, 16, (1, 2));
+        let corridor = || (1..=6).map(|x| (x, 2)).chain((3..=13).map(|y| (6, y)));
+
+        carve_map(&world, corridor());
+
+        for _ in 0..30 {
+            world.run(vision::recalculate_fields_of_view);
+            match explore_step(&world) {
+                Some(Some((dx, dy))) => {
+                    apply_action(&world, PlayerAction::Move { dx, dy, run: false });
+                }
+                Some(None) => panic!("nothing should be in the way"),
+                None => break,
+            }
+        }
+
+        let (x, y) = player_pos(&world);
+        let map = world.borrow::<UniqueView<Map>>();
+
+        assert_eq!(x, 6);
+        assert!(y > 2);
+        assert!(corridor().all(|(x, y)| map.seen.get_bit(x, y)));
+        assert!(map.seen.get_bit(6, 14));
+        drop(map);
+
+        assert!(matches!(
+            explore_player(&world),
+            PlayerInputResult::NoResult
+        ));
+        assert_eq!(
+            world
+                .borrow::<UniqueView<Messages>>()
+                .rev_iter()
+                .next()
+                .map(|(msg, ..)| msg),
+            Some("There is nothing left to explore here.")
+        );
+        assert_eq!(player_pos(&world), (x, y));
+    }
+
+    #[test]
+    fn travelling_takes_the_shortest_way_around_a_wall() {
+        let world = spawn::test_world(10, 7, (2, 2));
+        let wall = |(x, y): (i32, i32)| x == 5 && y <= 3;
+
+        carve_map(
+            &world,
+            (0..7)
+                .flat_map(|y| (0..10).map(move |x| (x, y)))
+                .filter(|&pos| !wall(pos)),
+        );
+        {
+            let mut map = world.borrow::<UniqueViewMut<Map>>();
+
+            for y in 0..7 {
+                for x in 0..10 {
+                    map.seen.set_bit(x, y, true);
+                }
+            }
+        }
+        world.run(vision::recalculate_fields_of_view);
+
+        let mut steps = vec![];
+
+        assert!(matches!(
+            travel_player(&world, Position { x: 8, y: 2 }),
+            PlayerInputResult::TurnDone
+        ));
+        steps.push(player_pos(&world));
+        while world.run(player_is_auto_running) && steps.len() < 20 {
+            world.run(vision::recalculate_fields_of_view);
+            player_input(&world, &mut InputBuffer::new());
+            steps.push(player_pos(&world));
+        }
+
+        assert_eq!(*steps.last().unwrap(), (8, 2));
+        // Going below the wall takes as many steps as going straight there would on open ground.
+        assert_eq!(steps.iter().filter(|&&pos| pos != (8, 2)).count(), 5);
+        assert!(steps.iter().all(|&pos| !wall(pos)));
+        assert!(steps.iter().any(|&(x, y)| x == 5 && y > 3));
+        assert!(steps.windows(2).all(|w| steps_between(w[0], w[1]) <= 1));
+    }
+
+    #[test]
+    fn retreating_steps_away_from_the_nearest_monster() {
+        let world = spawn::test_world(20, 20, (10, 10));
+        let goblin_pos = (12, 11);
+
+        spawn::spawn_monster_kind(&world, GameSym::Goblin, goblin_pos);
+        world.borrow::<UniqueViewMut<Options>>().assist_retreat = true;
+        world.run(vision::recalculate_fields_of_view);
+
+        let before = steps_between(player_pos(&world), goblin_pos);
+
+        assert!(matches!(
+            retreat_player(&world),
+            PlayerInputResult::TurnDone
+        ));
+        assert!(steps_between(player_pos(&world), goblin_pos) > before);
+    }
+
+    #[test]
+    fn retreating_needs_a_monster_in_sight() {
+        let world = spawn::test_world(20, 20, (10, 10));
+
+        world.borrow::<UniqueViewMut<Options>>().assist_retreat = true;
+        world.run(vision::recalculate_fields_of_view);
+
+        assert!(matches!(
+            retreat_player(&world),
+            PlayerInputResult::NoResult
+        ));
+        assert_eq!(player_pos(&world), (10, 10));
+    }
+}
@@ -1,44 +1,167 @@
-use shipyard::{Get, IntoIter, UniqueView, View, World};
+use shipyard::{EntityId, Get, IntoIter, Shiperator, UniqueView, View, World};
 
 use crate::{
     chunked::ChunkedMapGrid,
     components::{Coord, FieldOfView, RenderOnFloor, RenderOnMap, Renderable},
     gamesym::GameSym,
+    map::Map,
     player::PlayerId,
+    ui::Options,
 };
-use ruggrogue::TileGrid;
+use ruggrogue::{util::Color, TileGrid};
+
+/// Background color of map tiles that the player has left notes on.
+const NOTE_BG: Color = Color {
+    r: 0,
+    g: 64,
+    b: 128,
+};
+
+/// Foreground color to draw a renderable with, taking any color override in the options into account.
+pub fn renderable_fg(options: &Options, render: &Renderable) -> Color {
+    options
+        .color_overrides
+        .get(&render.sym)
+        .copied()
+        .unwrap_or(render.fg)
+}
 
 pub fn draw_renderables(
     chunked_map_grid: &ChunkedMapGrid,
     world: &World,
     grid: &mut TileGrid<GameSym>,
 ) {
-    let (player_id, coords, fovs, render_on_floors, render_on_maps, renderables) = world.borrow::<(
-        UniqueView<PlayerId>,
-        View<Coord>,
-        View<FieldOfView>,
-        View<RenderOnFloor>,
-        View<RenderOnMap>,
-        View<Renderable>,
-    )>();
+    draw_renderables_except(chunked_map_grid, world, grid, &[]);
+}
+
+/// Draw renderable entities onto the map grid, leaving out the hidden ones, e.g. because they're
+/// being drawn elsewhere as part of an animation.
+pub fn draw_renderables_except(
+    chunked_map_grid: &ChunkedMapGrid,
+    world: &World,
+    grid: &mut TileGrid<GameSym>,
+    hidden: &[EntityId],
+) {
+    let (map, options, player_id, coords, fovs, render_on_floors, render_on_maps, renderables) =
+        world.borrow::<(
+            UniqueView<Map>,
+            UniqueView<Options>,
+            UniqueView<PlayerId>,
+            View<Coord>,
+            View<FieldOfView>,
+            View<RenderOnFloor>,
+            View<RenderOnMap>,
+            View<Renderable>,
+        )>();
 
     let fov = fovs.get(player_id.0);
 
     // Draw floor entities first.
-    for (coord, render, _) in (&coords, &renderables, &render_on_floors).iter() {
-        if fov.get(coord.0.into()) {
+    for (id, (coord, render, _)) in (&coords, &renderables, &render_on_floors).iter().with_id() {
+        if fov.get(coord.0.into())
+            && chunked_map_grid.is_revealed(fov.center, coord.0.into())
+            && !hidden.contains(&id)
+        {
             if let Some(pos) = chunked_map_grid.map_to_grid_pos(world, coord.0) {
-                grid.put_sym_color(pos, render.sym, render.fg, render.bg);
+                grid.put_sym_color(pos, render.sym, renderable_fg(&options, render), render.bg);
             }
         }
     }
 
     // Draw normal map entities.
-    for (coord, render, _) in (&coords, &renderables, &render_on_maps).iter() {
-        if fov.get(coord.0.into()) {
+    for (id, (coord, render, _)) in (&coords, &renderables, &render_on_maps).iter().with_id() {
+        if fov.get(coord.0.into())
+            && chunked_map_grid.is_revealed(fov.center, coord.0.into())
+            && !hidden.contains(&id)
+        {
             if let Some(pos) = chunked_map_grid.map_to_grid_pos(world, coord.0) {
-                grid.put_sym_color(pos, render.sym, render.fg, render.bg);
+                grid.put_sym_color(pos, render.sym, renderable_fg(&options, render), render.bg);
             }
         }
     }
+
+    // Mark tiles with notes on them, keeping whatever is drawn there visible.
+    for note_pos in marked_note_positions(&map, chunked_map_grid, fov.center) {
+        if let Some(pos) = chunked_map_grid.map_to_grid_pos(world, note_pos.into()) {
+            grid.recolor_pos(pos, None, NOTE_BG);
+        }
+    }
+}
+
+/// Positions of the notes that should be marked on the map, i.e. those on tiles that the player
+/// has seen and that have been revealed around the given center.
+fn marked_note_positions<'a>(
+    map: &'a Map,
+    chunked_map_grid: &'a ChunkedMapGrid,
+    center: (i32, i32),
+) -> impl Iterator<Item = (i32, i32)> + 'a {
+    map.iter_notes()
+        .map(|note| (note.x, note.y))
+        .filter(move |&(x, y)| {
+            map.seen.get_bit(x, y) && chunked_map_grid.is_revealed(center, (x, y))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use shipyard::UniqueViewMut;
+
+    use super::*;
+    use crate::{saveload, spawn};
+
+    #[test]
+    fn color_override_changes_player_foreground() {
+        let mut options = Options::default();
+        let player = Renderable {
+            sym: GameSym::Player,
+            fg: Color::YELLOW,
+            bg: Color::BLACK,
+        };
+        let goblin = Renderable {
+            sym: GameSym::Goblin,
+            fg: Color::GREEN,
+            bg: Color::BLACK,
+        };
+
+        assert!(renderable_fg(&options, &player) == Color::YELLOW);
+
+        options.color_overrides.insert(GameSym::Player, Color::CYAN);
+        assert!(renderable_fg(&options, &player) == Color::CYAN);
+        assert!(renderable_fg(&options, &goblin) == Color::GREEN);
+    }
+
+    #[test]
+    fn notes_are_marked_on_seen_tiles_and_survive_saving() {
+        let world = spawn::test_world(20, 20, (5, 5));
+        let mut chunked_map_grid = ChunkedMapGrid::new();
+
+        {
+            let mut map = world.borrow::<UniqueViewMut<Map>>();
+
+            map.seen.set_bit(8, 6, true);
+            map.set_note(8, 6, "Stash".into());
+            map.set_note(15, 15, "Unseen".into());
+        }
+
+        saveload::save_game(&world).unwrap();
+        world
+            .borrow::<UniqueViewMut<Map>>()
+            .set_note(8, 6, "".into());
+        let loaded = saveload::load_game(&world);
+        saveload::delete_save_file();
+        assert!(loaded.is_ok());
+
+        let map = world.borrow::<UniqueView<Map>>();
+        let marked = marked_note_positions(&map, &chunked_map_grid, (5, 5)).collect::<Vec<_>>();
+
+        assert_eq!(map.note_at(8, 6), Some("Stash"));
+        assert_eq!(marked, [(8, 6)]);
+
+        // Notes beyond the edge of a level that's still being revealed wait for the reveal.
+        chunked_map_grid.set_reveal_radius(Some(2));
+        assert_eq!(
+            marked_note_positions(&map, &chunked_map_grid, (5, 5)).count(),
+            0
+        );
+    }
 }
@@ -9,7 +9,7 @@ use std::{
     error, fmt,
     fs::{self, File},
     io::{BufRead, BufReader, BufWriter, Write},
-    path::Path,
+    path::PathBuf,
 };
 
 #[cfg(target_os = "emscripten")]
@@ -26,11 +26,40 @@ use crate::{
 #[cfg(target_os = "emscripten")]
 const SAVE_FILENAME: &str = "/ruggrogue/savegame.txt";
 
-#[cfg(not(target_os = "emscripten"))]
+#[cfg(not(any(target_os = "emscripten", test)))]
 const SAVE_FILENAME: &str = "savegame.txt";
 
+#[cfg(not(test))]
+fn save_path() -> PathBuf {
+    PathBuf::from(SAVE_FILENAME)
+}
+
+/// Tests save to a file of their own in the temporary directory, named after the test thread, so
+/// that they can't clobber a real save or each other's.
+#[cfg(test)]
+fn save_path() -> PathBuf {
+    let test_name = std::thread::current()
+        .name()
+        .unwrap_or("unnamed")
+        .replace("::", "-");
+
+    std::env::temp_dir().join(format!(
+        "ruggrogue-{}-{}.txt",
+        std::process::id(),
+        test_name
+    ))
+}
+
 type BoxedError = Box<dyn error::Error>;
 
+/// Information about the save file itself, written before any game data.
+#[derive(Default, Deserialize, Serialize)]
+pub struct SaveHeader {
+    /// The save was made when quitting, and should be deleted as soon as it's loaded so that it
+    /// can't be loaded a second time, e.g. after the player dies.
+    pub quicksave: bool,
+}
+
 /// Game-specific errors that can occur when loading a save file.
 #[derive(Debug)]
 pub enum LoadError {
@@ -39,6 +68,7 @@ pub enum LoadError {
     MissingUnique(&'static str),
     UnknownId(EntityId),
     UnrecognizedLine(usize),
+    UnsupportedVersion(&'static str, u32),
 }
 
 impl fmt::Display for LoadError {
@@ -53,6 +83,9 @@ impl fmt::Display for LoadError {
             Self::MissingUnique(which) => write!(f, "missing {} unique", which),
             Self::UnknownId(id) => write!(f, "unknown entity ID {:?}", *id),
             Self::UnrecognizedLine(line_num) => write!(f, "line {}: unrecognized line", line_num),
+            Self::UnsupportedVersion(which, version) => {
+                write!(f, "unsupported {} format version {}", which, version)
+            }
         }
     }
 }
@@ -60,12 +93,12 @@ impl fmt::Display for LoadError {
 impl error::Error for LoadError {}
 
 pub fn save_file_exists() -> bool {
-    Path::new(SAVE_FILENAME).exists()
+    save_path().exists()
 }
 
 pub fn delete_save_file() {
     if save_file_exists() {
-        if let Err(e) = fs::remove_file(SAVE_FILENAME) {
+        if let Err(e) = fs::remove_file(save_path()) {
             eprintln!("Warning: saveload::delete_save_file: {}", e);
         }
     }
@@ -114,9 +147,13 @@ macro_rules! save_storage {
     };
 }
 
-/// Save all data in uniques and component storages to the save file.
-pub fn save_game(world: &World) -> Result<(), BoxedError> {
-    let mut writer = BufWriter::new(File::create(SAVE_FILENAME)?);
+/// Save all data in uniques and component storages to the save file, after a header line.
+fn write_save_file(world: &World, header: &SaveHeader) -> Result<(), BoxedError> {
+    let mut writer = BufWriter::new(File::create(save_path())?);
+
+    write!(writer, "*\tSaveHeader\t")?;
+    header.serialize(&mut Serializer::new(&mut writer))?;
+    writer.write_all(b"\n")?;
 
     save_unique!(GameSeed, world, &mut writer)?;
     save_unique!(TurnCount, world, &mut writer)?;
@@ -128,34 +165,47 @@ pub fn save_game(world: &World) -> Result<(), BoxedError> {
     save_unique!(PlayerId, world, &mut writer)?;
     save_unique!(Map, world, &mut writer)?;
 
+    save_storage!(AggroRange, world, &mut writer)?;
     save_storage!(AreaOfEffect, world, &mut writer)?;
     save_storage!(Asleep, world, &mut writer)?;
     save_storage!(BlocksTile, world, &mut writer)?;
+    save_storage!(Boss, world, &mut writer)?;
     save_storage!(CombatBonus, world, &mut writer)?;
     save_storage!(CombatStats, world, &mut writer)?;
+    save_storage!(Confusion, world, &mut writer)?;
     save_storage!(Consumable, world, &mut writer)?;
     save_storage!(Coord, world, &mut writer)?;
+    save_storage!(Disguise, world, &mut writer)?;
     save_storage!(EquipSlot, world, &mut writer)?;
     save_storage!(Equipment, world, &mut writer)?;
     save_storage!(Experience, world, &mut writer)?;
+    save_storage!(Fearful, world, &mut writer)?;
     save_storage!(FieldOfView, world, &mut writer)?;
     save_storage!(GivesExperience, world, &mut writer)?;
+    save_storage!(InflictsConfusion, world, &mut writer)?;
     save_storage!(InflictsDamage, world, &mut writer)?;
     save_storage!(InflictsSleep, world, &mut writer)?;
     save_storage!(Inventory, world, &mut writer)?;
     save_storage!(Item, world, &mut writer)?;
+    save_storage!(Lifesteal, world, &mut writer)?;
     save_storage!(Monster, world, &mut writer)?;
     save_storage!(Name, world, &mut writer)?;
     save_storage!(Nutrition, world, &mut writer)?;
     save_storage!(Player, world, &mut writer)?;
+    save_storage!(Poison, world, &mut writer)?;
+    save_storage!(ProperNoun, world, &mut writer)?;
     save_storage!(ProvidesHealing, world, &mut writer)?;
     save_storage!(Ranged, world, &mut writer)?;
     save_storage!(RenderOnFloor, world, &mut writer)?;
     save_storage!(RenderOnMap, world, &mut writer)?;
     save_storage!(Renderable, world, &mut writer)?;
+    save_storage!(Resistances, world, &mut writer)?;
     save_storage!(Stomach, world, &mut writer)?;
     save_storage!(Tally, world, &mut writer)?;
+    save_storage!(Thorns, world, &mut writer)?;
+    save_storage!(ThrowDamage, world, &mut writer)?;
     save_storage!(Victory, world, &mut writer)?;
+    save_storage!(Vulnerabilities, world, &mut writer)?;
 
     writer.flush()?;
 
@@ -167,6 +217,16 @@ pub fn save_game(world: &World) -> Result<(), BoxedError> {
     Ok(())
 }
 
+/// Save the game so that it can be loaded any number of times until the player dies.
+pub fn save_game(world: &World) -> Result<(), BoxedError> {
+    write_save_file(world, &SaveHeader { quicksave: false })
+}
+
+/// Save the game so that it's deleted when it's next loaded.
+pub fn quicksave_game(world: &World) -> Result<(), BoxedError> {
+    write_save_file(world, &SaveHeader { quicksave: true })
+}
+
 /// Attempt to deserialize a unique of the given named type from a line, inserting the data into
 /// the given `dest` on success.
 ///
@@ -276,7 +336,13 @@ macro_rules! deserialize_component {
 /// which would normally leave a partially-loaded group of entities behind.  To prevent this,
 /// freshly-created entities are added to the `despawn_ids` list, which is only cleared and
 /// replaced with old existing entities after final validation of the loaded data.
-fn load_save_file(world: &World, despawn_ids: &mut Vec<EntityId>) -> Result<(), BoxedError> {
+///
+/// Older save files lack a header, so a default one is returned for them.
+fn load_save_file(
+    world: &World,
+    despawn_ids: &mut Vec<EntityId>,
+) -> Result<SaveHeader, BoxedError> {
+    let mut header: Option<SaveHeader> = None;
     let mut game_seed: Option<GameSeed> = None;
     let mut turn_count: Option<TurnCount> = None;
     let mut wins: Option<Wins> = None;
@@ -287,7 +353,7 @@ fn load_save_file(world: &World, despawn_ids: &mut Vec<EntityId>) -> Result<(),
     let mut player_id: Option<PlayerId> = None;
     let mut map: Option<Map> = None;
     let mut old_to_new_ids: HashMap<EntityId, EntityId> = HashMap::new();
-    let reader = BufReader::new(File::open(SAVE_FILENAME)?);
+    let reader = BufReader::new(File::open(save_path())?);
 
     for (line_num, line_bytes) in reader.lines().enumerate() {
         let line_num = line_num + 1;
@@ -301,7 +367,8 @@ fn load_save_file(world: &World, despawn_ids: &mut Vec<EntityId>) -> Result<(),
             let maybe_unique = maybe_unique.trim_start();
 
             // Try parsing the line as a unique.
-            if deserialize_unique!(GameSeed, maybe_unique, line_num, &mut game_seed)?
+            if deserialize_unique!(SaveHeader, maybe_unique, line_num, &mut header)?
+                || deserialize_unique!(GameSeed, maybe_unique, line_num, &mut game_seed)?
                 || deserialize_unique!(TurnCount, maybe_unique, line_num, &mut turn_count)?
                 || deserialize_unique!(Wins, maybe_unique, line_num, &mut wins)?
                 || deserialize_unique!(
@@ -338,34 +405,47 @@ fn load_save_file(world: &World, despawn_ids: &mut Vec<EntityId>) -> Result<(),
             let maybe_data = maybe_data.trim_start();
 
             // Try parsing maybe_data and add it to the entity on success.
-            if deserialize_component!(AreaOfEffect, world, maybe_data, line_num, live_id)?
+            if deserialize_component!(AggroRange, world, maybe_data, line_num, live_id)?
+                || deserialize_component!(AreaOfEffect, world, maybe_data, line_num, live_id)?
                 || deserialize_component!(Asleep, world, maybe_data, line_num, live_id)?
                 || deserialize_component!(BlocksTile, world, maybe_data, line_num, live_id)?
+                || deserialize_component!(Boss, world, maybe_data, line_num, live_id)?
                 || deserialize_component!(CombatBonus, world, maybe_data, line_num, live_id)?
                 || deserialize_component!(CombatStats, world, maybe_data, line_num, live_id)?
+                || deserialize_component!(Confusion, world, maybe_data, line_num, live_id)?
                 || deserialize_component!(Consumable, world, maybe_data, line_num, live_id)?
                 || deserialize_component!(Coord, world, maybe_data, line_num, live_id)?
+                || deserialize_component!(Disguise, world, maybe_data, line_num, live_id)?
                 || deserialize_component!(EquipSlot, world, maybe_data, line_num, live_id)?
                 || deserialize_component!(Equipment, world, maybe_data, line_num, live_id)?
                 || deserialize_component!(Experience, world, maybe_data, line_num, live_id)?
+                || deserialize_component!(Fearful, world, maybe_data, line_num, live_id)?
                 || deserialize_component!(FieldOfView, world, maybe_data, line_num, live_id)?
                 || deserialize_component!(GivesExperience, world, maybe_data, line_num, live_id)?
+                || deserialize_component!(InflictsConfusion, world, maybe_data, line_num, live_id)?
                 || deserialize_component!(InflictsDamage, world, maybe_data, line_num, live_id)?
                 || deserialize_component!(InflictsSleep, world, maybe_data, line_num, live_id)?
                 || deserialize_component!(Inventory, world, maybe_data, line_num, live_id)?
                 || deserialize_component!(Item, world, maybe_data, line_num, live_id)?
+                || deserialize_component!(Lifesteal, world, maybe_data, line_num, live_id)?
                 || deserialize_component!(Monster, world, maybe_data, line_num, live_id)?
                 || deserialize_component!(Name, world, maybe_data, line_num, live_id)?
                 || deserialize_component!(Nutrition, world, maybe_data, line_num, live_id)?
                 || deserialize_component!(Player, world, maybe_data, line_num, live_id)?
+                || deserialize_component!(Poison, world, maybe_data, line_num, live_id)?
+                || deserialize_component!(ProperNoun, world, maybe_data, line_num, live_id)?
                 || deserialize_component!(ProvidesHealing, world, maybe_data, line_num, live_id)?
                 || deserialize_component!(Ranged, world, maybe_data, line_num, live_id)?
                 || deserialize_component!(RenderOnFloor, world, maybe_data, line_num, live_id)?
                 || deserialize_component!(RenderOnMap, world, maybe_data, line_num, live_id)?
                 || deserialize_component!(Renderable, world, maybe_data, line_num, live_id)?
+                || deserialize_component!(Resistances, world, maybe_data, line_num, live_id)?
                 || deserialize_component!(Stomach, world, maybe_data, line_num, live_id)?
                 || deserialize_component!(Tally, world, maybe_data, line_num, live_id)?
+                || deserialize_component!(Thorns, world, maybe_data, line_num, live_id)?
+                || deserialize_component!(ThrowDamage, world, maybe_data, line_num, live_id)?
                 || deserialize_component!(Victory, world, maybe_data, line_num, live_id)?
+                || deserialize_component!(Vulnerabilities, world, maybe_data, line_num, live_id)?
             {
                 continue;
             }
@@ -389,6 +469,8 @@ fn load_save_file(world: &World, despawn_ids: &mut Vec<EntityId>) -> Result<(),
     let mut player_id = player_id.ok_or(LoadError::MissingUnique("PlayerId"))?;
     let mut map = map.ok_or(LoadError::MissingUnique("Map"))?;
 
+    map.migrate()?;
+
     // Replace old save-internal entity IDs with new loaded entity IDs.
     difficulty.id = old_to_new_ids
         .get(&difficulty.id)
@@ -461,11 +543,13 @@ fn load_save_file(world: &World, despawn_ids: &mut Vec<EntityId>) -> Result<(),
     world.borrow::<UniqueViewMut<PlayerId>>().0 = player_id.0;
     world.borrow::<UniqueViewMut<Map>>().replace(map);
 
-    Ok(())
+    Ok(header.unwrap_or_default())
 }
 
 /// Load the game state stored in the save file and despawn entities that need despawning after the
 /// process of loading succeeds or fails.
+///
+/// A quicksave is deleted once it's been loaded successfully.
 pub fn load_game(world: &World) -> Result<(), BoxedError> {
     let mut delete_ids = Vec::new();
     let result = load_save_file(world, &mut delete_ids);
@@ -474,7 +558,11 @@ pub fn load_game(world: &World) -> Result<(), BoxedError> {
         spawn::despawn_entity(&mut world.borrow::<AllStoragesViewMut>(), id);
     }
 
-    result
+    if result.as_ref().is_ok_and(|header| header.quicksave) {
+        delete_save_file();
+    }
+
+    result.map(|_| ())
 }
 
 /// Helper module that converts a list of values into a run-length encoded vector of pairs when
@@ -516,7 +604,7 @@ pub mod run_length_encoded {
     {
         Ok(Vec::<(T, u32)>::deserialize(d)?
             .iter()
-            .flat_map(|(it, n)| std::iter::repeat(*it).take(*n as usize))
+            .flat_map(|(it, n)| std::iter::repeat_n(*it, *n as usize))
             .collect::<Vec<T>>())
     }
 }
@@ -546,3 +634,32 @@ pub mod bit_vec {
             .collect::<BitVec>())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quicksaves_are_deleted_once_loaded() {
+        let world = spawn::test_world(20, 20, (5, 5));
+
+        quicksave_game(&world).unwrap();
+        assert!(save_file_exists());
+
+        load_game(&world).unwrap();
+        assert!(!save_file_exists());
+    }
+
+    #[test]
+    fn regular_saves_survive_being_loaded() {
+        let world = spawn::test_world(20, 20, (5, 5));
+
+        save_game(&world).unwrap();
+        let loaded = load_game(&world);
+        let still_exists = save_file_exists();
+
+        delete_save_file();
+        assert!(loaded.is_ok());
+        assert!(still_exists);
+    }
+}
@@ -16,11 +16,49 @@ use crate::{
     gamesym::GameSym,
     magicnum,
     map::{Map, Rect},
-    BaseEquipmentLevel, GameSeed, Wins,
+    player::PlayerId,
+    ui::Options,
+    vision::{self, DistanceMetric},
+    BaseEquipmentLevel, GameSeed, TurnCount, Wins,
 };
-use ruggrogue::util::Color;
+use ruggrogue::{util::Color, PathableMap};
 
 const EQUIPMENT_SPAWN_PERIOD: u32 = 4;
+const WANDERING_MONSTER_PERIOD: u64 = 150;
+/// Turns that the wandering monster period shrinks by for each level of depth past the first.
+const WANDERING_MONSTER_PERIOD_STEP: u64 = 10;
+const MIN_WANDERING_MONSTER_PERIOD: u64 = 50;
+const WANDERING_MONSTER_CAP: usize = 20;
+/// Room area that gets the usual number of monsters, with bigger and smaller rooms getting more and
+/// fewer respectively.
+const TYPICAL_ROOM_AREA: i32 = 80;
+const MAX_MONSTERS_PER_ROOM: i32 = 8;
+const MONSTER_AGGRO_RANGE: i32 = 6;
+
+/// How the monsters spawned together in a room are chosen.
+#[derive(Clone, Copy)]
+enum SpawnKind {
+    /// Monsters of independently random kinds, with more of them in bigger rooms and deeper down.
+    Mixed,
+    /// A pack of monsters that all share the same level, and thus the same kind.
+    Pack,
+    /// A single monster that's tougher than usual.
+    Lone,
+}
+
+/// A way of populating a room with monsters, along with its spawn weight and the range of how
+/// many monsters it spawns.
+struct SpawnEntry {
+    weight: u32,
+    kind: SpawnKind,
+    count_range: (i32, i32),
+}
+
+/// Shallowest depth that lone monsters can appear at.
+const LONE_MONSTER_MIN_DEPTH: i32 = 3;
+
+/// Levels added to a lone monster on top of its usual random level.
+const LONE_MONSTER_LEVEL_BONUS: i32 = 2;
 
 const MONSTERS: [(GameSym, &str, (u8, u8, u8)); 25] = [
     (GameSym::Blob, "Blob", (89, 162, 191)),
@@ -50,6 +88,57 @@ const MONSTERS: [(GameSym, &str, (u8, u8, u8)); 25] = [
     (GameSym::BigHelper, "Big Helper", (255, 99, 99)),
 ];
 
+/// Shallowest depth that mimics can appear at.
+const MIMIC_MIN_DEPTH: i32 = 3;
+
+/// Chance out of this many that a randomly spawned monster is a mimic instead.
+const MIMIC_ONE_IN: u32 = 25;
+
+const MIMIC_COLOR: Color = Color {
+    r: 184,
+    g: 134,
+    b: 11,
+};
+
+/// Levels that the boss has above the current difficulty level.
+const BOSS_LEVEL_BONUS: i32 = 5;
+
+const BOSS_COLOR: Color = Color {
+    r: 255,
+    g: 215,
+    b: 0,
+};
+
+/// Items that mimics disguise themselves as.
+const MIMIC_DISGUISES: [(GameSym, &str, Color); 3] = [
+    (GameSym::HealthPotion, "Health Potion", Color::MAGENTA),
+    (
+        GameSym::MagicMissileScroll,
+        "Magic Missile Scroll",
+        Color::CYAN,
+    ),
+    (GameSym::Ration, "Ration", Color::BROWN),
+];
+
+/// Percentages of their usual brightness that randomly spawned monsters are drawn with, so that
+/// crowds of the same kind of monster aren't all exactly the same color.
+const MONSTER_SHADES: [u16; 3] = [85, 100, 115];
+
+/// Items that spawn randomly in rooms, along with their spawn weights.
+const ITEMS: [(u32, GameSym); 5] = [
+    (3, GameSym::HealthPotion),
+    (3, GameSym::MagicMissileScroll),
+    (2, GameSym::FireballScroll),
+    (2, GameSym::SleepScroll),
+    (2, GameSym::ConfusionScroll),
+];
+
+/// Weapons that deal extra damage when they're thrown.
+const THROWN_WEAPONS: [GameSym; 2] = [GameSym::Knife, GameSym::Spear];
+
+/// Base damage of a thrown weapon, before adding its attack bonus.
+const THROWN_WEAPON_DAMAGE: i32 = 2;
+
 const WEAPONS: [(GameSym, &str, (u8, u8, u8)); 10] = [
     (GameSym::Knife, "Knife", (165, 165, 165)),
     (GameSym::Club, "Club", (137, 88, 38)),
@@ -103,6 +192,7 @@ pub fn spawn_player(
     mut inventories: ViewMut<Inventory>,
     mut names: ViewMut<Name>,
     mut players: ViewMut<Player>,
+    (options, mut proper_nouns): (UniqueView<Options>, ViewMut<ProperNoun>),
     (mut render_on_maps, mut renderables, mut stomachs, mut tallies): (
         ViewMut<RenderOnMap>,
         ViewMut<Renderable>,
@@ -123,7 +213,11 @@ pub fn spawn_player(
             &mut stomachs,
         ),
         (
-            Player { auto_run: None },
+            Player {
+                auto_run: None,
+                low_hp_warned: false,
+                damage_previewed: None,
+            },
             CombatStats {
                 max_hp: experience::calc_player_max_hp(1),
                 hp: experience::calc_player_max_hp(1),
@@ -136,7 +230,7 @@ pub fn spawn_player(
                 next: 50,
                 base: 0,
             },
-            FieldOfView::new(8),
+            FieldOfView::new(options.fov_range as i32),
             Inventory { items: Vec::new() },
             Name("Player".into()),
             RenderOnMap {},
@@ -154,12 +248,13 @@ pub fn spawn_player(
     );
 
     entities.add_component(
-        (&mut equipments, &mut tallies),
+        (&mut equipments, &mut proper_nouns, &mut tallies),
         (
             Equipment {
                 weapon: None,
                 armor: None,
             },
+            ProperNoun,
             Tally {
                 damage_dealt: 0,
                 damage_taken: 0,
@@ -172,15 +267,15 @@ pub fn spawn_player(
     id
 }
 
-pub fn spawn_present(world: &World, pos: (i32, i32)) {
-    let mut map = world.borrow::<UniqueViewMut<Map>>();
-    let mut entities = world.borrow::<EntitiesViewMut>();
-    let mut coords = world.borrow::<ViewMut<Coord>>();
-    let mut items = world.borrow::<ViewMut<Item>>();
-    let mut names = world.borrow::<ViewMut<Name>>();
-    let mut render_on_floors = world.borrow::<ViewMut<RenderOnFloor>>();
-    let mut renderables = world.borrow::<ViewMut<Renderable>>();
-    let mut victories = world.borrow::<ViewMut<Victory>>();
+pub fn spawn_present(all_storages: &AllStoragesViewMut, pos: (i32, i32)) {
+    let mut map = all_storages.borrow::<UniqueViewMut<Map>>();
+    let mut entities = all_storages.borrow::<EntitiesViewMut>();
+    let mut coords = all_storages.borrow::<ViewMut<Coord>>();
+    let mut items = all_storages.borrow::<ViewMut<Item>>();
+    let mut names = all_storages.borrow::<ViewMut<Name>>();
+    let mut render_on_floors = all_storages.borrow::<ViewMut<RenderOnFloor>>();
+    let mut renderables = all_storages.borrow::<ViewMut<Renderable>>();
+    let mut victories = all_storages.borrow::<ViewMut<Victory>>();
     let present_id = entities.add_entity(
         (
             &mut items,
@@ -244,7 +339,7 @@ fn spawn_item(world: &World, pos: (i32, i32), name: String, sym: GameSym, fg: Co
     )
 }
 
-fn spawn_ration(world: &World, pos: (i32, i32)) {
+fn spawn_ration(world: &World, pos: (i32, i32)) -> EntityId {
     let item_id = spawn_item(world, pos, "Ration".into(), GameSym::Ration, Color::BROWN);
     let (entities, mut consumables, mut nutritions) =
         world.borrow::<(EntitiesView, ViewMut<Consumable>, ViewMut<Nutrition>)>();
@@ -254,9 +349,11 @@ fn spawn_ration(world: &World, pos: (i32, i32)) {
         (Consumable {}, Nutrition(750)),
         item_id,
     );
+
+    item_id
 }
 
-fn spawn_health_potion(world: &World, pos: (i32, i32)) {
+fn spawn_health_potion(world: &World, pos: (i32, i32)) -> EntityId {
     let item_id = spawn_item(
         world,
         pos,
@@ -272,9 +369,11 @@ fn spawn_health_potion(world: &World, pos: (i32, i32)) {
         (Consumable {}, ProvidesHealing { heal_amount: 20 }),
         item_id,
     );
+
+    item_id
 }
 
-fn spawn_magic_missile_scroll(world: &World, pos: (i32, i32)) {
+fn spawn_magic_missile_scroll(world: &World, pos: (i32, i32)) -> EntityId {
     let item_id = spawn_item(
         world,
         pos,
@@ -293,14 +392,22 @@ fn spawn_magic_missile_scroll(world: &World, pos: (i32, i32)) {
         (&mut consumables, &mut inflicts_damages, &mut rangeds),
         (
             Consumable {},
-            InflictsDamage { damage: 8 },
-            Ranged { range: 6 },
+            InflictsDamage {
+                damage: 8,
+                damage_type: DamageType::Physical,
+            },
+            Ranged {
+                range: 6,
+                metric: DistanceMetric::Euclidean,
+            },
         ),
         item_id,
     );
+
+    item_id
 }
 
-fn spawn_fireball_scroll(world: &World, pos: (i32, i32)) {
+fn spawn_fireball_scroll(world: &World, pos: (i32, i32)) -> EntityId {
     let item_id = spawn_item(
         world,
         pos,
@@ -326,14 +433,22 @@ fn spawn_fireball_scroll(world: &World, pos: (i32, i32)) {
         (
             AreaOfEffect { radius: 3 },
             Consumable {},
-            InflictsDamage { damage: 20 },
-            Ranged { range: 6 },
+            InflictsDamage {
+                damage: 20,
+                damage_type: DamageType::Fire,
+            },
+            Ranged {
+                range: 6,
+                metric: DistanceMetric::Euclidean,
+            },
         ),
         item_id,
     );
+
+    item_id
 }
 
-fn spawn_sleep_scroll(world: &World, pos: (i32, i32)) {
+fn spawn_sleep_scroll(world: &World, pos: (i32, i32)) -> EntityId {
     let item_id = spawn_item(
         world,
         pos,
@@ -360,10 +475,60 @@ fn spawn_sleep_scroll(world: &World, pos: (i32, i32)) {
             AreaOfEffect { radius: 1 },
             Consumable {},
             InflictsSleep { sleepiness: 36 },
-            Ranged { range: 6 },
+            Ranged {
+                range: 6,
+                metric: DistanceMetric::Euclidean,
+            },
+        ),
+        item_id,
+    );
+
+    item_id
+}
+
+fn spawn_confusion_scroll(world: &World, pos: (i32, i32)) -> EntityId {
+    let item_id = spawn_item(
+        world,
+        pos,
+        "Confusion Scroll".into(),
+        GameSym::ConfusionScroll,
+        Color::PURPLE,
+    );
+    let (entities, mut consumables, mut inflicts_confusions, mut rangeds) = world.borrow::<(
+        EntitiesView,
+        ViewMut<Consumable>,
+        ViewMut<InflictsConfusion>,
+        ViewMut<Ranged>,
+    )>();
+
+    entities.add_component(
+        (&mut consumables, &mut inflicts_confusions, &mut rangeds),
+        (
+            Consumable {},
+            InflictsConfusion { turns: 8 },
+            Ranged {
+                range: 6,
+                metric: DistanceMetric::Euclidean,
+            },
         ),
         item_id,
     );
+
+    item_id
+}
+
+/// Spawn an item of the given kind, returning its ID, or `None` if it isn't an item that can be
+/// spawned by kind alone.  Weapons and armor depend on the dungeon level, so they aren't included.
+pub fn spawn_item_kind(world: &World, sym: GameSym, pos: (i32, i32)) -> Option<EntityId> {
+    Some(match sym {
+        GameSym::Ration => spawn_ration(world, pos),
+        GameSym::HealthPotion => spawn_health_potion(world, pos),
+        GameSym::MagicMissileScroll => spawn_magic_missile_scroll(world, pos),
+        GameSym::FireballScroll => spawn_fireball_scroll(world, pos),
+        GameSym::SleepScroll => spawn_sleep_scroll(world, pos),
+        GameSym::ConfusionScroll => spawn_confusion_scroll(world, pos),
+        _ => return None,
+    })
 }
 
 fn rescale_level<R: Rng>(level: f32, scale: usize, rng: &mut R) -> usize {
@@ -384,20 +549,34 @@ fn spawn_weapon<R: Rng>(world: &World, rng: &mut R, pos: (i32, i32), level: f32,
         sym,
         rgb.into(),
     );
-    let (entities, mut combat_bonuses, mut equip_slots) =
-        world.borrow::<(EntitiesView, ViewMut<CombatBonus>, ViewMut<EquipSlot>)>();
+    let attack = experience::calc_weapon_attack(level + bonus + base_equipment_level);
+    let (entities, mut combat_bonuses, mut equip_slots, mut throw_damages) = world.borrow::<(
+        EntitiesView,
+        ViewMut<CombatBonus>,
+        ViewMut<EquipSlot>,
+        ViewMut<ThrowDamage>,
+    )>();
 
     entities.add_component(
         (&mut combat_bonuses, &mut equip_slots),
         (
             CombatBonus {
-                attack: experience::calc_weapon_attack(level + bonus + base_equipment_level),
+                attack,
                 defense: 0.0,
             },
             EquipSlot::Weapon,
         ),
         item_id,
     );
+
+    // Weapons made for throwing hit about as hard thrown as they do in hand.
+    if THROWN_WEAPONS.contains(&sym) {
+        entities.add_component(
+            &mut throw_damages,
+            ThrowDamage(THROWN_WEAPON_DAMAGE + attack.round() as i32),
+            item_id,
+        );
+    }
 }
 
 fn spawn_armor<R: Rng>(world: &World, rng: &mut R, pos: (i32, i32), level: f32, bonus: i32) {
@@ -427,10 +606,35 @@ fn spawn_armor<R: Rng>(world: &World, rng: &mut R, pos: (i32, i32), level: f32,
     );
 }
 
-fn spawn_monster(world: &World, pos: (i32, i32), level: i32, sym: GameSym, name: &str, fg: Color) {
+/// Damage types that a monster resists and is vulnerable to, respectively.
+fn monster_damage_affinities(sym: GameSym) -> (&'static [DamageType], &'static [DamageType]) {
+    match sym {
+        GameSym::Blob => (&[], &[DamageType::Fire]),
+        GameSym::Crab => (&[DamageType::Cold], &[]),
+        GameSym::Snake | GameSym::Naga => (&[DamageType::Poison], &[DamageType::Cold]),
+        GameSym::Ghost => (
+            &[DamageType::Physical, DamageType::Cold, DamageType::Poison],
+            &[],
+        ),
+        GameSym::Skeleton => (&[DamageType::Poison], &[]),
+        GameSym::Demon => (&[DamageType::Fire], &[DamageType::Cold]),
+        GameSym::Sentinel => (&[DamageType::Fire, DamageType::Cold], &[]),
+        _ => (&[], &[]),
+    }
+}
+
+fn spawn_monster(
+    world: &World,
+    pos: (i32, i32),
+    level: i32,
+    sym: GameSym,
+    name: &str,
+    fg: Color,
+) -> EntityId {
     let monster_id = world.borrow::<EntitiesViewMut>().add_entity(
         (
             &mut world.borrow::<ViewMut<Monster>>(),
+            &mut world.borrow::<ViewMut<AggroRange>>(),
             &mut world.borrow::<ViewMut<BlocksTile>>(),
             &mut world.borrow::<ViewMut<CombatStats>>(),
             &mut world.borrow::<ViewMut<Coord>>(),
@@ -441,7 +645,8 @@ fn spawn_monster(world: &World, pos: (i32, i32), level: i32, sym: GameSym, name:
             &mut world.borrow::<ViewMut<Renderable>>(),
         ),
         (
-            Monster {},
+            Monster::default(),
+            AggroRange(MONSTER_AGGRO_RANGE),
             BlocksTile {},
             CombatStats {
                 max_hp: experience::calc_monster_max_hp(level),
@@ -462,12 +667,201 @@ fn spawn_monster(world: &World, pos: (i32, i32), level: i32, sym: GameSym, name:
         ),
     );
 
+    // Bats drain the life of whatever they bite.
+    if matches!(sym, GameSym::Bat) {
+        world.borrow::<EntitiesView>().add_component(
+            &mut world.borrow::<ViewMut<Lifesteal>>(),
+            Lifesteal { fraction: 0.5 },
+            monster_id,
+        );
+    }
+
+    // Goblins and kobolds run away when they're losing a fight.
+    if matches!(sym, GameSym::Goblin | GameSym::Kobold) {
+        world.borrow::<EntitiesView>().add_component(
+            &mut world.borrow::<ViewMut<Fearful>>(),
+            Fearful { fleeing: false },
+            monster_id,
+        );
+    }
+
+    // Crabs have spiky shells that hurt whatever hits them.
+    if matches!(sym, GameSym::Crab) {
+        world.borrow::<EntitiesView>().add_component(
+            &mut world.borrow::<ViewMut<Thorns>>(),
+            Thorns { amount: 1 },
+            monster_id,
+        );
+    }
+
+    let (resists, vulns) = monster_damage_affinities(sym);
+    if !resists.is_empty() {
+        world.borrow::<EntitiesView>().add_component(
+            &mut world.borrow::<ViewMut<Resistances>>(),
+            Resistances(resists.to_vec()),
+            monster_id,
+        );
+    }
+    if !vulns.is_empty() {
+        world.borrow::<EntitiesView>().add_component(
+            &mut world.borrow::<ViewMut<Vulnerabilities>>(),
+            Vulnerabilities(vulns.to_vec()),
+            monster_id,
+        );
+    }
+
     world
         .borrow::<UniqueViewMut<Map>>()
         .place_entity(monster_id, pos, true);
+
+    monster_id
 }
 
-fn spawn_random_monster_at<R: Rng>(world: &World, rng: &mut R, pos: (i32, i32)) {
+/// Spawn a monster of the given kind at the level it usually appears at, returning its ID, or
+/// `None` if there's no such monster.
+#[cfg(test)]
+pub fn spawn_monster_kind(world: &World, sym: GameSym, pos: (i32, i32)) -> Option<EntityId> {
+    let level = MONSTERS.iter().position(|&(s, _, _)| s == sym)?;
+    let (_, name, fg) = MONSTERS[level];
+
+    Some(spawn_monster(
+        world,
+        pos,
+        level as i32 + 1,
+        sym,
+        name,
+        fg.into(),
+    ))
+}
+
+/// A world set up like a freshly-started game on an open map of the given size, with the player
+/// standing at `player_pos`.
+#[cfg(test)]
+pub fn test_world(width: i32, height: i32, player_pos: (i32, i32)) -> World {
+    use crate::{
+        animation::Animations, chunked::Camera, damage::DamageQueue, gamekey::KeyBindings,
+        item::PickUpHint, menu_memory::MenuMemory, message::Messages, monster::MonsterTurns,
+        player::PlayerAlive,
+    };
+
+    let world = World::new();
+
+    world.add_unique(Options::default());
+    world.add_unique(KeyBindings::new());
+    world.add_unique(GameSeed(1));
+    world.add_unique(TurnCount(1));
+    world.add_unique(Wins(0));
+    world.add_unique(BaseEquipmentLevel(0));
+    world.add_unique(Animations::new());
+    world.add_unique(Camera::new());
+    world.add_unique(DamageQueue::new());
+    world.add_unique(Difficulty::new(world.run(spawn_difficulty)));
+    world.add_unique(MenuMemory::new());
+    world.add_unique(Messages::new(100, 10));
+    world.add_unique(Map::new(width, height));
+    world.add_unique(PickUpHint(false));
+    world.add_unique(PlayerAlive(true));
+    world.add_unique(MonsterTurns::new());
+
+    let player_id = world.run(spawn_player);
+
+    world.borrow::<EntitiesView>().add_component(
+        &mut world.borrow::<ViewMut<Coord>>(),
+        Coord(player_pos.into()),
+        player_id,
+    );
+    world
+        .borrow::<UniqueViewMut<Map>>()
+        .place_entity(player_id, player_pos, false);
+    world.add_unique(PlayerId(player_id));
+
+    world
+}
+
+/// Lighten or darken a color to a percentage of its usual brightness.
+fn shade_color(color: Color, percent: u16) -> Color {
+    let shade = |c: u8| (c as u16 * percent / 100).min(255) as u8;
+
+    Color {
+        r: shade(color.r),
+        g: shade(color.g),
+        b: shade(color.b),
+    }
+}
+
+/// Spawn the boss that guards the Present on the final level, which is much tougher than the
+/// monsters around it.
+pub fn spawn_boss(world: &World, pos: (i32, i32)) {
+    let level = {
+        let difficulty = world.borrow::<UniqueView<Difficulty>>();
+        let exps = world.borrow::<View<Experience>>();
+        exps.get(difficulty.id).level + BOSS_LEVEL_BONUS
+    };
+    let boss_id = spawn_monster(world, pos, level, GameSym::Demon, "Demon King", BOSS_COLOR);
+
+    world.borrow::<EntitiesView>().add_component(
+        &mut world.borrow::<ViewMut<Boss>>(),
+        Boss {},
+        boss_id,
+    );
+}
+
+/// Spawn a mimic that's disguised as an item until the player gets close to it.
+fn spawn_mimic<R: Rng>(world: &World, rng: &mut R, pos: (i32, i32), level: i32) {
+    let monster_id = spawn_monster(world, pos, level, GameSym::Mimic, "Mimic", MIMIC_COLOR);
+    let (sym, name, fg) = MIMIC_DISGUISES[rng.gen_range(0..MIMIC_DISGUISES.len())];
+    let (entities, mut disguises, mut names, mut renderables) = world.borrow::<(
+        EntitiesView,
+        ViewMut<Disguise>,
+        ViewMut<Name>,
+        ViewMut<Renderable>,
+    )>();
+    let true_name = std::mem::replace(&mut (&mut names).get(monster_id).0, name.into());
+    let render = (&mut renderables).get(monster_id);
+
+    entities.add_component(
+        &mut disguises,
+        Disguise {
+            name: true_name,
+            sym: render.sym,
+            fg: render.fg,
+        },
+        monster_id,
+    );
+    render.sym = sym;
+    render.fg = fg;
+}
+
+/// Weighted ways that rooms are populated with monsters at the given depth.  Deeper levels favor
+/// packs and lone monsters over mixed groups.  This only picks how monsters are grouped; which
+/// monsters appear follows their level, which rises with [Difficulty].
+fn spawn_kind_table_for_depth(depth: i32) -> Vec<SpawnEntry> {
+    let mut table = vec![
+        SpawnEntry {
+            weight: 8,
+            kind: SpawnKind::Mixed,
+            count_range: (1, 3),
+        },
+        SpawnEntry {
+            weight: 1 + (depth.max(1) / 3) as u32,
+            kind: SpawnKind::Pack,
+            count_range: (3, 5),
+        },
+    ];
+
+    if depth >= LONE_MONSTER_MIN_DEPTH {
+        table.push(SpawnEntry {
+            weight: (depth / LONE_MONSTER_MIN_DEPTH) as u32,
+            kind: SpawnKind::Lone,
+            count_range: (1, 1),
+        });
+    }
+
+    table
+}
+
+/// Pick a random level for a monster based on the current difficulty, usually a bit lower.
+fn random_monster_level<R: Rng>(world: &World, rng: &mut R) -> i32 {
     let mut level = {
         let difficulty = world.borrow::<UniqueView<Difficulty>>();
         let exps = world.borrow::<View<Experience>>();
@@ -479,11 +873,31 @@ fn spawn_random_monster_at<R: Rng>(world: &World, rng: &mut R, pos: (i32, i32))
             level = rng.gen_range(1i32..level);
         }
     }
+
+    level
+}
+
+/// Spawn the kind of monster that matches the given level.
+fn spawn_monster_of_level<R: Rng>(world: &World, rng: &mut R, pos: (i32, i32), level: i32) {
     let (sym, name, fg) = MONSTERS[(level.max(1) as usize)
         .min(MONSTERS.len())
         .saturating_sub(1)];
 
-    spawn_monster(world, pos, level, sym, name, fg.into());
+    let shade = MONSTER_SHADES.choose(rng).copied().unwrap_or(100);
+
+    spawn_monster(world, pos, level, sym, name, shade_color(fg.into(), shade));
+}
+
+fn spawn_random_monster_at<R: Rng>(world: &World, rng: &mut R, pos: (i32, i32)) {
+    let level = random_monster_level(world, rng);
+
+    if world.borrow::<UniqueView<Map>>().depth >= MIMIC_MIN_DEPTH && rng.gen_ratio(1, MIMIC_ONE_IN)
+    {
+        spawn_mimic(world, rng, pos, level);
+        return;
+    }
+
+    spawn_monster_of_level(world, rng, pos, level);
 }
 
 fn spawn_random_item_at<R: Rng>(world: &World, rng: &mut R, pos: (i32, i32)) {
@@ -504,22 +918,20 @@ fn spawn_random_item_at<R: Rng>(world: &World, rng: &mut R, pos: (i32, i32)) {
         }
     } else {
         // Spawn an item.
-        type ItemFn = fn(&World, (i32, i32));
-        let choice: Result<&(u32, ItemFn), _> = [
-            (3, spawn_health_potion as _),
-            (3, spawn_magic_missile_scroll as _),
-            (2, spawn_fireball_scroll as _),
-            (2, spawn_sleep_scroll as _),
-        ]
-        .choose_weighted(rng, |&(weight, _)| weight);
-
-        if let Ok((_, item_fn)) = choice {
-            item_fn(world, pos);
+        if let Ok(&(_, sym)) = ITEMS.choose_weighted(rng, |&(weight, _)| weight) {
+            spawn_item_kind(world, sym, pos);
         }
     }
 }
 
-fn fill_room_with_spawns<R: Rng>(world: &World, rng: &mut R, room: &Rect) {
+/// Fill a room with random items and monsters, spawning no more than `monsters_left` monsters and
+/// counting down the ones that are spawned.
+fn fill_room_with_spawns<R: Rng>(
+    world: &World,
+    rng: &mut R,
+    room: &Rect,
+    monsters_left: &mut usize,
+) {
     let depth = world.borrow::<UniqueView<Map>>().depth;
     let wins = world.borrow::<UniqueView<Wins>>().0.min(i32::MAX as u32) as i32;
 
@@ -532,10 +944,51 @@ fn fill_room_with_spawns<R: Rng>(world: &World, rng: &mut R, room: &Rect) {
     }
 
     if rng.gen_ratio(1, 2) {
-        let num = rng.gen_range(1i32..1 + wins + ((depth + 1) / 2).max(1).min(3));
+        let table = spawn_kind_table_for_depth(depth);
+        let entry = table
+            .choose_weighted(rng, |entry| entry.weight)
+            .unwrap_or(&table[0]);
+        let (min_count, max_count) = entry.count_range;
+        let num = match entry.kind {
+            SpawnKind::Mixed => {
+                let num =
+                    rng.gen_range(min_count..=wins + ((depth + 1) / 2).clamp(min_count, max_count));
+                // Keep monster density consistent by scaling the count to the room size.
+                ((num * room.area() + TYPICAL_ROOM_AREA / 2) / TYPICAL_ROOM_AREA)
+                    .clamp(1, MAX_MONSTERS_PER_ROOM + wins)
+            }
+            SpawnKind::Pack | SpawnKind::Lone => rng.gen_range(min_count..=max_count),
+        }
+        .min(*monsters_left as i32);
+        let spawn_positions = {
+            let player_id = world.borrow::<UniqueView<PlayerId>>();
+            let coords = world.borrow::<View<Coord>>();
+            let fovs = world.borrow::<View<FieldOfView>>();
+            let player_pos = coords.get(player_id.0).0;
+            let player_fov = fovs.get(player_id.0);
+            let safe_radius = world.borrow::<UniqueView<Options>>().safe_spawn_radius as i32;
+            let safe_area = Rect::new(player_pos.x, player_pos.y, 1, 1).expand(safe_radius);
+
+            // Don't spawn monsters where the player can already see them, or too close to them.
+            room.iter_xy()
+                .filter(|&(x, y)| !player_fov.get((x, y)) && !safe_area.contains(x, y))
+                .choose_multiple(rng, num as usize)
+        };
 
-        for pos in room.iter_xy().choose_multiple(rng, num as usize) {
-            spawn_random_monster_at(world, rng, pos);
+        *monsters_left -= spawn_positions.len();
+
+        let shared_level = match entry.kind {
+            SpawnKind::Mixed => None,
+            SpawnKind::Pack => Some(random_monster_level(world, rng)),
+            SpawnKind::Lone => Some(random_monster_level(world, rng) + LONE_MONSTER_LEVEL_BONUS),
+        };
+
+        for pos in spawn_positions {
+            if let Some(level) = shared_level {
+                spawn_monster_of_level(world, rng, pos, level);
+            } else {
+                spawn_random_monster_at(world, rng, pos);
+            }
         }
     }
 }
@@ -653,21 +1106,94 @@ pub fn fill_rooms_with_spawns(world: &World) {
 
     spawn_guaranteed_equipment(world, &mut rng);
 
-    let rooms = world
-        .borrow::<UniqueViewMut<Map>>()
-        .rooms
-        .iter()
-        .skip(1)
-        .copied()
-        .collect::<Vec<_>>();
+    // Calculate the player's field of view up-front so that spawns can avoid it.
+    world.run(
+        |player_id: UniqueView<PlayerId>, mut fovs: ViewMut<FieldOfView>| {
+            (&mut fovs).get(player_id.0).dirty = true;
+        },
+    );
+    world.run(vision::recalculate_fields_of_view);
+
+    // The player starts in the first room, so leave it empty for a safe start, unless it's the
+    // only room there is.  Monsters still won't spawn where the player can see them.
+    let rooms = {
+        let map = world.borrow::<UniqueView<Map>>();
+        let skip = if map.rooms.len() > 1 { 1 } else { 0 };
+
+        map.rooms.iter().skip(skip).copied().collect::<Vec<_>>()
+    };
+
+    // The boss faces the player alone.
+    let mut monsters_left = if world.borrow::<View<Boss>>().is_empty() {
+        world.borrow::<UniqueView<Options>>().max_monsters_per_level as usize
+    } else {
+        0
+    };
 
     for room in &rooms {
-        fill_room_with_spawns(world, &mut rng, room);
+        fill_room_with_spawns(world, &mut rng, room, &mut monsters_left);
     }
 
     spawn_guaranteed_ration(world, &mut rng);
 }
 
+/// Number of turns between wandering monster spawns, which get more frequent deeper down.
+fn wandering_monster_period(depth: i32) -> u64 {
+    let steps = depth.saturating_sub(1).max(0) as u64;
+
+    WANDERING_MONSTER_PERIOD
+        .saturating_sub(steps * WANDERING_MONSTER_PERIOD_STEP)
+        .max(MIN_WANDERING_MONSTER_PERIOD)
+}
+
+/// Number of monsters on a level beyond which no more wandering monsters spawn, which is never more
+/// than the most monsters that a level is filled with to begin with.
+fn wandering_monster_cap(options: &Options) -> usize {
+    WANDERING_MONSTER_CAP.min(options.max_monsters_per_level as usize)
+}
+
+/// Spawn a wandering monster out of sight of the player every so many turns, as long as the level
+/// isn't already crowded with monsters.
+pub fn spawn_wandering_monster(world: &World) {
+    let turn_count = world.borrow::<UniqueView<TurnCount>>().0;
+    let depth = world.borrow::<UniqueView<Map>>().depth;
+    let options = world.borrow::<UniqueView<Options>>();
+
+    if !options.wandering_monsters
+        || turn_count == 0
+        || !turn_count.is_multiple_of(wandering_monster_period(depth))
+        || world.borrow::<View<Monster>>().len() >= wandering_monster_cap(&options)
+        || !world.borrow::<View<Boss>>().is_empty()
+    {
+        return;
+    }
+
+    let mut rng = {
+        let mut hasher = WyHash::with_seed(magicnum::SPAWN_WANDERING_MONSTER);
+        hasher.write_u64(world.borrow::<UniqueView<GameSeed>>().0);
+        hasher.write_i32(depth);
+        hasher.write_u64(turn_count);
+        GameRng::seed_from_u64(hasher.finish())
+    };
+
+    let spawn_pos = {
+        let map = world.borrow::<UniqueView<Map>>();
+        let player_id = world.borrow::<UniqueView<PlayerId>>();
+        let fovs = world.borrow::<View<FieldOfView>>();
+        let player_fov = fovs.get(player_id.0);
+
+        map.rooms.choose(&mut rng).and_then(|room| {
+            room.iter_xy()
+                .filter(|&(x, y)| !player_fov.get((x, y)) && !map.is_blocked(x, y))
+                .choose(&mut rng)
+        })
+    };
+
+    if let Some(pos) = spawn_pos {
+        spawn_random_monster_at(world, &mut rng, pos);
+    }
+}
+
 /// Despawn an entity, including all associated entities like equipment and inventory.
 pub fn despawn_entity(all_storages: &mut AllStoragesViewMut, id: EntityId) {
     let mut extra_despawn_ids = Vec::new();
@@ -709,3 +1235,424 @@ pub fn despawn_coord_entities(mut all_storages: AllStoragesViewMut) {
         despawn_entity(&mut all_storages, id);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        map,
+        message::Messages,
+        player::{self, PlayerAction},
+        ui::MapGenerator,
+    };
+
+    fn weight_of(table: &[SpawnEntry], kind: fn(&SpawnKind) -> bool) -> u32 {
+        table
+            .iter()
+            .filter(|entry| kind(&entry.kind))
+            .map(|entry| entry.weight)
+            .sum()
+    }
+
+    #[test]
+    fn spawn_by_kind_builds_a_scenario() {
+        let world = World::new();
+
+        world.add_unique(Map::new(10, 10));
+
+        let goblin_id = spawn_monster_kind(&world, GameSym::Goblin, (3, 3)).unwrap();
+        let crab_id = spawn_monster_kind(&world, GameSym::Crab, (4, 3)).unwrap();
+        let potion_id = spawn_item_kind(&world, GameSym::HealthPotion, (3, 4)).unwrap();
+
+        assert!(spawn_monster_kind(&world, GameSym::HealthPotion, (5, 5)).is_none());
+        assert!(spawn_item_kind(&world, GameSym::Goblin, (5, 5)).is_none());
+
+        let map = world.borrow::<UniqueView<Map>>();
+        let (fearfuls, monsters, names, thorns) =
+            world.borrow::<(View<Fearful>, View<Monster>, View<Name>, View<Thorns>)>();
+
+        assert!(monsters.contains(goblin_id) && monsters.contains(crab_id));
+        assert!(!monsters.contains(potion_id));
+        assert_eq!(names.get(goblin_id).0, "Goblin");
+        assert!(fearfuls.contains(goblin_id));
+        assert!(thorns.contains(crab_id));
+        assert!(map.iter_entities_at(3, 3).eq([goblin_id]));
+        assert!(map.iter_entities_at(3, 4).eq([potion_id]));
+        assert!(map.is_blocked(4, 3));
+        assert!(!map.is_blocked(3, 4));
+    }
+
+    /// An open 40x30 level at depth 6 with a level 8 player at the given position, who can see
+    /// the given distance around them.
+    fn open_level(seed: u64, player_pos: (i32, i32), fov_range: i32) -> World {
+        let world = World::new();
+
+        world.add_unique(Map::new(40, 30));
+        world.borrow::<UniqueViewMut<Map>>().depth = 6;
+        world.add_unique(Options::default());
+        world.add_unique(Wins(0));
+        world.add_unique(GameSeed(seed));
+        world.add_unique(BaseEquipmentLevel(0));
+
+        let difficulty_id = world.run(spawn_difficulty);
+        (&mut world.borrow::<ViewMut<Experience>>())
+            .get(difficulty_id)
+            .level = 8;
+        world.add_unique(Difficulty::new(difficulty_id));
+
+        let player_id = world.run(
+            |mut entities: EntitiesViewMut,
+             mut coords: ViewMut<Coord>,
+             mut fovs: ViewMut<FieldOfView>| {
+                entities.add_entity(
+                    (&mut coords, &mut fovs),
+                    (Coord(player_pos.into()), FieldOfView::new(fov_range)),
+                )
+            },
+        );
+        world.add_unique(PlayerId(player_id));
+        world.run(vision::recalculate_fields_of_view);
+
+        world
+    }
+
+    /// Monster names in a room filled with spawns using an RNG with the given seed, in order of
+    /// position.
+    fn room_monster_names(seed: u64, room: &Rect, monsters_left: &mut usize) -> Vec<String> {
+        let world = open_level(seed, (1, 1), 0);
+
+        fill_room_with_spawns(
+            &world,
+            &mut GameRng::seed_from_u64(seed),
+            room,
+            monsters_left,
+        );
+
+        let map = world.borrow::<UniqueView<Map>>();
+        let (monsters, names) = world.borrow::<(View<Monster>, View<Name>)>();
+
+        room.iter_xy()
+            .flat_map(|(x, y)| map.iter_entities_at(x, y).collect::<Vec<_>>())
+            .filter(|&id| monsters.contains(id))
+            .map(|id| names.get(id).0.clone())
+            .collect()
+    }
+
+    #[test]
+    fn room_spawns_follow_the_seed() {
+        let room = Rect::new(20, 10, 10, 8);
+        let mut left = 10;
+
+        assert_eq!(
+            room_monster_names(5, &room, &mut left),
+            ["Goblin", "Crab", "Goblin"]
+        );
+        assert_eq!(left, 7);
+
+        assert_eq!(room_monster_names(8, &room, &mut left), ["Kobold"; 5]);
+        assert_eq!(left, 2);
+
+        // Packs are cut short instead of overshooting the level's monster limit.
+        let mut left = 2;
+        assert_eq!(room_monster_names(8, &room, &mut left).len(), 2);
+        assert_eq!(left, 0);
+    }
+
+    #[test]
+    fn bigger_rooms_get_more_monsters() {
+        let small_room = Rect::new(20, 10, 3, 3);
+        let big_room = Rect::new(15, 10, 20, 15);
+        let total = |room: &Rect| {
+            (0..40)
+                .map(|seed| room_monster_names(seed, room, &mut 1000).len())
+                .sum::<usize>()
+        };
+
+        assert!(total(&big_room) > total(&small_room));
+    }
+
+    /// Generate and fill a level the way a new game does, for a player of the given level,
+    /// returning the world and the player's ID.
+    fn new_level(seed: u64, player_level: i32, options: Options) -> (World, EntityId) {
+        let world = World::new();
+
+        world.add_unique(Map::new(80, 50));
+        world.add_unique(options);
+        world.add_unique(Wins(0));
+        world.add_unique(GameSeed(seed));
+        world.add_unique(BaseEquipmentLevel(0));
+
+        let difficulty_id = world.run(spawn_difficulty);
+        (&mut world.borrow::<ViewMut<Experience>>())
+            .get(difficulty_id)
+            .level = player_level;
+        world.add_unique(Difficulty::new(difficulty_id));
+
+        let player_id = world.run(
+            |mut entities: EntitiesViewMut,
+             mut coords: ViewMut<Coord>,
+             mut fovs: ViewMut<FieldOfView>| {
+                entities.add_entity(
+                    (&mut coords, &mut fovs),
+                    (Coord((0, 0).into()), FieldOfView::new(8)),
+                )
+            },
+        );
+        world.add_unique(PlayerId(player_id));
+
+        if let Some(victory_pos) = world.run(map::generate_rooms_and_corridors) {
+            spawn_boss(&world, victory_pos);
+        }
+        world.run(map::place_player_in_first_room);
+        fill_rooms_with_spawns(&world);
+
+        (world, player_id)
+    }
+
+    /// Positions of all of the monsters in a world.
+    fn monster_positions(world: &World) -> Vec<(i32, i32)> {
+        let (coords, monsters) = world.borrow::<(View<Coord>, View<Monster>)>();
+
+        (&coords, &monsters)
+            .iter()
+            .map(|(coord, _)| coord.0.into())
+            .collect()
+    }
+
+    #[test]
+    fn no_monsters_spawn_in_view_of_the_player() {
+        let room = Rect::new(0, 0, 40, 30);
+
+        for seed in 0..20 {
+            let world = open_level(seed, (10, 15), 12);
+
+            fill_room_with_spawns(&world, &mut GameRng::seed_from_u64(seed), &room, &mut 50);
+
+            let player_id = world.borrow::<UniqueView<PlayerId>>().0;
+            let fovs = world.borrow::<View<FieldOfView>>();
+            let player_fov = fovs.get(player_id);
+
+            assert!(monster_positions(&world)
+                .iter()
+                .all(|&pos| !player_fov.get(pos)));
+        }
+    }
+
+    #[test]
+    fn no_monsters_spawn_within_the_safe_radius() {
+        let room = Rect::new(0, 0, 40, 30);
+        let mut spawned = 0;
+
+        for seed in 0..20 {
+            let world = open_level(seed, (20, 15), 0);
+
+            world.borrow::<UniqueViewMut<Options>>().safe_spawn_radius = 10;
+            fill_room_with_spawns(&world, &mut GameRng::seed_from_u64(seed), &room, &mut 50);
+
+            let positions = monster_positions(&world);
+
+            assert!(positions
+                .iter()
+                .all(|&(x, y)| (x - 20).abs() > 10 || (y - 15).abs() > 10));
+            spawned += positions.len();
+        }
+
+        assert!(spawned > 0);
+    }
+
+    #[test]
+    fn levels_without_rooms_spawn_nothing() {
+        let world = open_level(3, (10, 15), 4);
+
+        fill_rooms_with_spawns(&world);
+
+        assert!(monster_positions(&world).is_empty());
+        assert!(world.borrow::<View<Item>>().is_empty());
+    }
+
+    #[test]
+    fn a_lone_room_is_filled_out_of_sight_of_the_player() {
+        let mut spawned = 0;
+
+        for seed in 0..10 {
+            let world = open_level(seed, (2, 2), 4);
+
+            world
+                .borrow::<UniqueViewMut<Map>>()
+                .rooms
+                .push(Rect::new(0, 0, 40, 30));
+            fill_rooms_with_spawns(&world);
+
+            let player_id = world.borrow::<UniqueView<PlayerId>>().0;
+            let fovs = world.borrow::<View<FieldOfView>>();
+            let positions = monster_positions(&world);
+
+            assert!(positions.iter().all(|&pos| !fovs.get(player_id).get(pos)));
+            spawned += positions.len();
+        }
+
+        // The only room is also the starting room, but it isn't left empty.
+        assert!(spawned > 0);
+    }
+
+    #[test]
+    fn boss_floor_has_one_boss_away_from_the_player() {
+        for map_generator in [MapGenerator::Rooms, MapGenerator::Bsp, MapGenerator::Caves] {
+            let options = Options {
+                map_generator,
+                ..Default::default()
+            };
+            let (world, player_id) = new_level(7, 25, options);
+            let (bosses, coords, monsters) =
+                world.borrow::<(View<Boss>, View<Coord>, View<Monster>)>();
+            let boss_pos: Vec<(i32, i32)> = (&coords, &bosses)
+                .iter()
+                .map(|(coord, _)| coord.0.into())
+                .collect();
+            let player_pos: (i32, i32) = coords.get(player_id).0.into();
+
+            assert_eq!(bosses.len(), 1);
+            assert_eq!(monsters.len(), 1);
+            assert_ne!(boss_pos[0], player_pos);
+        }
+    }
+
+    #[test]
+    fn wandering_monsters_come_sooner_deeper_down() {
+        assert_eq!(wandering_monster_period(0), WANDERING_MONSTER_PERIOD);
+        assert_eq!(wandering_monster_period(1), WANDERING_MONSTER_PERIOD);
+        assert_eq!(
+            wandering_monster_period(2),
+            WANDERING_MONSTER_PERIOD - WANDERING_MONSTER_PERIOD_STEP
+        );
+        assert!((1..30).all(|d| wandering_monster_period(d + 1) <= wandering_monster_period(d)));
+        assert_eq!(wandering_monster_period(100), MIN_WANDERING_MONSTER_PERIOD);
+        assert_eq!(
+            wandering_monster_period(i32::MAX),
+            MIN_WANDERING_MONSTER_PERIOD
+        );
+    }
+
+    #[test]
+    fn wandering_monster_cap_respects_options() {
+        let cap = |max_monsters_per_level| {
+            wandering_monster_cap(&Options {
+                max_monsters_per_level,
+                ..Options::default()
+            })
+        };
+
+        assert_eq!(cap(60), WANDERING_MONSTER_CAP);
+        assert_eq!(cap(5), 5);
+        assert_eq!(cap(0), 0);
+    }
+
+    #[test]
+    fn generated_levels_never_exceed_the_monster_cap() {
+        let monster_count = |seed, max_monsters_per_level| {
+            let options = Options {
+                max_monsters_per_level,
+                ..Default::default()
+            };
+            let (world, _) = new_level(seed, 8, options);
+
+            monster_positions(&world).len()
+        };
+
+        for seed in 0..10 {
+            assert_eq!(monster_count(seed, 0), 0);
+            assert!(monster_count(seed, 3) <= 3);
+            assert!(monster_count(seed, 7) <= 7);
+        }
+
+        // The cap isn't satisfied just by levels being sparse to begin with.
+        assert!((0..10).any(|seed| monster_count(seed, 100) > 7));
+    }
+
+    #[test]
+    fn deeper_levels_favor_packs_and_lone_monsters() {
+        let shallow = spawn_kind_table_for_depth(1);
+        let deep = spawn_kind_table_for_depth(9);
+        let is_pack = |k: &SpawnKind| matches!(k, SpawnKind::Pack);
+        let is_lone = |k: &SpawnKind| matches!(k, SpawnKind::Lone);
+
+        assert!(weight_of(&deep, is_pack) > weight_of(&shallow, is_pack));
+        assert_eq!(weight_of(&shallow, is_lone), 0);
+        assert!(weight_of(&deep, is_lone) > 0);
+        assert!(spawn_kind_table_for_depth(LONE_MONSTER_MIN_DEPTH - 1)
+            .iter()
+            .all(|entry| !is_lone(&entry.kind)));
+    }
+
+    #[test]
+    fn bumping_a_mimic_reveals_it() {
+        let world = test_world(20, 20, (5, 5));
+        let mut rng = GameRng::seed_from_u64(3);
+
+        spawn_mimic(&world, &mut rng, (6, 5), 3);
+
+        let mimic_id = world
+            .borrow::<UniqueView<Map>>()
+            .iter_entities_at(6, 5)
+            .next();
+        let mimic_id = mimic_id.unwrap();
+
+        assert!(world.borrow::<View<Disguise>>().contains(mimic_id));
+        assert_ne!(world.borrow::<View<Name>>().get(mimic_id).0, "Mimic");
+
+        player::apply_action(
+            &world,
+            PlayerAction::Move {
+                dx: 1,
+                dy: 0,
+                run: false,
+            },
+        );
+
+        let (disguises, names, renderables) =
+            world.borrow::<(View<Disguise>, View<Name>, View<Renderable>)>();
+
+        assert!(!disguises.contains(mimic_id));
+        assert_eq!(names.get(mimic_id).0, "Mimic");
+        assert!(renderables.get(mimic_id).sym == GameSym::Mimic);
+        assert!(renderables.get(mimic_id).fg == MIMIC_COLOR);
+        assert!(world
+            .borrow::<UniqueView<Messages>>()
+            .rev_iter()
+            .any(|(msg, ..)| msg.ends_with("is really a mimic!")));
+    }
+
+    #[test]
+    fn goblins_spawn_in_each_shade_of_green() {
+        let world = World::new();
+        let mut rng = GameRng::seed_from_u64(7);
+
+        world.add_unique(Map::new(10, 10));
+        for x in 0..10 {
+            spawn_monster_of_level(&world, &mut rng, (x, 0), 5);
+            spawn_monster_of_level(&world, &mut rng, (x, 1), 5);
+        }
+
+        let (names, renderables) = world.borrow::<(View<Name>, View<Renderable>)>();
+        let mut shades = (&names, &renderables)
+            .iter()
+            .map(|(name, render)| {
+                assert_eq!(name.0, "Goblin");
+                (render.fg.r, render.fg.g, render.fg.b)
+            })
+            .collect::<Vec<_>>();
+
+        shades.sort_unstable();
+        shades.dedup();
+        assert_eq!(shades, [(28, 158, 50), (34, 187, 59), (39, 215, 67)]);
+    }
+
+    #[test]
+    fn shades_stay_within_color_bounds() {
+        let bright = shade_color(Color::WHITE, 115);
+        let dim = shade_color(Color::WHITE, 85);
+
+        assert_eq!((bright.r, bright.g, bright.b), (255, 255, 255));
+        assert_eq!((dim.r, dim.g, dim.b), (216, 216, 216));
+    }
+}
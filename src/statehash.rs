@@ -0,0 +1,148 @@
+use shipyard::{IntoIter, Shiperator, UniqueView, View, World};
+use std::hash::Hasher;
+use wyhash::WyHash;
+
+use crate::{
+    components::{CombatStats, Coord},
+    magicnum,
+    map::Map,
+    player::PlayerId,
+    GameSeed, TurnCount,
+};
+
+/// Hash the parts of the game state that decide how the game plays out from here, so that tests can
+/// compare states compactly.
+///
+/// Random number generators are seeded from the game seed, depth and turn count whenever they're
+/// needed, so hashing those covers them too.  Entities are hashed in order of their positions and
+/// stats instead of their entity IDs, so the hash doesn't depend on how the ECS stores them.
+pub fn world_hash(world: &World) -> u64 {
+    let (game_seed, turn_count, map, player_id, combat_stats, coords) = world.borrow::<(
+        UniqueView<GameSeed>,
+        UniqueView<TurnCount>,
+        UniqueView<Map>,
+        UniqueView<PlayerId>,
+        View<CombatStats>,
+        View<Coord>,
+    )>();
+    let mut hasher = WyHash::with_seed(magicnum::WORLD_HASH);
+
+    hasher.write_u64(game_seed.0);
+    hasher.write_u64(turn_count.0);
+    hasher.write_i32(map.depth);
+
+    // Explored tiles, packed into bytes.
+    let mut seen_byte = 0u8;
+    let mut seen_bit_count = 0;
+    for y in 0..map.height {
+        for x in 0..map.width {
+            seen_byte = seen_byte << 1 | map.seen.get_bit(x, y) as u8;
+            seen_bit_count += 1;
+            if seen_bit_count == 8 {
+                hasher.write_u8(seen_byte);
+                seen_byte = 0;
+                seen_bit_count = 0;
+            }
+        }
+    }
+    hasher.write_u8(seen_byte);
+
+    // The player and monsters, as (x, y, is_player, hp, max_hp).
+    let mut fighters = (&coords, &combat_stats)
+        .iter()
+        .with_id()
+        .map(|(id, (coord, stats))| {
+            (
+                coord.0.x,
+                coord.0.y,
+                id == player_id.0,
+                stats.hp,
+                stats.max_hp,
+            )
+        })
+        .collect::<Vec<_>>();
+
+    fighters.sort_unstable();
+    hasher.write_usize(fighters.len());
+    for (x, y, is_player, hp, max_hp) in fighters {
+        hasher.write_i32(x);
+        hasher.write_i32(y);
+        hasher.write_u8(is_player as u8);
+        hasher.write_i32(hp);
+        hasher.write_i32(max_hp);
+    }
+
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use shipyard::{EntitiesViewMut, EntityId, Get, ViewMut};
+
+    use super::*;
+
+    fn stats(hp: i32) -> CombatStats {
+        CombatStats {
+            max_hp: 20,
+            hp,
+            attack: 4.0,
+            defense: 2.0,
+        }
+    }
+
+    /// Build a small world with a player and two monsters, adding the monsters in the given
+    /// order, and return it along with the ID of the first monster.
+    fn make_world(monsters: &[((i32, i32), i32)]) -> (World, EntityId) {
+        let world = World::new();
+
+        world.add_unique(GameSeed(1234));
+        world.add_unique(TurnCount(56));
+        world.add_unique(Map::new(10, 10));
+
+        let ids = world.run(
+            |mut entities: EntitiesViewMut,
+             mut combat_stats: ViewMut<CombatStats>,
+             mut coords: ViewMut<Coord>| {
+                let player_id = entities.add_entity(
+                    (&mut coords, &mut combat_stats),
+                    (Coord((1, 1).into()), stats(20)),
+                );
+                let monster_ids = monsters
+                    .iter()
+                    .map(|&(pos, hp)| {
+                        entities.add_entity(
+                            (&mut coords, &mut combat_stats),
+                            (Coord(pos.into()), stats(hp)),
+                        )
+                    })
+                    .collect::<Vec<_>>();
+
+                (player_id, monster_ids[0])
+            },
+        );
+
+        world.add_unique(PlayerId(ids.0));
+
+        (world, ids.1)
+    }
+
+    #[test]
+    fn identical_states_hash_equal_regardless_of_entity_order() {
+        let (a, _) = make_world(&[((3, 4), 10), ((7, 2), 5)]);
+        let (b, _) = make_world(&[((7, 2), 5), ((3, 4), 10)]);
+
+        assert_eq!(world_hash(&a), world_hash(&b));
+    }
+
+    #[test]
+    fn hp_change_changes_hash() {
+        let (world, monster_id) = make_world(&[((3, 4), 10), ((7, 2), 5)]);
+        let before = world_hash(&world);
+
+        (&mut world.borrow::<ViewMut<CombatStats>>())
+            .get(monster_id)
+            .hp -= 1;
+
+        assert_ne!(world_hash(&world), before);
+    }
+}
@@ -0,0 +1,173 @@
+use shipyard::{
+    EntityId, Get, IntoIter, Shiperator, UniqueView, UniqueViewMut, View, ViewMut, World,
+};
+
+use crate::{
+    components::{Confusion, Coord, FieldOfView, HurtBy, Name, Poison},
+    damage::DamageQueue,
+    message::Messages,
+    player::PlayerId,
+};
+
+/// A status effect component that lasts for a limited number of turns.
+pub trait TimedEffect: 'static + Send + Sync {
+    /// Turns left until the effect wears off.
+    fn remaining_mut(&mut self) -> &mut i32;
+
+    /// Apply the effect to the entity that has it for one turn.
+    fn on_tick(&self, _world: &World, _who: EntityId) {}
+
+    /// Message shown when the effect wears off of the named entity.
+    fn expired_msg(&self, name: &str) -> String;
+}
+
+impl TimedEffect for Confusion {
+    fn remaining_mut(&mut self) -> &mut i32 {
+        &mut self.turns
+    }
+
+    fn expired_msg(&self, name: &str) -> String {
+        format!("{} is no longer confused.", name)
+    }
+}
+
+impl TimedEffect for Poison {
+    fn remaining_mut(&mut self) -> &mut i32 {
+        &mut self.turns
+    }
+
+    fn on_tick(&self, world: &World, who: EntityId) {
+        world
+            .borrow::<UniqueViewMut<DamageQueue>>()
+            .push(who, self.damage, HurtBy::Poison);
+    }
+
+    fn expired_msg(&self, name: &str) -> String {
+        format!("{} is no longer poisoned.", name)
+    }
+}
+
+/// Apply one turn of a kind of timed effect to every entity that has it, removing it from any
+/// entity whose effect has run out.
+fn tick_effect<T: TimedEffect>(world: &World) {
+    let ids = world
+        .borrow::<View<T>>()
+        .iter()
+        .with_id()
+        .map(|(id, _)| id)
+        .collect::<Vec<_>>();
+
+    for id in ids {
+        let mut effects = world.borrow::<ViewMut<T>>();
+        let effect = (&mut effects).get(id);
+
+        effect.on_tick(world, id);
+
+        let remaining = effect.remaining_mut();
+        *remaining -= 1;
+
+        if *remaining <= 0 {
+            let (mut msgs, player_id, coords, fovs, names) = world.borrow::<(
+                UniqueViewMut<Messages>,
+                UniqueView<PlayerId>,
+                View<Coord>,
+                View<FieldOfView>,
+                View<Name>,
+            )>();
+            let show_msg = if id == player_id.0 {
+                true
+            } else if let Ok(coord) = coords.try_get(id) {
+                fovs.get(player_id.0).get(coord.0.into())
+            } else {
+                false
+            };
+
+            if show_msg {
+                msgs.add(effect.expired_msg(&names.get(id).0));
+            }
+            effects.remove(id);
+        }
+    }
+}
+
+/// Count down all timed status effects by a turn, applying their per-turn effects.
+pub fn tick_status_effects(world: &World) {
+    tick_effect::<Confusion>(world);
+    tick_effect::<Poison>(world);
+}
+
+#[cfg(test)]
+mod tests {
+    use shipyard::EntitiesViewMut;
+
+    use super::*;
+    use crate::{
+        components::{CombatStats, Player},
+        damage,
+    };
+
+    #[test]
+    fn poison_hurts_for_exactly_its_duration() {
+        let world = World::new();
+
+        world.add_unique(DamageQueue::new());
+        world.add_unique(Messages::new(10, 5));
+
+        let player_id = world.run(
+            |mut entities: EntitiesViewMut,
+             mut combat_stats: ViewMut<CombatStats>,
+             mut coords: ViewMut<Coord>,
+             mut fovs: ViewMut<FieldOfView>,
+             mut names: ViewMut<Name>,
+             mut players: ViewMut<Player>,
+             mut poisons: ViewMut<Poison>| {
+                entities.add_entity(
+                    (
+                        &mut combat_stats,
+                        &mut coords,
+                        &mut fovs,
+                        &mut names,
+                        &mut players,
+                        &mut poisons,
+                    ),
+                    (
+                        CombatStats {
+                            max_hp: 20,
+                            hp: 20,
+                            attack: 1.0,
+                            defense: 1.0,
+                        },
+                        Coord((1, 1).into()),
+                        FieldOfView::new(4),
+                        Name("Player".into()),
+                        Player {
+                            auto_run: None,
+                            low_hp_warned: false,
+                            damage_previewed: None,
+                        },
+                        Poison {
+                            damage: 2,
+                            turns: 3,
+                        },
+                    ),
+                )
+            },
+        );
+
+        world.add_unique(PlayerId(player_id));
+
+        let mut hps = Vec::new();
+        for _ in 0..5 {
+            tick_status_effects(&world);
+            world.run(damage::apply_damage_queue);
+            hps.push(world.borrow::<View<CombatStats>>().get(player_id).hp);
+        }
+
+        assert_eq!(hps, [18, 16, 14, 14, 14]);
+        assert!(!world.borrow::<View<Poison>>().contains(player_id));
+        assert!(world
+            .borrow::<UniqueView<Messages>>()
+            .rev_iter()
+            .any(|(msg, ..)| msg == "Player is no longer poisoned."));
+    }
+}
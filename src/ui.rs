@@ -1,4 +1,6 @@
+use serde::{Deserialize, Serialize};
 use shipyard::{Get, UniqueView, View, World};
+use std::collections::HashMap;
 
 use crate::{
     chunked::ChunkedMapGrid,
@@ -21,11 +23,189 @@ pub const SELECTED_BG: Color = Color {
     b: 255,
 };
 
+/// Health bar color when the player's hit points are above `HP_MODERATE_PERCENT`.
+const HP_HEALTHY_COLOR: Color = Color { r: 0, g: 128, b: 0 };
+
+/// Health bar color when the player's hit points are between the two thresholds.
+const HP_MODERATE_COLOR: Color = Color {
+    r: 160,
+    g: 128,
+    b: 0,
+};
+
+/// Health bar color when the player's hit points are at or below `HP_CRITICAL_PERCENT`.
+const HP_CRITICAL_COLOR: Color = Color { r: 192, g: 0, b: 0 };
+
+/// Percentage of maximum hit points and below that the health bar is shown as moderate.
+const HP_MODERATE_PERCENT: i32 = 60;
+
+/// Percentage of maximum hit points and below that the health bar is shown as critical.
+pub const HP_CRITICAL_PERCENT: i32 = 30;
+
+/// Number of tilesets that the map can be drawn with.
+pub const NUM_TILESETS: u32 = 3;
+
+/// Number of tilesets, starting from the first, that are fonts that text can be drawn with.
+pub const NUM_FONTS: u32 = 2;
+
+/// Largest zoom factor that the map and text can be drawn at.
+pub const MAX_ZOOM: u32 = 4;
+
+/// Shortest range that the player's field of view can be set to in the options.
+pub const MIN_FOV_RANGE: u32 = 4;
+
+/// Longest range that the player's field of view can be set to in the options.
+pub const MAX_FOV_RANGE: u32 = 12;
+
+/// Step by which the hit point percentage to rest until can be set in the options, which is also
+/// the lowest it can be set to.
+pub const REST_UNTIL_STEP: u32 = 10;
+
+/// Longest delay that can be set in the options between each monster's actions.
+pub const MAX_MONSTER_ACTION_DELAY: u32 = 9;
+
+/// Step by which the most monsters per level can be set in the options, which is also the fewest
+/// it can be set to.
+pub const MAX_MONSTERS_STEP: u32 = 10;
+
+/// Most monsters per level that can be set in the options.
+pub const MAX_MONSTERS_LIMIT: u32 = 100;
+
+/// How walls are drawn on the map.
+#[derive(Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum WallStyle {
+    /// Lines that connect to neighboring walls.
+    Lines,
+    /// Hash signs.
+    Hash,
+    /// Solid blocks.
+    Block,
+}
+
+/// How the layout of each new level is generated.
+#[derive(Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum MapGenerator {
+    /// Rectangular rooms scattered across the level, joined by corridors.
+    Rooms,
+    /// Rooms carved out of a binary space partition of the level, joined along the partitions.
+    Bsp,
+    /// Winding natural caves.
+    Caves,
+}
+
+/// How remembered tiles outside of the player's field of view are drawn.
+#[derive(Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum RevealStyle {
+    /// Darkened shades of gray.
+    Dim,
+    /// A single flat gray, for higher contrast.
+    Monochrome,
+    /// Not at all, so that only visible tiles are shown.
+    Hidden,
+}
+
+/// Shape of the player's field of view.
+#[derive(Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum VisionShape {
+    Circle,
+    Square,
+    Diamond,
+}
+
+/// Game options chosen by the player, saved in the config file so they persist across sessions.
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(default)]
 pub struct Options {
     pub tileset: u32,
     pub font: u32,
     pub map_zoom: u32,
     pub text_zoom: u32,
+    pub wandering_monsters: bool,
+    /// Animate monsters sliding between tiles instead of having them jump instantly.
+    pub smooth_scroll: bool,
+    /// Animate attackers lunging towards whoever they're attacking.
+    pub lunge_animations: bool,
+    /// Flash the screen when hit points run low and reveal each new level outwards from the player.
+    pub screen_effects: bool,
+    /// Allow the last turn to be taken back, for casual play.
+    pub allow_undo: bool,
+    /// Percentage of maximum hit points that resting stops at.
+    pub rest_until_percent: u32,
+    /// Frames to wait between the animations of each monster that acts in a turn.
+    pub monster_action_delay: u32,
+    /// Describe the health of monsters with exact hit points instead of rough descriptions.
+    pub show_exact_hp: bool,
+    /// Allow the player to automatically step away from visible monsters, as an assist.
+    pub assist_retreat: bool,
+    /// Show the damage range of a melee attack the first time the player bumps into a monster.
+    pub show_damage_preview: bool,
+    pub wall_style: WallStyle,
+    pub vision_shape: VisionShape,
+    /// How many tiles away the player can see.
+    pub fov_range: u32,
+    pub reveal_style: RevealStyle,
+    /// Save on quitting in a way that can only be loaded once, for interrupting a run without
+    /// being able to reload it after dying.
+    pub quicksave_on_exit: bool,
+    /// Most monsters that a new level can be generated with, to keep levels from being crowded.
+    pub max_monsters_per_level: u32,
+    /// Monsters won't spawn within this many tiles of where the player starts a level.
+    pub safe_spawn_radius: u32,
+    /// Forbid stepping diagonally between two walls that meet at a corner.
+    pub strict_diagonals: bool,
+    /// How the layout of each new level is generated, leaving the current level as it is.
+    pub map_generator: MapGenerator,
+    /// Foreground colors to draw entities with in place of the usual colors for their symbols.
+    pub color_overrides: HashMap<GameSym, Color>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            tileset: 2,
+            font: 0,
+            map_zoom: 1,
+            text_zoom: 1,
+            wandering_monsters: false,
+            smooth_scroll: false,
+            lunge_animations: false,
+            screen_effects: false,
+            allow_undo: false,
+            rest_until_percent: 100,
+            monster_action_delay: 0,
+            show_exact_hp: false,
+            assist_retreat: false,
+            show_damage_preview: false,
+            wall_style: WallStyle::Lines,
+            vision_shape: VisionShape::Circle,
+            fov_range: 8,
+            reveal_style: RevealStyle::Dim,
+            quicksave_on_exit: false,
+            max_monsters_per_level: 60,
+            safe_spawn_radius: 6,
+            strict_diagonals: false,
+            map_generator: MapGenerator::Rooms,
+            color_overrides: HashMap::new(),
+        }
+    }
+}
+
+impl Options {
+    /// Bring any options that are out of bounds back into them, e.g. from a hand-edited config
+    /// file.
+    pub fn clamp_to_limits(&mut self) {
+        self.tileset = self.tileset.min(NUM_TILESETS - 1);
+        self.font = self.font.min(NUM_FONTS - 1);
+        self.map_zoom = self.map_zoom.clamp(1, MAX_ZOOM);
+        self.text_zoom = self.text_zoom.clamp(1, MAX_ZOOM);
+        self.fov_range = self.fov_range.clamp(MIN_FOV_RANGE, MAX_FOV_RANGE);
+        self.rest_until_percent = (self.rest_until_percent / REST_UNTIL_STEP * REST_UNTIL_STEP)
+            .clamp(REST_UNTIL_STEP, 100);
+        self.monster_action_delay = self.monster_action_delay.min(MAX_MONSTER_ACTION_DELAY);
+        self.max_monsters_per_level = (self.max_monsters_per_level / MAX_MONSTERS_STEP
+            * MAX_MONSTERS_STEP)
+            .clamp(MAX_MONSTERS_STEP, MAX_MONSTERS_LIMIT);
+    }
 }
 
 pub const MAP_GRID: usize = 0;
@@ -34,6 +214,19 @@ pub const ITEM_GRID: usize = 2;
 pub const MSG_FRAME_GRID: usize = 3;
 pub const MSG_GRID: usize = 4;
 
+/// Pick a health bar color based on what fraction of maximum hit points remain.
+fn hp_bar_color(hp: i32, max_hp: i32) -> Color {
+    let percent = if max_hp > 0 { hp * 100 / max_hp } else { 0 };
+
+    if percent <= HP_CRITICAL_PERCENT {
+        HP_CRITICAL_COLOR
+    } else if percent <= HP_MODERATE_PERCENT {
+        HP_MODERATE_COLOR
+    } else {
+        HP_HEALTHY_COLOR
+    }
+}
+
 fn draw_status<Y: Symbol>(world: &World, grid: &mut TileGrid<Y>) {
     let player_id = world.borrow::<UniqueView<PlayerId>>();
 
@@ -107,7 +300,7 @@ fn draw_status<Y: Symbol>(world: &World, grid: &mut TileGrid<Y>) {
             0,
             player_stats.hp,
             player_stats.max_hp,
-            Color { r: 192, g: 0, b: 0 },
+            hp_bar_color(player_stats.hp, player_stats.max_hp),
             None,
         );
         grid.print_color(
@@ -254,7 +447,7 @@ where
         (Color::DARK_GRAY, Color::GRAY)
     };
 
-    for (message, highlighted) in messages.rev_iter() {
+    for (message, highlighted, color, _) in messages.recent_rev_iter() {
         if y > max_y {
             break;
         }
@@ -264,7 +457,11 @@ where
             continue;
         }
 
-        let msg_fg = if highlighted { highlight_fg } else { fg };
+        let msg_fg = if highlighted {
+            color.filter(|_| active).unwrap_or(highlight_fg)
+        } else {
+            fg
+        };
 
         grid.put_char_color((0, y), '>', msg_fg, None);
         for line in ruggrogue::word_wrap(message, width) {
@@ -301,6 +498,50 @@ pub fn draw_ui<Y: Symbol>(
     }
 }
 
+/// Zoom, pixel width and grid sizes of the sidebar beside the dungeon map.
+struct SidebarLayout {
+    text_zoom: u32,
+    px_w: u32,
+    status_size: Size,
+    item_size: Size,
+    msg_frame_size: Size,
+    msg_size: Size,
+}
+
+/// Lay out the sidebar for text tiles of the given size, reducing the text zoom if the sidebar
+/// would otherwise be wider than the window.
+fn sidebar_layout(tile_size: Size, text_zoom: u32, window_size: Size) -> SidebarLayout {
+    let sidebar_w = 36;
+    let text_zoom = text_zoom
+        .min(window_size.w / (sidebar_w * tile_size.w))
+        .max(1);
+
+    let status_size = Size {
+        w: sidebar_w,
+        h: 10,
+    };
+    let item_size = Size { w: sidebar_w, h: 4 };
+    let msg_frame_size = Size {
+        w: sidebar_w,
+        h: (window_size.h / (tile_size.h * text_zoom))
+            .saturating_sub(status_size.h + item_size.h)
+            .max(4),
+    };
+    let msg_size = Size {
+        w: msg_frame_size.w.saturating_sub(2).max(1),
+        h: msg_frame_size.h.saturating_sub(2).clamp(1, 100),
+    };
+
+    SidebarLayout {
+        text_zoom,
+        px_w: sidebar_w * tile_size.w * text_zoom,
+        status_size,
+        item_size,
+        msg_frame_size,
+        msg_size,
+    }
+}
+
 /// Prepares grids to display the dungeon map and user interface.
 pub fn prepare_main_grids<Y: Symbol>(
     chunked_map_grid: &mut ChunkedMapGrid,
@@ -319,24 +560,21 @@ pub fn prepare_main_grids<Y: Symbol>(
         .get(ui_tileset_index as usize)
         .unwrap_or(&tilesets[0]);
 
-    let sidebar_w = 36;
-    let sidebar_px_w = sidebar_w * ui_tileset.tile_width() * text_zoom;
-
-    let new_status_size = Size {
-        w: sidebar_w,
-        h: 10,
-    };
-    let new_item_size = Size { w: sidebar_w, h: 4 };
-    let new_msg_frame_size = Size {
-        w: sidebar_w,
-        h: (window_size.h / (ui_tileset.tile_height() * text_zoom))
-            .saturating_sub(new_status_size.h + new_item_size.h)
-            .max(4),
-    };
-    let new_msg_size = Size {
-        w: new_msg_frame_size.w.saturating_sub(2).max(1),
-        h: new_msg_frame_size.h.saturating_sub(2).max(1).min(100),
-    };
+    let SidebarLayout {
+        text_zoom,
+        px_w: sidebar_px_w,
+        status_size: new_status_size,
+        item_size: new_item_size,
+        msg_frame_size: new_msg_frame_size,
+        msg_size: new_msg_size,
+    } = sidebar_layout(
+        Size {
+            w: ui_tileset.tile_width(),
+            h: ui_tileset.tile_height(),
+        },
+        text_zoom,
+        window_size,
+    );
 
     if !grids.is_empty() {
         // MAP_GRID resizing is handled by ChunkedMapGrid::prepare_grid below.
@@ -436,3 +674,47 @@ pub fn prepare_main_grids<Y: Symbol>(
     };
     grids[MSG_GRID].view.zoom = text_zoom;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FONT_TILE: Size = Size { w: 8, h: 8 };
+
+    #[test]
+    fn sidebar_layout_at_high_zoom() {
+        let window = Size { w: 1920, h: 1080 };
+
+        let x3 = sidebar_layout(FONT_TILE, 3, window);
+        assert_eq!(x3.text_zoom, 3);
+        assert_eq!(x3.px_w, 36 * 8 * 3);
+        assert_eq!((x3.msg_frame_size.w, x3.msg_frame_size.h), (36, 31));
+        assert_eq!((x3.msg_size.w, x3.msg_size.h), (34, 29));
+
+        let x4 = sidebar_layout(FONT_TILE, 4, window);
+        assert_eq!(x4.text_zoom, 4);
+        assert_eq!(x4.px_w, 36 * 8 * 4);
+        assert_eq!((x4.msg_frame_size.w, x4.msg_frame_size.h), (36, 19));
+        assert_eq!((x4.msg_size.w, x4.msg_size.h), (34, 17));
+    }
+
+    #[test]
+    fn sidebar_zoom_is_reduced_to_fit_the_window() {
+        let layout = sidebar_layout(FONT_TILE, 4, Size { w: 1024, h: 768 });
+
+        assert_eq!(layout.text_zoom, 3);
+        assert!(layout.px_w <= 1024);
+    }
+
+    #[test]
+    fn hp_bar_color_follows_thresholds() {
+        assert!(hp_bar_color(100, 100) == HP_HEALTHY_COLOR);
+        assert!(hp_bar_color(61, 100) == HP_HEALTHY_COLOR);
+        assert!(hp_bar_color(60, 100) == HP_MODERATE_COLOR);
+        assert!(hp_bar_color(31, 100) == HP_MODERATE_COLOR);
+        assert!(hp_bar_color(30, 100) == HP_CRITICAL_COLOR);
+        assert!(hp_bar_color(0, 100) == HP_CRITICAL_COLOR);
+        assert!(hp_bar_color(-5, 100) == HP_CRITICAL_COLOR);
+        assert!(hp_bar_color(0, 0) == HP_CRITICAL_COLOR);
+    }
+}
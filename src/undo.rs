@@ -0,0 +1,339 @@
+use shipyard::{
+    EntitiesView, EntityId, Get, IntoIter, Shiperator, UniqueView, UniqueViewMut, View, ViewMut,
+    World,
+};
+
+use crate::{
+    bitgrid::BitGrid,
+    components::{
+        Asleep, BlocksTile, CombatStats, Confusion, Coord, Equipment, Fearful, FieldOfView,
+        Inventory, Monster, Name, Player, Poison, Stomach, Tally,
+    },
+    map::Map,
+    message::Messages,
+    TurnCount,
+};
+use ruggrogue::util::Position;
+
+/// The minimal game state needed to take back a player action that only moved things around and
+/// hurt things, i.e. one that didn't kill, spawn, use up, pick up, drop or equip anything.
+pub struct UndoSnapshot {
+    depth: i32,
+    turn_count: u64,
+    /// Every named entity, i.e. every monster and item, including those that are being carried.
+    named: Vec<EntityId>,
+    inventories: Vec<(EntityId, Vec<EntityId>)>,
+    equipments: Vec<(EntityId, Option<EntityId>, Option<EntityId>)>,
+    coords: Vec<(EntityId, Position)>,
+    combat_stats: Vec<(EntityId, CombatStats)>,
+    stomachs: Vec<(EntityId, Stomach)>,
+    tallies: Vec<(EntityId, Tally)>,
+    monsters: Vec<(EntityId, Monster)>,
+    fearfuls: Vec<(EntityId, Fearful)>,
+    /// Status effects can start and wear off during a turn, so these are restored outright.
+    poisons: Vec<(EntityId, Poison)>,
+    confusions: Vec<(EntityId, Confusion)>,
+    asleeps: Vec<(EntityId, Asleep)>,
+    low_hp_warned: Vec<(EntityId, bool)>,
+    seen: BitGrid,
+    messages: Messages,
+}
+
+/// Copy every component in a storage along with the entity it belongs to.
+fn components_of<T: Clone + 'static>(storage: &View<T>) -> Vec<(EntityId, T)> {
+    storage
+        .iter()
+        .with_id()
+        .map(|(id, c)| (id, c.clone()))
+        .collect()
+}
+
+/// Replace every component in a storage with the ones copied by [components_of].
+fn replace_components<T: Clone + 'static>(
+    entities: &EntitiesView,
+    storage: &mut ViewMut<T>,
+    components: &[(EntityId, T)],
+) {
+    let ids = (&*storage)
+        .iter()
+        .with_id()
+        .map(|(id, _)| id)
+        .collect::<Vec<_>>();
+
+    for id in ids {
+        storage.remove(id);
+    }
+    for (id, c) in components {
+        entities.add_component(&mut *storage, c.clone(), *id);
+    }
+}
+
+/// Capture the current state of the game for undoing later.
+pub fn take_snapshot(world: &World) -> UndoSnapshot {
+    let (map, turn_count, messages, combat_stats, coords, stomachs, tallies) = world.borrow::<(
+        UniqueView<Map>,
+        UniqueView<TurnCount>,
+        UniqueView<Messages>,
+        View<CombatStats>,
+        View<Coord>,
+        View<Stomach>,
+        View<Tally>,
+    )>();
+    let (names, inventories, equipments) =
+        world.borrow::<(View<Name>, View<Inventory>, View<Equipment>)>();
+    let (monsters, fearfuls, poisons, confusions, asleeps, players) = world.borrow::<(
+        View<Monster>,
+        View<Fearful>,
+        View<Poison>,
+        View<Confusion>,
+        View<Asleep>,
+        View<Player>,
+    )>();
+
+    UndoSnapshot {
+        depth: map.depth,
+        turn_count: turn_count.0,
+        named: names.iter().with_id().map(|(id, _)| id).collect(),
+        inventories: inventories
+            .iter()
+            .with_id()
+            .map(|(id, inv)| (id, inv.items.clone()))
+            .collect(),
+        equipments: equipments
+            .iter()
+            .with_id()
+            .map(|(id, equip)| (id, equip.weapon, equip.armor))
+            .collect(),
+        coords: coords.iter().with_id().map(|(id, c)| (id, c.0)).collect(),
+        combat_stats: combat_stats
+            .iter()
+            .with_id()
+            .map(|(id, s)| (id, s.clone()))
+            .collect(),
+        stomachs: stomachs
+            .iter()
+            .with_id()
+            .map(|(id, s)| (id, s.clone()))
+            .collect(),
+        tallies: tallies
+            .iter()
+            .with_id()
+            .map(|(id, t)| (id, t.clone()))
+            .collect(),
+        monsters: components_of(&monsters),
+        fearfuls: components_of(&fearfuls),
+        poisons: components_of(&poisons),
+        confusions: components_of(&confusions),
+        asleeps: components_of(&asleeps),
+        low_hp_warned: players
+            .iter()
+            .with_id()
+            .map(|(id, p)| (id, p.low_hp_warned))
+            .collect(),
+        seen: map.seen.clone(),
+        messages: messages.clone(),
+    }
+}
+
+/// Returns true if the game can be restored to the snapshot without leaving anything behind that
+/// the snapshot doesn't know about.
+fn can_restore(world: &World, snapshot: &UndoSnapshot) -> bool {
+    let (entities, map, coords) = world.borrow::<(EntitiesView, UniqueView<Map>, View<Coord>)>();
+    let (names, inventories, equipments) =
+        world.borrow::<(View<Name>, View<Inventory>, View<Equipment>)>();
+
+    snapshot.depth == map.depth
+        && snapshot.coords.len() == coords.len()
+        && snapshot
+            .coords
+            .iter()
+            .all(|&(id, _)| entities.is_alive(id) && coords.contains(id))
+        // Used up items are deleted, which can't be taken back.
+        && snapshot.named.len() == names.len()
+        && snapshot
+            .named
+            .iter()
+            .all(|&id| entities.is_alive(id) && names.contains(id))
+        && snapshot.inventories.iter().all(|(id, items)| {
+            inventories
+                .try_get(*id)
+                .is_ok_and(|inv| inv.items == *items)
+        })
+        && snapshot.equipments.iter().all(|&(id, weapon, armor)| {
+            equipments
+                .try_get(id)
+                .is_ok_and(|equip| equip.weapon == weapon && equip.armor == armor)
+        })
+}
+
+/// Restore the game to the state captured in the snapshot, returning false if that isn't possible.
+pub fn restore_snapshot(world: &World, snapshot: &UndoSnapshot) -> bool {
+    if !can_restore(world, snapshot) {
+        return false;
+    }
+
+    {
+        let (mut map, blocks_tile, mut coords, mut fovs) = world.borrow::<(
+            UniqueViewMut<Map>,
+            View<BlocksTile>,
+            ViewMut<Coord>,
+            ViewMut<FieldOfView>,
+        )>();
+
+        for &(id, pos) in snapshot.coords.iter() {
+            let coord = (&mut coords).get(id);
+
+            if coord.0 != pos {
+                map.move_entity(id, coord.0.into(), pos.into(), blocks_tile.contains(id));
+                coord.0 = pos;
+
+                if let Ok(fov) = (&mut fovs).try_get(id) {
+                    fov.dirty = true;
+                }
+            }
+        }
+    }
+
+    {
+        let (mut combat_stats, mut stomachs, mut tallies) =
+            world.borrow::<(ViewMut<CombatStats>, ViewMut<Stomach>, ViewMut<Tally>)>();
+
+        for (id, stats) in snapshot.combat_stats.iter() {
+            if let Ok(current) = (&mut combat_stats).try_get(*id) {
+                *current = stats.clone();
+            }
+        }
+        for (id, stomach) in snapshot.stomachs.iter() {
+            if let Ok(current) = (&mut stomachs).try_get(*id) {
+                *current = stomach.clone();
+            }
+        }
+        for (id, tally) in snapshot.tallies.iter() {
+            if let Ok(current) = (&mut tallies).try_get(*id) {
+                *current = tally.clone();
+            }
+        }
+    }
+
+    {
+        let entities = world.borrow::<EntitiesView>();
+        let (mut monsters, mut fearfuls, mut poisons, mut confusions, mut asleeps, mut players) =
+            world.borrow::<(
+                ViewMut<Monster>,
+                ViewMut<Fearful>,
+                ViewMut<Poison>,
+                ViewMut<Confusion>,
+                ViewMut<Asleep>,
+                ViewMut<Player>,
+            )>();
+
+        replace_components(&entities, &mut monsters, &snapshot.monsters);
+        replace_components(&entities, &mut fearfuls, &snapshot.fearfuls);
+        replace_components(&entities, &mut poisons, &snapshot.poisons);
+        replace_components(&entities, &mut confusions, &snapshot.confusions);
+        replace_components(&entities, &mut asleeps, &snapshot.asleeps);
+        for &(id, warned) in snapshot.low_hp_warned.iter() {
+            if let Ok(player) = (&mut players).try_get(id) {
+                player.low_hp_warned = warned;
+            }
+        }
+    }
+
+    world.borrow::<UniqueViewMut<Map>>().seen = snapshot.seen.clone();
+    world.borrow::<UniqueViewMut<TurnCount>>().0 = snapshot.turn_count;
+    world
+        .borrow::<UniqueViewMut<Messages>>()
+        .replace(snapshot.messages.clone());
+
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{gamesym::GameSym, player::PlayerId, spawn, statehash};
+
+    #[test]
+    fn restoring_a_snapshot_takes_back_everything_a_turn_changes() {
+        let world = spawn::test_world(20, 20, (5, 5));
+        let player_id = world.borrow::<UniqueView<PlayerId>>().0;
+        let goblin_id = spawn::spawn_monster_kind(&world, GameSym::Goblin, (8, 5)).unwrap();
+
+        {
+            let entities = world.borrow::<EntitiesView>();
+            entities.add_component(
+                &mut world.borrow::<ViewMut<Poison>>(),
+                Poison {
+                    damage: 1,
+                    turns: 1,
+                },
+                player_id,
+            );
+            entities.add_component(
+                &mut world.borrow::<ViewMut<Asleep>>(),
+                Asleep {
+                    sleepiness: 10,
+                    last_hp: 5,
+                },
+                goblin_id,
+            );
+        }
+
+        let snapshot = take_snapshot(&world);
+        let hash = statehash::world_hash(&world);
+
+        // Change everything that a turn might.
+        {
+            let entities = world.borrow::<EntitiesView>();
+            let (mut map, blocks_tile, mut coords) =
+                world.borrow::<(UniqueViewMut<Map>, View<BlocksTile>, ViewMut<Coord>)>();
+
+            map.move_entity(player_id, (5, 5), (6, 5), blocks_tile.contains(player_id));
+            (&mut coords).get(player_id).0 = (6, 5).into();
+            map.seen.set_bit(6, 5, true);
+
+            (&mut world.borrow::<ViewMut<CombatStats>>())
+                .get(player_id)
+                .hp -= 1;
+            world.borrow::<ViewMut<Poison>>().remove(player_id);
+            world.borrow::<ViewMut<Asleep>>().remove(goblin_id);
+            entities.add_component(
+                &mut world.borrow::<ViewMut<Confusion>>(),
+                Confusion { turns: 3 },
+                goblin_id,
+            );
+
+            let mut monsters = world.borrow::<ViewMut<Monster>>();
+            let monster = (&mut monsters).get(goblin_id);
+            monster.last_seen_player = Some((6, 5));
+            monster.pursuit_turns = 5;
+
+            (&mut world.borrow::<ViewMut<Fearful>>())
+                .get(goblin_id)
+                .fleeing = true;
+            (&mut world.borrow::<ViewMut<Player>>())
+                .get(player_id)
+                .low_hp_warned = true;
+            world.borrow::<UniqueViewMut<TurnCount>>().0 += 1;
+        }
+        assert_ne!(statehash::world_hash(&world), hash);
+
+        assert!(restore_snapshot(&world, &snapshot));
+
+        assert_eq!(statehash::world_hash(&world), hash);
+        assert!(world
+            .borrow::<UniqueView<Map>>()
+            .iter_entities_at(5, 5)
+            .eq([player_id]));
+        assert!(!world.borrow::<UniqueView<Map>>().seen.get_bit(6, 5));
+        assert_eq!(world.borrow::<View<Poison>>().get(player_id).turns, 1);
+        assert_eq!(world.borrow::<View<Asleep>>().get(goblin_id).sleepiness, 10);
+        assert!(!world.borrow::<View<Confusion>>().contains(goblin_id));
+
+        let monsters = world.borrow::<View<Monster>>();
+        assert!(monsters.get(goblin_id).last_seen_player.is_none());
+        assert_eq!(monsters.get(goblin_id).pursuit_turns, 0);
+        assert!(!world.borrow::<View<Fearful>>().get(goblin_id).fleeing);
+        assert!(!world.borrow::<View<Player>>().get(player_id).low_hp_warned);
+    }
+}
@@ -1,27 +1,125 @@
-use shipyard::{IntoIter, Shiperator, UniqueViewMut, View, ViewMut};
+use serde::{Deserialize, Serialize};
+use shipyard::{IntoIter, Shiperator, UniqueView, UniqueViewMut, View, ViewMut};
 
 use crate::{
     components::{Coord, FieldOfView, Player},
     map::{Map, Tile},
+    ui::{Options, VisionShape},
 };
-use ruggrogue::FovShape;
+use ruggrogue::{FovShape, ViewableField};
+
+/// Returns true if an offset from the center of a field of view lies within the given shape.
+fn in_vision_shape(shape: VisionShape, dx: i32, dy: i32, range: i32) -> bool {
+    match shape {
+        // Matches the extended circle of FovShape::CirclePlus.
+        VisionShape::Circle => dx * dx + dy * dy <= range * (range + 1),
+        VisionShape::Square => true,
+        VisionShape::Diamond => dx.abs() + dy.abs() <= range,
+    }
+}
+
+/// How distance is measured when deciding what lies within range of the player or the area of
+/// effect of a target.  Items are aimed with [DistanceMetric::Euclidean] unless their [Ranged]
+/// component calls for something else, whatever the shape of the player's vision.
+#[derive(Clone, Copy, Default, Deserialize, Serialize)]
+pub enum DistanceMetric {
+    /// Circles, with half a tile added to the range to round out the bumps at the cardinal edges.
+    #[default]
+    Euclidean,
+    /// Squares, where diagonal steps count the same as orthogonal steps.
+    Chebyshev,
+    /// Diamonds, where diagonal steps count as two orthogonal steps.
+    Manhattan,
+}
+
+impl DistanceMetric {
+    /// Returns true if the two positions are within `range` of each other under this metric.
+    pub fn within(self, (x1, y1): (i32, i32), (x2, y2): (i32, i32), range: i32) -> bool {
+        let dx = (x2 - x1).abs();
+        let dy = (y2 - y1).abs();
+
+        match self {
+            DistanceMetric::Euclidean => dx * dx + dy * dy <= range * (range + 1),
+            DistanceMetric::Chebyshev => dx.max(dy) <= range,
+            DistanceMetric::Manhattan => dx + dy <= range,
+        }
+    }
+}
+
+/// Positions along the Bresenham line from `from` to `to`, including both ends.
+pub fn line_between((x1, y1): (i32, i32), (x2, y2): (i32, i32)) -> Vec<(i32, i32)> {
+    let dx = (x2 - x1).abs();
+    let dy = -(y2 - y1).abs();
+    let sx = if x1 < x2 { 1 } else { -1 };
+    let sy = if y1 < y2 { 1 } else { -1 };
+    let mut err = dx + dy;
+    let (mut x, mut y) = (x1, y1);
+    let mut line = vec![(x, y)];
+
+    while (x, y) != (x2, y2) {
+        let e2 = 2 * err;
+        if e2 >= dy {
+            err += dy;
+            x += sx;
+        }
+        if e2 <= dx {
+            err += dx;
+            y += sy;
+        }
+        line.push((x, y));
+    }
+
+    line
+}
+
+/// Returns true if no opaque map tile lies on the line between `from` and `to`, not counting the
+/// ends themselves.
+pub fn clear_line(map: &Map, from: (i32, i32), to: (i32, i32)) -> bool {
+    let line = line_between(from, to);
+
+    line.iter()
+        .skip(1)
+        .take(line.len().saturating_sub(2))
+        .all(|&(x, y)| !map.is_opaque(x, y))
+}
 
 pub fn recalculate_fields_of_view(
     mut map: UniqueViewMut<Map>,
+    options: UniqueView<Options>,
     coords: View<Coord>,
     mut fovs: ViewMut<FieldOfView>,
     players: View<Player>,
 ) {
-    for (id, (coord, mut fov)) in (&coords, &mut fovs).iter().with_id() {
+    for (id, (coord, fov)) in (&coords, &mut fovs).iter().with_id() {
+        // The player's field of view is resized to match the range chosen in the options, which
+        // may have changed since it was made or saved.
+        if players.contains(id) && fov.range != options.fov_range as i32 {
+            *fov = FieldOfView::new(options.fov_range as i32);
+        }
+
         if fov.dirty {
             fov.center = coord.0.into();
             fov.tiles.zero_out_bits();
 
+            // Only the player's field of view takes on the shape chosen in the options.  Shapes
+            // other than the usual circle are cut out of a square field of view.
+            let shape = if players.contains(id) {
+                options.vision_shape
+            } else {
+                VisionShape::Circle
+            };
+            let fov_shape = match shape {
+                VisionShape::Circle => FovShape::CirclePlus,
+                VisionShape::Square | VisionShape::Diamond => FovShape::Square,
+            };
+
             // Update field of view.
             for (x, y, symmetric) in
-                ruggrogue::field_of_view(&*map, coord.0.into(), fov.range, FovShape::CirclePlus)
+                ruggrogue::field_of_view(&*map, coord.0.into(), fov.range, fov_shape)
             {
-                if symmetric || matches!(map.get_tile(x, y), &Tile::Wall) {
+                if (symmetric || matches!(map.get_tile(x, y), &Tile::Wall))
+                    && in_vision_shape(shape, x - coord.0.x, y - coord.0.y, fov.range)
+                {
                     fov.set((x, y), true);
                 }
             }
@@ -35,3 +133,155 @@ pub fn recalculate_fields_of_view(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use shipyard::{EntitiesViewMut, EntityId, Get, World};
+
+    use super::*;
+
+    #[test]
+    fn distance_metrics_cover_their_shapes() {
+        let count = |metric: DistanceMetric| {
+            (-5..=5)
+                .flat_map(|y| (-5..=5).map(move |x| (x, y)))
+                .filter(|&pos| metric.within(pos, (0, 0), 3))
+                .count()
+        };
+
+        assert_eq!(count(DistanceMetric::Euclidean), 37);
+        assert_eq!(count(DistanceMetric::Chebyshev), 7 * 7);
+        assert_eq!(count(DistanceMetric::Manhattan), 25);
+        assert!(DistanceMetric::Euclidean.within((0, 0), (3, 1), 3));
+        assert!(!DistanceMetric::Euclidean.within((0, 0), (3, 2), 3));
+        assert!(DistanceMetric::Chebyshev.within((0, 0), (3, 3), 3));
+        assert!(!DistanceMetric::Manhattan.within((0, 0), (2, 2), 3));
+    }
+
+    #[test]
+    fn walls_block_clear_lines() {
+        let mut map = Map::new(20, 20);
+
+        map.set_tile(10, 5, Tile::Wall);
+
+        assert!(!clear_line(&map, (5, 5), (15, 5)));
+        assert!(!clear_line(&map, (15, 5), (5, 5)));
+        assert!(clear_line(&map, (5, 6), (15, 6)));
+        assert!(clear_line(&map, (5, 5), (15, 15)));
+        // The ends of a line don't block it themselves.
+        assert!(clear_line(&map, (10, 5), (15, 5)));
+        assert!(clear_line(&map, (5, 5), (10, 5)));
+    }
+
+    /// A world with the given map and options and a player at (20, 20).
+    fn player_world(map: Map, options: Options) -> (World, EntityId) {
+        let world = World::new();
+
+        world.add_unique(map);
+        world.add_unique(options);
+
+        let player_id = world.run(
+            |mut entities: EntitiesViewMut,
+             mut coords: ViewMut<Coord>,
+             mut fovs: ViewMut<FieldOfView>,
+             mut players: ViewMut<Player>| {
+                entities.add_entity(
+                    (&mut coords, &mut fovs, &mut players),
+                    (
+                        Coord((20, 20).into()),
+                        FieldOfView::new(8),
+                        Player {
+                            auto_run: None,
+                            low_hp_warned: false,
+                            damage_previewed: None,
+                        },
+                    ),
+                )
+            },
+        );
+
+        (world, player_id)
+    }
+
+    #[test]
+    fn player_fov_follows_range_option() {
+        let (world, player_id) = player_world(
+            Map::new(40, 40),
+            Options {
+                fov_range: 5,
+                vision_shape: VisionShape::Square,
+                ..Options::default()
+            },
+        );
+
+        world.run(recalculate_fields_of_view);
+
+        let fovs = world.borrow::<View<FieldOfView>>();
+        let fov = fovs.get(player_id);
+        let visible = (0..40)
+            .flat_map(|y| (0..40).map(move |x| (x, y)))
+            .filter(|&pos| fov.get(pos))
+            .count();
+
+        assert_eq!(fov.range, 5);
+        assert_eq!(visible, 11 * 11);
+    }
+
+    #[test]
+    fn square_vision_sees_the_corners_that_circles_cut() {
+        let sees = |vision_shape, pos| {
+            let (world, player_id) = player_world(
+                Map::new(40, 40),
+                Options {
+                    fov_range: 5,
+                    vision_shape,
+                    ..Options::default()
+                },
+            );
+
+            world.run(recalculate_fields_of_view);
+            world.run(|fovs: View<FieldOfView>| fovs.get(player_id).get(pos))
+        };
+
+        for pos in [(25, 25), (15, 15), (25, 23)] {
+            assert!(sees(VisionShape::Square, pos));
+            assert!(!sees(VisionShape::Circle, pos));
+        }
+        for pos in [(25, 20), (25, 22), (22, 24)] {
+            assert!(sees(VisionShape::Square, pos));
+            assert!(sees(VisionShape::Circle, pos));
+        }
+        assert!(!sees(VisionShape::Square, (26, 20)));
+    }
+
+    #[test]
+    fn fov_iterates_in_a_stable_order() {
+        let mut map = Map::new(40, 40);
+        for y in 17..=23 {
+            map.set_tile(23, y, Tile::Wall);
+        }
+        map.set_tile(18, 19, Tile::Wall);
+
+        let (world, player_id) = player_world(map, Options::default());
+        let visible_in_order = || {
+            world.run(recalculate_fields_of_view);
+            world
+                .borrow::<View<FieldOfView>>()
+                .get(player_id)
+                .iter()
+                .collect::<Vec<_>>()
+        };
+
+        let first = visible_in_order();
+        (&mut world.borrow::<ViewMut<FieldOfView>>())
+            .get(player_id)
+            .dirty = true;
+        let second = visible_in_order();
+
+        let mut row_major = first.clone();
+        row_major.sort_by_key(|&(x, y)| (y, x));
+        assert!(!first.is_empty());
+        assert_eq!(first, second);
+        assert_eq!(first, row_major);
+    }
+}